@@ -0,0 +1,6 @@
+def greet(name: str) -> None:
+    print(f"Hello, {name}!")
+
+
+if __name__ == "__main__":
+    greet("world")
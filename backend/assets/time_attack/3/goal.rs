@@ -0,0 +1 @@
+let names = vec!["alice", "bob"];
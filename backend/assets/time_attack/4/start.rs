@@ -0,0 +1,3 @@
+struct Point {
+    x: f64,
+}
@@ -0,0 +1,3 @@
+if count == 0 {
+    println!("empty");
+}
@@ -1,23 +1,99 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Result};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use common::{constants::MAX_LOBBY_SIZE, BackendMessage, JoinMode, LobbyListItem};
+use common::{
+    BackendMessage, CloseReason, JoinMode, LobbyFilter, LobbyListItem, LobbyStatus,
+    QuickplayOutcome, SpectatorCount,
+};
 
-use self::message::AppMessage;
-use crate::lobby::Lobby;
+use self::{clients::Clients, message::AppMessage};
+use crate::{
+    challenge::{self, Challenge},
+    chat_filter::{self, ChatFilter},
+    config::Config,
+    constants::{ADMIN_CLOSE_REASON, DEFAULT_CHALLENGE_ID},
+    lobby::Lobby,
+    metrics::ConnectionHistory,
+    player::Player,
+    record::Records,
+    rotation::ChallengeRotation,
+};
 
+pub(crate) mod clients;
 pub(crate) mod message;
 
+/// The player a spectating client watches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpectatorTarget {
+    pub lobby_id: Uuid,
+    pub player_id: Uuid,
+}
+
+/// The watch position of a spectating client whose connection was lost.
+#[derive(Clone, Copy, Debug)]
+pub struct HeldSpectator {
+    pub target: SpectatorTarget,
+    pub disconnected_at: Instant,
+}
+
 #[derive(Debug)]
 pub struct App {
     /// All non-playing clients.
-    pub clients: BTreeMap<Uuid, UnboundedSender<BackendMessage>>,
+    pub clients: Clients,
     /// All active lobbies.
     pub lobbies: BTreeMap<Uuid, Lobby>,
+    /// Lobby each player is currently tracked in.
+    pub player_lobbies: BTreeMap<Uuid, Uuid>,
+    /// Lobby each client currently plays in by client ID. Unlike player IDs,
+    /// client IDs are shared by every player connection of a client.
+    pub client_lobbies: BTreeMap<Uuid, Uuid>,
+    /// The player each spectating client watches.
+    pub spectators: BTreeMap<Uuid, SpectatorTarget>,
+    /// Reconnect token of every client that spectated.
+    pub spectator_tokens: BTreeMap<Uuid, Uuid>,
+    /// Watch positions of disconnected spectators by reconnect token. They
+    /// keep their spectator slot for the reconnect grace period.
+    pub held_spectators: BTreeMap<Uuid, HeldSpectator>,
+    /// All challenges lobbies can race by ID.
+    pub challenges: BTreeMap<String, Challenge>,
+    /// Picks the challenges of lobbies created by quickplay.
+    pub quickplay_rotation: ChallengeRotation,
+    /// How many lobbies with a free seat are kept ready for quickplay.
+    pub prewarm_target: usize,
+    /// Fastest solve times per challenge across all lobbies.
+    pub records: Records,
+    /// Rolling history of connection counts for operators.
+    pub connection_history: ConnectionHistory,
+    /// Filters chat messages before they are broadcast, if configured.
+    pub chat_filter: Option<Box<dyn ChatFilter>>,
+    /// How long the seat of a disconnected player is held.
+    pub reconnect_grace: Duration,
+    /// How long ready players wait for the others before their ready state is
+    /// reset.
+    pub ready_timeout: Duration,
+    /// How long races last if their challenge has no time limit of its own.
+    pub round_time_limit: Duration,
+    /// How long the other players of new lobbies can still finish a race
+    /// after the first one did.
+    pub finish_grace: Duration,
+    /// Race solves faster than this are flagged and not recorded.
+    pub min_solve_time: Duration,
+    /// How long empty lobbies are kept before they are removed.
+    pub empty_lobby_grace: Duration,
+    /// Seats of new lobbies.
+    pub max_players: usize,
+    /// Spectator cap of new lobbies.
+    pub max_spectators: usize,
+    /// Directory the challenge catalog is loaded from.
+    pub challenge_directory: PathBuf,
 
     pub tx: UnboundedSender<AppMessage>,
     pub rx: UnboundedReceiver<AppMessage>,
@@ -26,54 +102,176 @@ pub struct App {
 impl App {
     /// # Create a new app
     ///
-    /// Creates a new app with no clients and lobbies. Holds the passed in
-    /// communication channel.
-    pub fn new(tx: UnboundedSender<AppMessage>, rx: UnboundedReceiver<AppMessage>) -> Self {
-        Self {
-            clients: BTreeMap::default(),
+    /// Creates a new app with no clients and lobbies and loads the challenge
+    /// catalog and chat filter of the given config. Holds the passed in
+    /// communication channel. Fails if the configured chat filter cannot be
+    /// loaded, instead of running without one.
+    pub fn new(
+        tx: UnboundedSender<AppMessage>,
+        rx: UnboundedReceiver<AppMessage>,
+        config: &Config,
+    ) -> Result<Self> {
+        let mut app = Self {
+            clients: Clients::new(tx.clone()),
             lobbies: BTreeMap::default(),
+            player_lobbies: BTreeMap::default(),
+            client_lobbies: BTreeMap::default(),
+            spectators: BTreeMap::default(),
+            spectator_tokens: BTreeMap::default(),
+            held_spectators: BTreeMap::default(),
+            challenges: BTreeMap::default(),
+            quickplay_rotation: ChallengeRotation::new(
+                config.quickplay_rotation.clone(),
+                config.quickplay_difficulty,
+            ),
+            prewarm_target: config.prewarmed_lobbies,
+            records: Records::default(),
+            connection_history: ConnectionHistory::default(),
+            chat_filter: chat_filter::load(config.chat_filter.as_deref())?,
+            reconnect_grace: config.reconnect_grace,
+            ready_timeout: config.ready_timeout,
+            round_time_limit: config.round_time_limit,
+            finish_grace: config.finish_grace,
+            min_solve_time: config.min_solve_time,
+            empty_lobby_grace: config.empty_lobby_grace,
+            max_players: config.max_players,
+            max_spectators: config.max_spectators,
+            challenge_directory: config.challenge_directory.clone(),
             tx,
             rx,
+        };
+        app.reload_challenges();
+        Ok(app)
+    }
+
+    /// # Reload challenges
+    ///
+    /// Loads the challenge catalog from the challenge directory and swaps it in
+    /// for future lobbies. Running lobbies keep their current challenge. The
+    /// current catalog is kept if the directory can not be read.
+    pub fn reload_challenges(&mut self) {
+        let directory = &self.challenge_directory;
+        match challenge::load_catalog(directory, &self.challenges) {
+            Ok(challenges) => {
+                challenge::log_catalog_changes(&self.challenges, &challenges);
+                self.challenges = challenges;
+            }
+            Err(e) => error!(
+                "Error reading challenge directory {}: {e}",
+                directory.display()
+            ),
         }
     }
 
     /// # Get lobby ID
     ///
     /// Returns the ID of an available lobby or creates a new one depending on
-    /// the provided `JoinMode`.
-    pub fn get_lobby_id(&mut self, join_mode: JoinMode) -> Result<Uuid> {
+    /// the provided `JoinMode`. For quickplay, also returns whether an
+    /// existing lobby was found.
+    pub fn get_lobby_id(
+        &mut self,
+        join_mode: JoinMode,
+    ) -> Result<(Uuid, Option<QuickplayOutcome>)> {
         match join_mode {
-            // Find a non-full lobby. If there is none, create a new one.
-            JoinMode::Quickplay => {
-                if let Some(lobby) = self
-                    .lobbies
-                    .values_mut()
-                    .filter(|lobby| lobby.players.len() < MAX_LOBBY_SIZE)
-                    .max_by_key(|lobby| lobby.players.len())
-                {
-                    Ok(lobby.id)
-                } else {
-                    self.create_new_lobby()
-                }
-            }
+            // Find a non-full lobby which is not about to start. If there is
+            // none, create a new one.
+            JoinMode::Quickplay => match self.find_quickplay_lobby() {
+                Some(lobby_id) => Ok((lobby_id, Some(QuickplayOutcome::Joined))),
+                None => Ok((
+                    self.create_quickplay_lobby()?,
+                    Some(QuickplayOutcome::Created),
+                )),
+            },
             // Try to join the lobby with the provided ID.
             JoinMode::Join { lobby_id } => {
                 let Some(lobby) = self.lobbies.get_mut(&lobby_id) else {
                     return Err(anyhow!("Lobby with ID {} was not found in app state. Could not get lobby information.", lobby_id));
                 };
-                Ok(lobby.id)
+                Ok((lobby.id, None))
             }
             // Create a new lobby.
-            JoinMode::Create => self.create_new_lobby(),
+            JoinMode::Create => Ok((self.create_new_lobby()?, None)),
+        }
+    }
+
+    /// # Find quickplay lobby
+    ///
+    /// Returns the fullest lobby that has a free seat and is not about to
+    /// start, if there is any.
+    pub fn find_quickplay_lobby(&self) -> Option<Uuid> {
+        self.lobbies
+            .values()
+            .filter(|lobby| accepts_quickplay(lobby))
+            .max_by_key(|lobby| lobby.players.len())
+            .map(|lobby| lobby.id)
+    }
+
+    /// # Quickplay ready count
+    ///
+    /// Returns the amount of lobbies quickplay could put a player into right
+    /// now.
+    pub fn quickplay_ready_count(&self) -> usize {
+        self.lobbies
+            .values()
+            .filter(|lobby| accepts_quickplay(lobby))
+            .count()
+    }
+
+    /// # Pre-warm lobbies
+    ///
+    /// Creates quickplay lobbies until `prewarm_target` of them are ready.
+    /// Lobbies above the target are left to the regular removal of empty
+    /// lobbies.
+    pub fn prewarm_lobbies(&mut self) {
+        let missing = self
+            .prewarm_target
+            .saturating_sub(self.quickplay_ready_count());
+        if missing == 0 {
+            return;
+        }
+        debug!("Pre-warming {} quickplay lobby/lobbies.", missing);
+        for _ in 0..missing {
+            if let Err(e) = self.create_quickplay_lobby() {
+                error!("Error pre-warming quickplay lobby: {e}");
+                return;
+            }
         }
     }
 
     /// # Create new lobby
     ///
-    /// Creates a new lobby and inserts it into the application state.
+    /// Creates a new lobby with the default challenge and inserts it into the
+    /// application state.
     pub fn create_new_lobby(&mut self) -> Result<Uuid> {
-        // Create the new lobby.
-        let lobby = Lobby::default();
+        let Some(challenge) = self
+            .challenges
+            .get(DEFAULT_CHALLENGE_ID)
+            .or_else(|| self.challenges.values().next())
+        else {
+            return Err(anyhow!("There are no challenges to create a lobby with."));
+        };
+        self.insert_lobby(challenge.clone())
+    }
+
+    /// # Create quickplay lobby
+    ///
+    /// Creates a new lobby with the next challenge of the quickplay rotation
+    /// and inserts it into the application state.
+    pub fn create_quickplay_lobby(&mut self) -> Result<Uuid> {
+        let Some(challenge) = self.quickplay_rotation.next(&self.challenges) else {
+            return Err(anyhow!("There are no challenges to create a lobby with."));
+        };
+        self.insert_lobby(challenge.clone())
+    }
+
+    fn insert_lobby(&mut self, challenge: Challenge) -> Result<Uuid> {
+        let lobby = Lobby::new(
+            challenge,
+            self.finish_grace,
+            self.empty_lobby_grace,
+            self.max_players,
+            self.max_spectators,
+        );
         self.lobbies.insert(lobby.id, lobby.clone());
         self.tx.send(AppMessage::AddLobby { lobby_id: lobby.id })?;
 
@@ -88,61 +286,531 @@ impl App {
 
     /// # Get current lobbies
     ///
-    /// Fetches all active lobbies and returns them inside a `BTreeMap`. Lobbies
-    /// are converted into the client compatible `common::Lobby` type.
-    pub fn get_current_lobbies(&self) -> BTreeMap<Uuid, LobbyListItem> {
+    /// Fetches all active lobbies matching the filter and returns them inside a
+    /// `BTreeMap`. Lobbies are converted into the client compatible
+    /// `common::LobbyListItem` type.
+    pub fn get_current_lobbies(&self, filter: &LobbyFilter) -> BTreeMap<Uuid, LobbyListItem> {
         let mut lobbies = BTreeMap::new();
         for lobby in self.lobbies.values() {
-            lobbies.insert(lobby.id, lobby.to_list_item());
+            let list_item = lobby.to_list_item();
+            if filter.matches(&list_item) {
+                lobbies.insert(lobby.id, list_item);
+            }
         }
         lobbies
     }
 
+    /// # Spectate player
+    ///
+    /// Makes the client watch the given player, replacing any previous target.
+    /// Tells the client if the player is not part of the lobby. Hands out a
+    /// reconnect token the first time the client spectates.
+    pub fn spectate_player(&mut self, client_id: Uuid, target: SpectatorTarget) -> Result<()> {
+        if !self.clients.contains(&client_id) {
+            return Err(anyhow!("Client with ID {} was not found.", client_id));
+        }
+        let is_playing = self
+            .lobbies
+            .get(&target.lobby_id)
+            .is_some_and(|lobby| lobby.players.contains_key(&target.player_id));
+        if !is_playing {
+            self.spectators.remove(&client_id);
+            self.clients.send(
+                client_id,
+                BackendMessage::SpectateTargetLeft {
+                    player_id: target.player_id,
+                },
+            )?;
+            return Ok(());
+        }
+        let (max_spectators, spectators_allowed) = self
+            .lobbies
+            .get(&target.lobby_id)
+            .map_or((0, false), |lobby| {
+                (lobby.max_spectators, lobby.spectators_allowed)
+            });
+        if !spectators_allowed {
+            info!(
+                "Client {} tried to spectate lobby {} without spectators.",
+                client_id, target.lobby_id
+            );
+            self.clients
+                .send(client_id, BackendMessage::SpectatorsDisabled)?;
+            return Ok(());
+        }
+        let is_watching_lobby = self
+            .spectators
+            .get(&client_id)
+            .is_some_and(|spectated| spectated.lobby_id == target.lobby_id);
+        if !is_watching_lobby && self.spectator_count(target.lobby_id) >= max_spectators {
+            info!(
+                "Client {} tried to spectate full lobby {}.",
+                client_id, target.lobby_id
+            );
+            self.clients
+                .send(client_id, BackendMessage::SpectatorsFull)?;
+            return Ok(());
+        }
+        info!(
+            "Client {} spectates player {} in lobby {}.",
+            client_id, target.player_id, target.lobby_id
+        );
+        let previous = self.spectators.insert(client_id, target);
+        if let Some(previous) = previous.filter(|previous| previous.lobby_id != target.lobby_id) {
+            self.send_spectator_count(previous.lobby_id);
+        }
+        self.send_spectator_count(target.lobby_id);
+        if let Entry::Vacant(entry) = self.spectator_tokens.entry(client_id) {
+            let token = *entry.insert(Uuid::new_v4());
+            self.clients
+                .send(client_id, BackendMessage::ProvideSpectatorToken { token })?;
+        }
+        Ok(())
+    }
+
+    /// # Hold spectator
+    ///
+    /// Keeps the watch position of a client whose connection was lost. Returns
+    /// the reconnect token if the position is held, which is not the case for
+    /// clients that were not spectating.
+    pub fn hold_spectator(&mut self, client_id: Uuid) -> Option<Uuid> {
+        let token = self.spectator_tokens.remove(&client_id)?;
+        let target = self.spectators.remove(&client_id)?;
+        info!(
+            "Holding spectator slot of client {} watching player {} in lobby {}.",
+            client_id, target.player_id, target.lobby_id
+        );
+        self.held_spectators.insert(
+            token,
+            HeldSpectator {
+                target,
+                disconnected_at: Instant::now(),
+            },
+        );
+        Some(token)
+    }
+
+    /// # Resume spectator
+    ///
+    /// Lets a new client connection take over the watch position held for the
+    /// given token. The target is checked again, as it might have left in the
+    /// meantime. Returns whether the client watches again.
+    pub fn resume_spectator(&mut self, client_id: Uuid, token: Uuid) -> Result<bool> {
+        let Some(held) = self.held_spectators.remove(&token) else {
+            return Ok(false);
+        };
+        info!(
+            "Client {} resumes watching player {} in lobby {}.",
+            client_id, held.target.player_id, held.target.lobby_id
+        );
+        self.spectator_tokens.insert(client_id, token);
+        self.spectate_player(client_id, held.target)?;
+        Ok(self.spectators.contains_key(&client_id))
+    }
+
+    /// # Reconnect client
+    ///
+    /// Adds a client connection presenting a reconnect token. If the client of
+    /// the token is still connected, e.g., through a half-open socket, the
+    /// new connection takes over its client ID and watch position, so that
+    /// the client is not counted twice. Otherwise a held watch position is
+    /// resumed under the given client ID. Returns the client ID the
+    /// connection continues as and whether it watches a player.
+    pub fn reconnect_client(
+        &mut self,
+        client_id: Uuid,
+        token: Uuid,
+        client_tx: UnboundedSender<BackendMessage>,
+    ) -> (Uuid, bool) {
+        let connected_id = self
+            .spectator_tokens
+            .iter()
+            .find(|(id, spectator_token)| **spectator_token == token && self.clients.contains(id))
+            .map(|(id, _)| *id);
+        if let Some(connected_id) = connected_id {
+            info!(
+                "Client {} reconnected through a new connection.",
+                connected_id
+            );
+            self.add_client(connected_id, client_tx);
+            return (connected_id, self.spectators.contains_key(&connected_id));
+        }
+        self.add_client(client_id, client_tx);
+        let resumed = self.resume_spectator(client_id, token).unwrap_or_else(|e| {
+            error!("Error resuming spectator: {e}");
+            false
+        });
+        (client_id, resumed)
+    }
+
+    /// # Spectator count
+    ///
+    /// Returns the amount of clients watching players of the given lobby.
+    pub fn spectator_count(&self, lobby_id: Uuid) -> usize {
+        self.spectators
+            .values()
+            .chain(self.held_spectators.values().map(|held| &held.target))
+            .filter(|spectated| spectated.lobby_id == lobby_id)
+            .count()
+    }
+
+    /// # Send spectator count
+    ///
+    /// Tells all clients how many clients watch the given lobby and how many
+    /// are allowed to.
+    pub fn send_spectator_count(&mut self, lobby_id: Uuid) {
+        let Some(lobby) = self.lobbies.get(&lobby_id) else {
+            return;
+        };
+        let count = SpectatorCount {
+            lobby_id,
+            spectators: self.spectator_count(lobby_id),
+            max_spectators: lobby.max_spectators,
+            spectators_allowed: lobby.spectators_allowed,
+        };
+        self.clients
+            .broadcast(BackendMessage::SpectatorCountChanged(count));
+    }
+
+    /// # Release spectators
+    ///
+    /// Tells all clients watching the given player that he left, so that they
+    /// can pick someone else.
+    pub fn release_spectators(&mut self, target: SpectatorTarget) {
+        let clients = &mut self.clients;
+        self.spectators.retain(|client_id, spectated| {
+            if *spectated != target {
+                return true;
+            }
+            let _ = clients.send(
+                *client_id,
+                BackendMessage::SpectateTargetLeft {
+                    player_id: target.player_id,
+                },
+            );
+            false
+        });
+        self.held_spectators.retain(|_, held| held.target != target);
+        self.send_spectator_count(target.lobby_id);
+    }
+
+    /// # Dismiss spectators
+    ///
+    /// Stops all clients watching players of the given lobby, e.g., after the
+    /// owner forbade spectators.
+    pub fn dismiss_spectators(&mut self, lobby_id: Uuid) {
+        let clients = &mut self.clients;
+        self.spectators.retain(|client_id, spectated| {
+            if spectated.lobby_id != lobby_id {
+                return true;
+            }
+            let _ = clients.send(*client_id, BackendMessage::SpectatorsDisabled);
+            false
+        });
+        self.held_spectators
+            .retain(|_, held| held.target.lobby_id != lobby_id);
+    }
+
+    /// # Add client
+    ///
+    /// Adds a non-playing client. A second connection with a known client ID
+    /// replaces the first one, which is closed as superseded, and leaves the
+    /// connection counts untouched.
+    pub fn add_client(&mut self, client_id: Uuid, client_tx: UnboundedSender<BackendMessage>) {
+        if let Some(replaced_tx) = self.clients.insert(client_id, client_tx) {
+            // Closing the replaced connection ends its tasks. Its removal is
+            // ignored, as the client is connected through the new one.
+            let _ = replaced_tx.send(BackendMessage::CloseConnection {
+                reason: Some(CloseReason::Superseded),
+            });
+            warn!(
+                "Replaced the connection of client with ID {}. Client count is {}.",
+                client_id,
+                self.clients.len()
+            );
+            return;
+        }
+        let _ = self.tx.send(AppMessage::SendConnectionCounts);
+        info!(
+            "Added client with ID {}. Client count is {}.",
+            client_id,
+            self.clients.len()
+        );
+    }
+
+    /// # Track player
+    ///
+    /// Remembers the lobby of a player that joined it, also by the client the
+    /// player joined from.
+    pub fn track_player(&mut self, player: &Player, lobby_id: Uuid) {
+        self.player_lobbies.insert(player.id, lobby_id);
+        if let Some(client_id) = player.client_id {
+            self.client_lobbies.insert(client_id, lobby_id);
+        }
+    }
+
+    /// # Untrack player
+    ///
+    /// Forgets the lobby of a player once the player left it. Entries of
+    /// another lobby are kept, e.g., of a client that already joined a new
+    /// lobby.
+    pub fn untrack_player(&mut self, player: &Player, lobby_id: Uuid) {
+        if self.player_lobbies.get(&player.id) == Some(&lobby_id) {
+            self.player_lobbies.remove(&player.id);
+        }
+        if let Some(client_id) = player.client_id {
+            if self.client_lobbies.get(&client_id) == Some(&lobby_id) {
+                self.client_lobbies.remove(&client_id);
+            }
+        }
+    }
+
+    /// # Connection counts
+    ///
+    /// Returns the amount of connected clients and players.
+    pub fn connection_counts(&self) -> (usize, usize) {
+        let clients = self.clients.len();
+        let players = self.lobbies.values().map(|lobby| lobby.players.len()).sum();
+        (clients, players)
+    }
+
     /// # Remove lobby
     ///
-    /// Removes a lobby if it exists and it is empty. All connected clients are
-    /// informed about the removed lobby.
+    /// Removes a lobby if it exists and stayed empty for the whole grace
+    /// period. All connected clients are informed about the removed lobby.
     pub fn remove_lobby(&mut self, lobby_id: Uuid) -> Result<()> {
         let Some(lobby) = self.lobbies.get(&lobby_id) else {
             let error_message = format!("Lobby with ID {} was not found.", lobby_id);
             error!("{}", error_message);
             return Err(anyhow!(error_message));
         };
-        if lobby.players.is_empty() {
-            if let Some(lobby) = self.lobbies.remove(&lobby_id) {
-                info!(
-                    "Removed lobby {} with player count {}. Lobby count is {}.",
-                    lobby.name,
-                    lobby.players.len(),
-                    self.lobbies.len(),
-                );
-                for client in self.clients.values() {
-                    client.send(BackendMessage::RemoveLobby(lobby_id))?;
-                }
-            }
-        } else {
-            error!(
-                "Can not remove non-empty lobby {} with {} players.",
+        // Someone rejoined within the grace period. If the lobby emptied
+        // again since, a later removal is already scheduled.
+        if !lobby.players.is_empty() {
+            info!(
+                "Keeping lobby {} with {} rejoined player(s).",
                 lobby.name,
                 lobby.players.len()
             );
+            return Ok(());
+        }
+        if lobby
+            .empty_since
+            .is_some_and(|empty_since| empty_since.elapsed() < lobby.empty_lobby_grace)
+        {
+            debug!(
+                "Lobby {} emptied again, postponing its removal.",
+                lobby.name
+            );
+            return Ok(());
+        }
+        // Removing the lobby would only make the pre-warming create a new one.
+        if accepts_quickplay(lobby) && self.quickplay_ready_count() <= self.prewarm_target {
+            info!("Keeping empty lobby {} ready for quickplay.", lobby.name);
+            return Ok(());
+        }
+        if let Some(lobby) = self.lobbies.remove(&lobby_id) {
+            info!(
+                "Removed lobby {}. Lobby count is {}.",
+                lobby.name,
+                self.lobbies.len(),
+            );
+            self.clients
+                .broadcast(BackendMessage::RemoveLobby(lobby_id));
         }
         Ok(())
     }
 
+    /// # Close lobby
+    ///
+    /// Sends every player of the lobby back to the lobby browser and removes
+    /// the lobby right away, regardless of who is still inside. Spectators of
+    /// its players are released.
+    pub fn close_lobby(&mut self, lobby_id: Uuid, origin: &str) -> Result<()> {
+        let Some(lobby) = self.lobbies.remove(&lobby_id) else {
+            return Err(anyhow!("Lobby with ID {} was not found.", lobby_id));
+        };
+        info!(
+            "Closing lobby {} with {} player(s) on {origin}.",
+            lobby.name,
+            lobby.players.len()
+        );
+        // Forget the lobby right away, so that the players and their clients
+        // can join another one before their connections are closed.
+        for player in lobby.players.values() {
+            self.untrack_player(player, lobby_id);
+            let _ = player.tx.send(BackendMessage::ReturnToBrowser {
+                reason: ADMIN_CLOSE_REASON.to_string(),
+            });
+            let _ = player.tx.send(BackendMessage::CloseConnection {
+                reason: Some(CloseReason::LobbyClosed),
+            });
+        }
+        for player_id in lobby.players.keys() {
+            self.release_spectators(SpectatorTarget {
+                lobby_id,
+                player_id: *player_id,
+            });
+        }
+        info!(
+            "Removed lobby {}. Lobby count is {}.",
+            lobby.name,
+            self.lobbies.len()
+        );
+        self.clients
+            .broadcast(BackendMessage::RemoveLobby(lobby_id));
+        Ok(())
+    }
+
     /// # Send lobby list information
     ///
     /// Sends the lobby list information to every connected client. This is used
     /// to keep clients up to date to available lobbies.
-    pub fn send_lobby_list_information(&self, lobby_id: Uuid) -> Result<()> {
+    pub fn send_lobby_list_information(&mut self, lobby_id: Uuid) -> Result<()> {
         let Some(lobby) = self.lobbies.get(&lobby_id) else {
             let error_message = format!("Lobby with ID {} was not found.", lobby_id);
             error!("{}", error_message);
             return Err(anyhow!(error_message));
         };
-        for client in self.clients.values() {
-            client.send(BackendMessage::AddLobby(lobby_id, lobby.to_list_item()))?;
-        }
+        self.clients
+            .broadcast(BackendMessage::AddLobby(lobby_id, lobby.to_list_item()));
         Ok(())
     }
 }
+
+/// # Accepts quickplay
+///
+/// Whether quickplay may put a player into the lobby, i.e., it has a free
+/// seat and is not about to start.
+fn accepts_quickplay(lobby: &Lobby) -> bool {
+    lobby.has_free_seat() && !matches!(lobby.status, LobbyStatus::AboutToStart(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    fn test_app() -> App {
+        let (tx, rx) = unbounded_channel();
+        App::new(tx, rx, &Config::default()).unwrap()
+    }
+
+    fn fill(app: &mut App, lobby_id: Uuid, players: usize) {
+        let lobby = app.lobbies.get_mut(&lobby_id).unwrap();
+        for _ in 0..players {
+            let (tx, _) = unbounded_channel();
+            assert!(lobby.add_player(Player::new(tx), &app.tx));
+        }
+    }
+
+    #[test]
+    fn quickplay_creates_a_lobby_if_none_accepts_players() {
+        let mut app = test_app();
+        let (created_id, outcome) = app.get_lobby_id(JoinMode::Quickplay).unwrap();
+        assert_eq!(outcome, Some(QuickplayOutcome::Created));
+        assert!(app.lobbies.contains_key(&created_id));
+
+        // Full lobbies and lobbies about to start do not take new players.
+        let max_players = app.lobbies[&created_id].max_players;
+        fill(&mut app, created_id, max_players);
+        let (starting_id, _) = app.get_lobby_id(JoinMode::Quickplay).unwrap();
+        app.lobbies.get_mut(&starting_id).unwrap().status = LobbyStatus::AboutToStart(Utc::now());
+        let (lobby_id, outcome) = app.get_lobby_id(JoinMode::Quickplay).unwrap();
+        assert_eq!(outcome, Some(QuickplayOutcome::Created));
+        assert!(![created_id, starting_id].contains(&lobby_id));
+        assert_eq!(app.lobbies.len(), 3);
+    }
+
+    #[test]
+    fn quickplay_joins_the_fullest_lobby_with_a_free_seat() {
+        let mut app = test_app();
+        let emptier_id = app.create_new_lobby().unwrap();
+        let fuller_id = app.create_new_lobby().unwrap();
+        fill(&mut app, emptier_id, 1);
+        fill(&mut app, fuller_id, 2);
+
+        let (lobby_id, outcome) = app.get_lobby_id(JoinMode::Quickplay).unwrap();
+        assert_eq!(outcome, Some(QuickplayOutcome::Joined));
+        assert_eq!(lobby_id, fuller_id);
+        assert_eq!(app.lobbies.len(), 2);
+    }
+
+    #[test]
+    fn replacing_a_client_connection_keeps_the_count() {
+        let mut app = test_app();
+        let client_id = Uuid::new_v4();
+        let (first_tx, mut first_rx) = unbounded_channel();
+        let (second_tx, _second_rx) = unbounded_channel();
+
+        app.add_client(client_id, first_tx.clone());
+        app.add_client(client_id, second_tx.clone());
+        assert_eq!(app.clients.len(), 1);
+        let count_updates = std::iter::from_fn(|| app.rx.try_recv().ok())
+            .filter(|msg| matches!(msg, AppMessage::SendConnectionCounts))
+            .count();
+        assert_eq!(count_updates, 1);
+        assert!(matches!(
+            first_rx.try_recv(),
+            Ok(BackendMessage::CloseConnection {
+                reason: Some(CloseReason::Superseded)
+            })
+        ));
+
+        // The replaced connection closing late keeps the new one.
+        assert!(app.clients.remove(&client_id, &first_tx).is_none());
+        assert!(app.clients.contains(&client_id));
+        assert!(app.clients.remove(&client_id, &second_tx).is_some());
+    }
+
+    #[test]
+    fn spectating_is_rejected_if_the_lobby_forbids_it() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let (player_tx, _player_rx) = unbounded_channel();
+        let player = Player::new(player_tx);
+        let lobby = app.lobbies.get_mut(&lobby_id).unwrap();
+        assert!(lobby.add_player(player.clone(), &app.tx));
+        lobby.spectators_allowed = false;
+        let client_id = Uuid::new_v4();
+        let (client_tx, mut client_rx) = unbounded_channel();
+        app.add_client(client_id, client_tx);
+
+        let target = SpectatorTarget {
+            lobby_id,
+            player_id: player.id,
+        };
+        app.spectate_player(client_id, target).unwrap();
+        assert!(!app.spectators.contains_key(&client_id));
+        assert!(matches!(
+            client_rx.try_recv(),
+            Ok(BackendMessage::SpectatorsDisabled)
+        ));
+
+        // Allowing spectators lets the client watch.
+        app.lobbies.get_mut(&lobby_id).unwrap().spectators_allowed = true;
+        app.spectate_player(client_id, target).unwrap();
+        assert!(app.spectators.contains_key(&client_id));
+    }
+
+    #[test]
+    fn closing_a_lobby_forgets_its_players() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let (player_tx, _player_rx) = unbounded_channel();
+        let mut player = Player::new(player_tx);
+        player.client_id = Some(Uuid::new_v4());
+        assert!(app
+            .lobbies
+            .get_mut(&lobby_id)
+            .unwrap()
+            .add_player(player.clone(), &app.tx));
+        app.track_player(&player, lobby_id);
+
+        app.close_lobby(lobby_id, "test").unwrap();
+        assert!(app.player_lobbies.is_empty());
+        assert!(app.client_lobbies.is_empty());
+    }
+}
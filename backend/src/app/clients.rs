@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+use uuid::Uuid;
+
+use common::BackendMessage;
+
+use super::message::AppMessage;
+use crate::constants::MAX_CLIENT_SEND_FAILURES;
+
+/// All non-playing clients. Counts consecutive failed sends per client, so
+/// that a single hiccup does not drop a client, but a dead one is removed
+/// once `MAX_CLIENT_SEND_FAILURES` is reached.
+#[derive(Debug)]
+pub struct Clients {
+    senders: BTreeMap<Uuid, UnboundedSender<BackendMessage>>,
+    send_failures: BTreeMap<Uuid, usize>,
+    app_tx: UnboundedSender<AppMessage>,
+}
+
+impl Clients {
+    pub fn new(app_tx: UnboundedSender<AppMessage>) -> Self {
+        Self {
+            senders: BTreeMap::default(),
+            send_failures: BTreeMap::default(),
+            app_tx,
+        }
+    }
+
+    /// # Insert client
+    ///
+    /// Adds a client and returns the sender it replaced, if the client was
+    /// already known.
+    pub fn insert(
+        &mut self,
+        client_id: Uuid,
+        client_tx: UnboundedSender<BackendMessage>,
+    ) -> Option<UnboundedSender<BackendMessage>> {
+        self.send_failures.remove(&client_id);
+        self.senders.insert(client_id, client_tx)
+    }
+
+    /// # Remove client
+    ///
+    /// Removes the client if the given sender still belongs to its current
+    /// connection. A replaced connection closing late does not remove the
+    /// connection that replaced it.
+    pub fn remove(
+        &mut self,
+        client_id: &Uuid,
+        client_tx: &UnboundedSender<BackendMessage>,
+    ) -> Option<UnboundedSender<BackendMessage>> {
+        if !self
+            .senders
+            .get(client_id)
+            .is_some_and(|current_tx| current_tx.same_channel(client_tx))
+        {
+            return None;
+        }
+        self.send_failures.remove(client_id);
+        self.senders.remove(client_id)
+    }
+
+    pub fn contains(&self, client_id: &Uuid) -> bool {
+        self.senders.contains_key(client_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// # Send message
+    ///
+    /// Sends a message to a single client. Fails if the client is unknown. A
+    /// failed delivery only counts towards the removal of the client.
+    pub fn send(&mut self, client_id: Uuid, message: BackendMessage) -> Result<()> {
+        let Some(client_tx) = self.senders.get(&client_id) else {
+            return Err(anyhow!("Client with ID {} was not found.", client_id));
+        };
+        let delivered = client_tx.send(message).is_ok();
+        track_send(
+            &mut self.send_failures,
+            &self.app_tx,
+            client_id,
+            client_tx,
+            delivered,
+        );
+        Ok(())
+    }
+
+    /// # Broadcast message
+    ///
+    /// Sends a message to every client. Failed deliveries do not stop the
+    /// broadcast.
+    pub fn broadcast(&mut self, message: BackendMessage) {
+        for (client_id, client_tx) in self.senders.iter() {
+            let delivered = client_tx.send(message.clone()).is_ok();
+            track_send(
+                &mut self.send_failures,
+                &self.app_tx,
+                *client_id,
+                client_tx,
+                delivered,
+            );
+        }
+    }
+}
+
+/// # Track send
+///
+/// Resets the failure counter of a client on a successful send and increments
+/// it otherwise. Asks the app to remove the client once the counter reaches
+/// `MAX_CLIENT_SEND_FAILURES`.
+fn track_send(
+    send_failures: &mut BTreeMap<Uuid, usize>,
+    app_tx: &UnboundedSender<AppMessage>,
+    client_id: Uuid,
+    client_tx: &UnboundedSender<BackendMessage>,
+    delivered: bool,
+) {
+    if delivered {
+        send_failures.remove(&client_id);
+        return;
+    }
+    let failures = send_failures.entry(client_id).or_default();
+    *failures += 1;
+    if *failures == MAX_CLIENT_SEND_FAILURES {
+        warn!(
+            "Sending to client {} failed {} times in a row, removing it.",
+            client_id, failures
+        );
+        let _ = app_tx.send(AppMessage::RemoveClient {
+            client_id,
+            client_tx: client_tx.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+
+    fn removals(app_rx: &mut UnboundedReceiver<AppMessage>) -> usize {
+        std::iter::from_fn(|| app_rx.try_recv().ok())
+            .filter(|msg| matches!(msg, AppMessage::RemoveClient { .. }))
+            .count()
+    }
+
+    #[test]
+    fn removes_a_client_once_sending_failed_too_often() {
+        let (app_tx, mut app_rx) = unbounded_channel();
+        let mut clients = Clients::new(app_tx);
+        let client_id = Uuid::new_v4();
+        let (client_tx, client_rx) = unbounded_channel();
+        clients.insert(client_id, client_tx);
+        drop(client_rx);
+
+        for _ in 1..MAX_CLIENT_SEND_FAILURES {
+            clients
+                .send(client_id, BackendMessage::ServerShutdown)
+                .unwrap();
+        }
+        assert_eq!(removals(&mut app_rx), 0);
+
+        clients
+            .send(client_id, BackendMessage::ServerShutdown)
+            .unwrap();
+        assert_eq!(removals(&mut app_rx), 1);
+    }
+
+    #[test]
+    fn successful_send_resets_the_failures() {
+        let (app_tx, mut app_rx) = unbounded_channel();
+        let mut send_failures = BTreeMap::default();
+        let client_id = Uuid::new_v4();
+        let (client_tx, _client_rx) = unbounded_channel();
+
+        for _ in 1..MAX_CLIENT_SEND_FAILURES {
+            track_send(&mut send_failures, &app_tx, client_id, &client_tx, false);
+        }
+        track_send(&mut send_failures, &app_tx, client_id, &client_tx, true);
+        track_send(&mut send_failures, &app_tx, client_id, &client_tx, false);
+        assert_eq!(removals(&mut app_rx), 0);
+        assert_eq!(send_failures.get(&client_id), Some(&1));
+    }
+}
@@ -1,16 +1,26 @@
-use chrono::Utc;
-use strsim::normalized_levenshtein;
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
 use tokio::sync::{mpsc::UnboundedSender, oneshot::Sender};
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use common::{BackendMessage, JoinMode, LobbyInformation, LobbyStatus};
+use common::{
+    BackendMessage, ChatMessage, CloseReason, Comparison, Difficulty, GameMode, JoinMode,
+    LobbyAvailability, LobbyFilter, LobbyInformation, LobbyPlayerCount, LobbyStatus, LobbyTag,
+    ReplayFrame, SessionInfo,
+};
 
-use super::App;
+use super::{App, SpectatorTarget};
 use crate::{
-    constants::{
-        LOBBY_FINISH_TIME, LOBBY_START_TIMER, MAX_LOBBY_PLAY_TIME, REDUCED_LOBBY_PLAY_TIME,
-    },
+    challenge::Challenge,
+    comparison::{compute_binary_progress, compute_progress},
+    constants::{BOT_TICK_RATE, LOBBY_FINISH_TIME, ROUND_TIMER_TOLERANCE, TIME_ATTACK_PLAY_TIME},
+    lobby::DisconnectOutcome,
+    metrics::{ConnectionSample, LobbyMetrics},
     player::Player,
 };
 
@@ -21,6 +31,9 @@ pub enum AppMessage {
     ProvideLobbyInformation {
         tx: Sender<LobbyInformation>,
         join_mode: JoinMode,
+        /// Description and tags of a created lobby, already validated.
+        description: Option<String>,
+        tags: BTreeSet<LobbyTag>,
     },
     AddPlayerToLobby {
         lobby_id: Uuid,
@@ -32,6 +45,26 @@ pub enum AppMessage {
         player: Player,
         lobby_id: Uuid,
     },
+    /// Holds the seat of a player whose connection was lost for the reconnect
+    /// grace period. Removes the player right away without a grace period.
+    DisconnectPlayer {
+        player: Player,
+        lobby_id: Uuid,
+    },
+    /// Removes a disconnected player once the grace period is over, unless
+    /// the player resumed in the meantime.
+    ReleaseHeldSeat {
+        lobby_id: Uuid,
+        player_id: Uuid,
+    },
+    /// Hands a held seat to the new connection presenting its reconnect
+    /// token. Answers with the resumed player, if the token matched.
+    ResumePlayer {
+        lobby_id: Uuid,
+        token: Uuid,
+        tx: UnboundedSender<BackendMessage>,
+        reply: Sender<Option<Player>>,
+    },
     /// Broadcasts a message of provided player to all connected players.
     SendMessage {
         player: Player,
@@ -39,9 +72,84 @@ pub enum AppMessage {
         lobby_id: Uuid,
     },
 
-    /// Broadcasts all existing lobbies to a freshly connected client.
+    /// Sends a private message of provided player to the player with the given
+    /// name.
+    Whisper {
+        player: Player,
+        lobby_id: Uuid,
+        name: String,
+        message: String,
+    },
+    /// Removes the player with the given name from the lobby if the provided
+    /// player is the lobby owner.
+    KickPlayer {
+        player: Player,
+        lobby_id: Uuid,
+        name: String,
+        reason: Option<String>,
+    },
+
+    /// Mutes or unmutes the chat of the target player if the provided player
+    /// is the lobby owner.
+    SetPlayerMuted {
+        player: Player,
+        lobby_id: Uuid,
+        target_id: Uuid,
+        muted: bool,
+    },
+    /// Counts the vote of a player to kick the target player. The target is
+    /// kicked once a majority of the other players voted for it.
+    VoteKick {
+        lobby_id: Uuid,
+        voter_id: Uuid,
+        target_id: Uuid,
+    },
+    /// Changes the name of the provided player. Invalid names are rejected,
+    /// taken ones get a number appended.
+    RenamePlayer {
+        player: Player,
+        lobby_id: Uuid,
+        new_name: String,
+    },
+    /// Stores the inputs the provided player sent to his editor during the
+    /// last round.
+    SubmitReplay {
+        player: Player,
+        lobby_id: Uuid,
+        frames: Vec<ReplayFrame>,
+    },
+    /// Sends the last round replay of the player with the given name to the
+    /// provided player.
+    RequestReplay {
+        player: Player,
+        lobby_id: Uuid,
+        name: String,
+    },
+
+    /// Tells a lobby browser or spectator client its ID and the lobby it
+    /// spectates, if any.
+    SendClientSessionInfo {
+        client_id: Uuid,
+    },
+    /// Tells a player its ID, its lobby and whether it owns the lobby.
+    SendPlayerSessionInfo {
+        player: Player,
+    },
+    /// Sends the challenge catalog to a client or player.
+    SendChallenges {
+        tx: UnboundedSender<BackendMessage>,
+    },
+    /// Sends the full roster of a lobby to one of its players.
+    SendRoster {
+        player: Player,
+        lobby_id: Uuid,
+    },
+
+    /// Sends all existing lobbies matching the filter to a client, e.g., a
+    /// freshly connected one.
     CurrentLobbies {
         client_id: Uuid,
+        filter: LobbyFilter,
     },
     /// Broadcasts name, player count, and status of a lobby to all connected
     /// clients.
@@ -52,13 +160,31 @@ pub enum AppMessage {
     SendLobbyPlayerCountUpdate {
         lobby_id: Uuid,
     },
+    /// Broadcasts the spectator count of a lobby to all connected clients.
+    SendSpectatorCount {
+        lobby_id: Uuid,
+    },
     SendLobbyStatusUpdate {
         lobby_id: Uuid,
     },
+    /// Tells all connected clients that a lobby became full or has free seats
+    /// again.
+    SendLobbyAvailabilityUpdate {
+        lobby_id: Uuid,
+    },
     /// Removes an existing lobby.
     RemoveLobby {
         lobby_id: Uuid,
     },
+    /// Sends all members of a lobby back to the lobby browser and removes the
+    /// lobby right away. Triggered by an operator, described by `origin`.
+    /// Replies once the lobby is closed and drops the sender if the lobby
+    /// does not exist.
+    AdminCloseLobby {
+        lobby_id: Uuid,
+        origin: String,
+        tx: Sender<()>,
+    },
     /// Tells a player that the lobby he is trying to connect to is already
     /// full.
     LobbyFull {
@@ -67,20 +193,197 @@ pub enum AppMessage {
     /// Broadcasts the current amount of connected clients and players to
     /// clients and players.
     SendConnectionCounts,
+    /// Adds the current amount of connected clients and players to the
+    /// connection history.
+    SampleConnectionCounts,
+    /// Sends the current server time to every player, so that clients can
+    /// align their timers with the backend.
+    BroadcastServerTime,
+    /// Provides the connection history to an operator.
+    ProvideConnectionHistory {
+        tx: Sender<Vec<ConnectionSample>>,
+    },
+    /// Provides the lobby counts of the quickplay pre-warming to an operator.
+    ProvideLobbyMetrics {
+        tx: Sender<LobbyMetrics>,
+    },
+    /// Creates quickplay lobbies until the configured amount is ready.
+    PrewarmLobbies,
+    /// Lets a client watch the editor of the given player. Only frames of the
+    /// currently watched player are relayed to the client.
+    SpectatePlayer {
+        client_id: Uuid,
+        lobby_id: Uuid,
+        target_id: Uuid,
+    },
+    /// Reloads the challenge catalog from disk.
+    ReloadChallenges,
+    /// Answers the health checks with whether the startup is complete, i.e.,
+    /// challenges to create lobbies with are loaded.
+    Heartbeat {
+        tx: Sender<bool>,
+    },
+    /// Tells every client and player that the backend shuts down. Replies
+    /// once the notice is queued for all of them.
+    Shutdown {
+        tx: Sender<()>,
+    },
     /// Adds a new client.
     AddClient {
         client_id: Uuid,
         client_tx: UnboundedSender<BackendMessage>,
     },
-    /// Removes an existing client.
+    /// Adds a client connection presenting its reconnect token. Answers with
+    /// the client ID the connection continues as and whether the client
+    /// watches again. Unknown tokens add a plain client.
+    ReconnectSpectator {
+        client_id: Uuid,
+        token: Uuid,
+        client_tx: UnboundedSender<BackendMessage>,
+        reply: Sender<(Uuid, bool)>,
+    },
+    /// Removes an existing client. The watch position of a spectating client
+    /// is held for the reconnect grace period.
     RemoveClient {
         client_id: Uuid,
+        /// The sender of the closed connection. The client is only removed if
+        /// it was not replaced by another connection in the meantime.
+        client_tx: UnboundedSender<BackendMessage>,
+    },
+    /// Frees the spectator slot held for the token once the grace period is
+    /// over, unless the spectator resumed in the meantime.
+    ReleaseHeldSpectator {
+        token: Uuid,
+    },
+    /// Starts the countdown inside a lobby regardless of the ready states if
+    /// the requesting player is the lobby owner.
+    ForceStart {
+        lobby_id: Uuid,
+        requester_id: Uuid,
+    },
+    /// Freezes the round timer of a lobby if the requesting player is the
+    /// lobby owner.
+    PauseRound {
+        lobby_id: Uuid,
+        requester_id: Uuid,
+    },
+    /// Lets the round timer of a paused lobby run again if the requesting
+    /// player is the lobby owner.
+    ResumeRound {
+        lobby_id: Uuid,
+        requester_id: Uuid,
+    },
+    /// Toggles the ready state of the provided player. Starts the game inside
+    /// the lobby once all active players are ready.
+    ToggleReady {
+        player: Player,
+        lobby_id: Uuid,
+    },
+    /// Resets the ready states of a lobby if not all players got ready within
+    /// the ready timeout.
+    ExpireReadyPhase {
+        lobby_id: Uuid,
+    },
+    /// Marks the editor of the provided player as ready. A requested start is
+    /// deferred until all editors are ready.
+    EditorReady {
+        player: Player,
+        lobby_id: Uuid,
+    },
+    /// Changes the game mode of a lobby if the provided player is the lobby
+    /// owner and the lobby is waiting for players.
+    SetGameMode {
+        player: Player,
+        lobby_id: Uuid,
+        game_mode: GameMode,
+    },
+    /// Replaces the race challenge of a lobby if the provided player is the
+    /// lobby owner and the lobby is waiting for players.
+    SetLobbyGoal {
+        player: Player,
+        lobby_id: Uuid,
+        challenge_id: String,
+    },
+    /// Replaces or removes the warmup challenge of the lobby if the provided
+    /// player is the lobby owner.
+    SetWarmup {
+        player: Player,
+        lobby_id: Uuid,
+        challenge_id: Option<String>,
+    },
+    /// Shows or hides the goal file in the lobby if the provided player is
+    /// the lobby owner.
+    SetGoalPreview {
+        player: Player,
+        lobby_id: Uuid,
+        enabled: bool,
+    },
+    /// Turns the reveal of the winning solution in the lobby on or off if the
+    /// provided player is the lobby owner.
+    SetSolutionReveal {
+        player: Player,
+        lobby_id: Uuid,
+        enabled: bool,
+    },
+    /// Replaces the description of a lobby if the provided player is the
+    /// lobby owner. `None` removes the description.
+    SetLobbyDescription {
+        player: Player,
+        lobby_id: Uuid,
+        description: Option<String>,
+    },
+    /// Replaces the tags of a lobby if the provided player is the lobby
+    /// owner.
+    SetLobbyTags {
+        player: Player,
+        lobby_id: Uuid,
+        tags: BTreeSet<LobbyTag>,
+    },
+    /// Overrides how player files of a lobby are compared against the goal
+    /// file if the provided player is the lobby owner.
+    SetComparison {
+        player: Player,
+        lobby_id: Uuid,
+        comparison: Option<Comparison>,
+    },
+    /// Changes the seats of a lobby if the provided player is the lobby
+    /// owner.
+    SetMaxPlayers {
+        player: Player,
+        lobby_id: Uuid,
+        max_players: usize,
+    },
+    /// Changes how long races of a lobby last if the provided player is the
+    /// lobby owner.
+    SetTimeLimit {
+        player: Player,
+        lobby_id: Uuid,
+        time_limit: Option<Duration>,
     },
-    /// Requests to start the game inside a lobby if the provided player is the
+    /// Changes the spectator cap of a lobby if the provided player is the
     /// lobby owner.
-    RequestStart {
+    SetMaxSpectators {
         player: Player,
         lobby_id: Uuid,
+        max_spectators: usize,
+    },
+    /// Allows or forbids spectators in a lobby if the provided player is the
+    /// lobby owner. Forbidding them stops everyone watching.
+    SetSpectatorsAllowed {
+        player: Player,
+        lobby_id: Uuid,
+        allowed: bool,
+    },
+    /// Replaces the bot opponent of a lobby if the provided player is the
+    /// lobby owner. `None` removes the bot.
+    SetBot {
+        player: Player,
+        lobby_id: Uuid,
+        difficulty: Option<Difficulty>,
+    },
+    /// Advances the progress of the bot opponent inside a lobby.
+    AdvanceBot {
+        lobby_id: Uuid,
     },
     /// Starts the game inside a lobby.
     Start {
@@ -100,6 +403,8 @@ pub enum AppMessage {
         lobby_id: Uuid,
         player_id: Uuid,
         progress: Vec<u8>,
+        /// When the player route received the progress, used to time solves.
+        received_at: DateTime<Utc>,
     },
 }
 
@@ -109,335 +414,1629 @@ pub enum AppMessage {
 /// handled in here which allows us to avoid the use of `Mutex` entirely.
 pub async fn handle_app_message(mut app: App) {
     while let Some(msg) = app.rx.recv().await {
-        match msg {
-            AppMessage::ProvideLobbyInformation { tx, join_mode } => {
-                let Ok(lobby_id) = app.get_lobby_id(join_mode) else {
-                    error!("Unable to retrieve lobby ID by join mode.");
-                    continue;
-                };
-                let Some(lobby) = app.lobbies.get(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                let lobby_information = lobby.to_information();
-                let _ = tx.send(lobby_information);
-            }
-            AppMessage::AddPlayerToLobby { lobby_id, player } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                lobby.add_player(player, &app.tx);
-            }
-            AppMessage::RemovePlayer { player, lobby_id } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                lobby.remove_player(player, &app.tx);
-            }
-            AppMessage::SendMessage {
-                player,
-                message,
-                lobby_id,
-            } => {
-                let Some(lobby) = app.lobbies.get(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                lobby.send_message(player, message.clone());
-            }
-
-            AppMessage::LobbyFull { player_tx } => {
-                let message = BackendMessage::LobbyFull;
-                let _ = player_tx.send(message);
-            }
-
-            AppMessage::CurrentLobbies { client_id } => {
-                let Some(client) = app.clients.get(&client_id) else {
-                    error!("Client with ID {} was not found.", client_id);
-                    continue;
-                };
-                let lobbies = app.get_current_lobbies();
-                let message = BackendMessage::CurrentLobbies(lobbies);
-                let _ = client.send(message);
-            }
-            AppMessage::AddLobby { lobby_id } => {
-                if let Err(e) = app.send_lobby_list_information(lobby_id) {
-                    error!("Error sending lobby list information: {e}");
+        handle_message(&mut app, msg);
+    }
+}
+
+/// # Handle message
+///
+/// Applies a single `AppMessage` to the app state.
+fn handle_message(app: &mut App, msg: AppMessage) {
+    match msg {
+        AppMessage::ProvideLobbyInformation {
+            tx,
+            join_mode,
+            description,
+            tags,
+        } => {
+            let is_created = matches!(join_mode, JoinMode::Create);
+            let Ok((lobby_id, quickplay)) = app.get_lobby_id(join_mode) else {
+                error!("Unable to retrieve lobby ID by join mode.");
+                return;
+            };
+            // Only the creator describes a lobby upfront.
+            if is_created && (description.is_some() || !tags.is_empty()) {
+                if let Some(lobby) = app.lobbies.get_mut(&lobby_id) {
+                    lobby.description = description;
+                    lobby.tags = tags;
                 }
+                let _ = app.tx.send(AppMessage::AddLobby { lobby_id });
             }
-            AppMessage::RemoveLobby { lobby_id } => {
-                if let Err(e) = app.remove_lobby(lobby_id) {
-                    error!("Error removing lobby: {e}");
-                }
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            if let Some(quickplay) = quickplay {
+                info!("Quickplay picked lobby {} ({:?}).", lobby.name, quickplay);
+            }
+            let mut lobby_information = lobby.to_information();
+            lobby_information.quickplay = quickplay;
+            let _ = tx.send(lobby_information);
+        }
+        AppMessage::AddPlayerToLobby { lobby_id, player } => {
+            // A client can only play in a single lobby at a time, even
+            // through several player connections. Clients are identified by
+            // the ID the backend gave their lobby browser connection, so
+            // joins have to come from a connected client.
+            let Some(client_id) = player
+                .client_id
+                .filter(|client_id| app.clients.contains(client_id))
+            else {
+                warn!(
+                    "Player {} tried to join lobby {} without a connected client.",
+                    player.name, lobby_id
+                );
+                let _ = player.tx.send(BackendMessage::UnknownClient);
+                return;
+            };
+            if let Some(current_lobby_id) = app.client_lobbies.get(&client_id) {
+                warn!(
+                    "Player {} tried to join lobby {} while being in lobby {}.",
+                    player.name, lobby_id, current_lobby_id
+                );
+                let _ = player.tx.send(BackendMessage::AlreadyInLobby);
+                return;
             }
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            if !lobby.add_player(player.clone(), &app.tx) {
+                return;
+            }
+            let challenge_id = lobby.challenge.id.clone();
+            app.track_player(&player, lobby_id);
 
-            AppMessage::AddClient {
-                client_id,
-                client_tx,
-            } => {
-                app.clients.insert(client_id, client_tx);
-                let _ = app.tx.send(AppMessage::SendConnectionCounts);
-                info!(
-                    "Added client with ID {}. Client count is {}.",
-                    client_id,
-                    app.clients.len()
+            // Tell the new player about the time to beat, if there is one.
+            if let Some(record) = app.records.get(&challenge_id) {
+                let _ = player
+                    .tx
+                    .send(BackendMessage::ChallengeRecord(record.clone()));
+            }
+        }
+        AppMessage::RemovePlayer { player, lobby_id } => {
+            // A connection the player replaced by resuming must not take the
+            // seat with it.
+            if app
+                .lobbies
+                .get(&lobby_id)
+                .is_some_and(|lobby| lobby.is_superseded(&player))
+            {
+                debug!(
+                    "Ignoring removal of superseded connection of player {}.",
+                    player.name
                 );
+                return;
+            }
+            // Kicked players are removed from the lobby beforehand, but
+            // stay tracked until their connection is closed.
+            app.untrack_player(&player, lobby_id);
+            app.release_spectators(SpectatorTarget {
+                lobby_id,
+                player_id: player.id,
+            });
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.remove_player(player, &app.tx);
+        }
+        AppMessage::DisconnectPlayer { player, lobby_id } => {
+            let grace = app.reconnect_grace;
+            let outcome = app
+                .lobbies
+                .get_mut(&lobby_id)
+                .map_or(DisconnectOutcome::Released, |lobby| {
+                    lobby.disconnect_player(&player, grace)
+                });
+            match outcome {
+                DisconnectOutcome::Held => {}
+                DisconnectOutcome::Superseded => {
+                    debug!(
+                        "Ignoring disconnect of superseded connection of player {}.",
+                        player.name
+                    );
+                    return;
+                }
+                DisconnectOutcome::Released => {
+                    let _ = app.tx.send(AppMessage::RemovePlayer { player, lobby_id });
+                    return;
+                }
             }
-            AppMessage::RemoveClient { client_id } => {
-                app.clients.remove(&client_id);
-                let _ = app.tx.send(AppMessage::SendConnectionCounts);
+            let app_tx = app.tx.clone();
+            let player_id = player.id;
+            tokio::spawn(async move {
+                tokio::time::sleep(grace).await;
+                let _ = app_tx.send(AppMessage::ReleaseHeldSeat {
+                    lobby_id,
+                    player_id,
+                });
+            });
+        }
+        AppMessage::ReleaseHeldSeat {
+            lobby_id,
+            player_id,
+        } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                return;
+            };
+            // Players that resumed or disconnected again later keep their
+            // seat.
+            if let Some(player) = lobby.held_seat_expired(player_id, app.reconnect_grace) {
                 info!(
-                    "Removed client with ID {}. Client count is {}.",
-                    client_id,
-                    app.clients.len()
+                    "Releasing seat of player {} in lobby {}.",
+                    player.name, lobby.name
                 );
+                let player = player.clone();
+                let _ = app.tx.send(AppMessage::RemovePlayer { player, lobby_id });
             }
-            AppMessage::SendConnectionCounts => {
-                let clients = app.clients.len();
-                let players = app.lobbies.values().map(|lobby| lobby.players.len()).sum();
-                let message = BackendMessage::ConnectionCounts { clients, players };
+        }
+        AppMessage::ResumePlayer {
+            lobby_id,
+            token,
+            tx,
+            reply,
+        } => {
+            let player = app
+                .lobbies
+                .get_mut(&lobby_id)
+                .and_then(|lobby| lobby.resume_player(token, tx));
+            let _ = reply.send(player);
+        }
+        AppMessage::SendMessage {
+            player,
+            message,
+            lobby_id,
+        } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.send_message(player, message.clone(), app.chat_filter.as_deref());
+        }
 
-                // Send counts to all clients.
-                for client in app.clients.values() {
-                    let _ = client.send(message.clone());
-                }
+        AppMessage::Whisper {
+            player,
+            lobby_id,
+            name,
+            message,
+        } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.whisper(player, &name, message, app.chat_filter.as_deref());
+        }
+        AppMessage::KickPlayer {
+            player,
+            lobby_id,
+            name,
+            reason,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.kick_player(player, &name, reason, &app.tx);
+        }
 
-                // Send counts to all players.
-                for lobby in app.lobbies.values() {
-                    lobby.broadcast(message.clone());
-                }
+        AppMessage::SetPlayerMuted {
+            player,
+            lobby_id,
+            target_id,
+            muted,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_player_muted(player, target_id, muted);
+        }
+        AppMessage::VoteKick {
+            lobby_id,
+            voter_id,
+            target_id,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.vote_kick(voter_id, target_id, &app.tx);
+        }
+        AppMessage::RenamePlayer {
+            player,
+            lobby_id,
+            new_name,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.rename_player(player, new_name);
+        }
+        AppMessage::SubmitReplay {
+            player,
+            lobby_id,
+            frames,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.submit_replay(player, frames);
+        }
+        AppMessage::RequestReplay {
+            player,
+            lobby_id,
+            name,
+        } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.send_replay(player, &name);
+        }
+
+        AppMessage::LobbyFull { player_tx } => {
+            let message = BackendMessage::LobbyFull;
+            let _ = player_tx.send(message);
+        }
+
+        AppMessage::CurrentLobbies { client_id, filter } => {
+            let lobbies = app.get_current_lobbies(&filter);
+            let message = BackendMessage::CurrentLobbies(lobbies);
+            if let Err(e) = app.clients.send(client_id, message) {
+                error!("{e}");
             }
-            AppMessage::RequestStart { player, lobby_id } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                // Start the game inside the lobby if the player is the
-                // lobby owner.
-                if lobby.owner.is_some_and(|owner_id| owner_id.eq(&player.id))
-                    && lobby.status == LobbyStatus::WaitingForPlayers
-                {
-                    // Change the lobby status and tell clients about it.
-                    lobby.status = LobbyStatus::AboutToStart(Utc::now() + LOBBY_START_TIMER);
-                    let _ = app
-                        .tx
-                        .send(AppMessage::SendLobbyStatusUpdate { lobby_id: lobby.id });
-                    // Tell players in the lobby about the status update.
-                    lobby.broadcast(BackendMessage::StatusUpdate {
-                        status: lobby.status.clone(),
-                    });
-
-                    // Wait for a duration of `LOBBY_START_TIMER` and tell
-                    // the application to start the lobby.
-                    let app_tx = app.tx.clone();
-                    let lobby_id = lobby.id;
-                    tokio::spawn(async move {
-                        tokio::time::sleep(LOBBY_START_TIMER).await;
-                        let _ = app_tx.send(AppMessage::Start { lobby_id });
-                    });
-                }
+        }
+        AppMessage::SendClientSessionInfo { client_id } => {
+            let message = BackendMessage::SessionInfo(SessionInfo {
+                client_id: Some(client_id),
+                player_id: None,
+                lobby_id: app.spectators.get(&client_id).map(|target| target.lobby_id),
+                is_host: false,
+            });
+            if let Err(e) = app.clients.send(client_id, message) {
+                error!("{e}");
             }
-            AppMessage::Start { lobby_id } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                let LobbyStatus::AboutToStart(_) = lobby.status else {
-                    warn!(
-                        "Tried to start lobby {} with {} players that was not about to start.",
-                        lobby.name,
-                        lobby.players.len()
-                    );
-                    continue;
-                };
-                lobby.status = LobbyStatus::InProgress(Utc::now() + MAX_LOBBY_PLAY_TIME);
-                // Tell clients about the started lobby.
-                let _ = app
-                    .tx
-                    .send(AppMessage::SendLobbyStatusUpdate { lobby_id: lobby.id });
-                // Tell players in the lobby about the status update.
-                lobby.broadcast(BackendMessage::StatusUpdate {
-                    status: lobby.status.clone(),
-                });
+        }
+        AppMessage::SendPlayerSessionInfo { player } => {
+            let lobby_id = app.player_lobbies.get(&player.id).copied();
+            let is_host = lobby_id
+                .and_then(|lobby_id| app.lobbies.get(&lobby_id))
+                .is_some_and(|lobby| lobby.owner == Some(player.id));
+            let _ = player.tx.send(BackendMessage::SessionInfo(SessionInfo {
+                client_id: None,
+                player_id: Some(player.id),
+                lobby_id,
+                is_host,
+            }));
+        }
+        AppMessage::SendChallenges { tx } => {
+            let challenges = app
+                .challenges
+                .values()
+                .map(Challenge::to_information)
+                .collect();
+            let _ = tx.send(BackendMessage::Challenges(challenges));
+        }
+        AppMessage::SendRoster { player, lobby_id } => {
+            if app.player_lobbies.get(&player.id) != Some(&lobby_id) {
+                warn!(
+                    "Player {} requested the roster of lobby {} without being in it.",
+                    player.name, lobby_id
+                );
+                return;
+            }
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let _ = player.tx.send(BackendMessage::Roster(lobby.roster()));
+        }
+        AppMessage::AddLobby { lobby_id } => {
+            if let Err(e) = app.send_lobby_list_information(lobby_id) {
+                error!("Error sending lobby list information: {e}");
+            }
+        }
+        AppMessage::RemoveLobby { lobby_id } => {
+            if let Err(e) = app.remove_lobby(lobby_id) {
+                error!("Error removing lobby: {e}");
+            }
+        }
+        AppMessage::AdminCloseLobby {
+            lobby_id,
+            origin,
+            tx,
+        } => match app.close_lobby(lobby_id, &origin) {
+            Ok(()) => {
+                let _ = tx.send(());
+            }
+            Err(e) => warn!("Error closing lobby on {origin}: {e}"),
+        },
 
-                // Put the lobby in `LobbyStatus::Finish` after two minutes.
+        AppMessage::AddClient {
+            client_id,
+            client_tx,
+        } => {
+            app.add_client(client_id, client_tx);
+        }
+        AppMessage::ReconnectSpectator {
+            client_id,
+            token,
+            client_tx,
+            reply,
+        } => {
+            let _ = reply.send(app.reconnect_client(client_id, token, client_tx));
+        }
+        AppMessage::SpectatePlayer {
+            client_id,
+            lobby_id,
+            target_id,
+        } => {
+            let target = SpectatorTarget {
+                lobby_id,
+                player_id: target_id,
+            };
+            if let Err(e) = app.spectate_player(client_id, target) {
+                error!("Error spectating player: {e}");
+            }
+        }
+        AppMessage::ReloadChallenges => {
+            app.reload_challenges();
+        }
+        AppMessage::Heartbeat { tx } => {
+            let _ = tx.send(!app.challenges.is_empty());
+        }
+        AppMessage::Shutdown { tx } => {
+            info!(
+                "Announcing the shutdown to {} clients and {} lobbies.",
+                app.clients.len(),
+                app.lobbies.len()
+            );
+            let close = BackendMessage::CloseConnection {
+                reason: Some(CloseReason::Shutdown),
+            };
+            app.clients.broadcast(BackendMessage::ServerShutdown);
+            app.clients.broadcast(close.clone());
+            for lobby in app.lobbies.values() {
+                lobby.broadcast(BackendMessage::ServerShutdown);
+                lobby.broadcast(close.clone());
+            }
+            let _ = tx.send(());
+        }
+        AppMessage::RemoveClient {
+            client_id,
+            client_tx,
+        } => {
+            // Clients can be removed by their route and after failed
+            // sends.
+            if app.clients.remove(&client_id, &client_tx).is_none() {
+                return;
+            }
+            let grace = app.reconnect_grace;
+            let held = (!grace.is_zero())
+                .then(|| app.hold_spectator(client_id))
+                .flatten();
+            if let Some(token) = held {
+                // The held position keeps counting towards the cap.
                 let app_tx = app.tx.clone();
                 tokio::spawn(async move {
-                    tokio::time::sleep(MAX_LOBBY_PLAY_TIME).await;
-                    let _ = app_tx.send(AppMessage::Finish { lobby_id });
+                    tokio::time::sleep(grace).await;
+                    let _ = app_tx.send(AppMessage::ReleaseHeldSpectator { token });
                 });
+            } else if let Some(target) = app.spectators.remove(&client_id) {
+                app.send_spectator_count(target.lobby_id);
             }
-            AppMessage::SendLobbyPlayerCountUpdate { lobby_id } => {
-                let Some(lobby) = app.lobbies.get(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                for client in app.clients.values() {
-                    let _ = client.send(BackendMessage::UpdateLobbyPlayerCount {
-                        id: lobby_id,
-                        player_count: lobby.players.len(),
-                    });
-                }
+            app.spectator_tokens.remove(&client_id);
+            let _ = app.tx.send(AppMessage::SendConnectionCounts);
+            info!(
+                "Removed client with ID {}. Client count is {}.",
+                client_id,
+                app.clients.len()
+            );
+        }
+        AppMessage::ReleaseHeldSpectator { token } => {
+            // Spectators that resumed or disconnected again later keep
+            // their slot.
+            let Some(held) = app
+                .held_spectators
+                .get(&token)
+                .filter(|held| held.disconnected_at.elapsed() >= app.reconnect_grace)
+                .copied()
+            else {
+                return;
+            };
+            info!(
+                "Releasing spectator slot in lobby {}.",
+                held.target.lobby_id
+            );
+            app.held_spectators.remove(&token);
+            app.send_spectator_count(held.target.lobby_id);
+        }
+        AppMessage::SendConnectionCounts => {
+            let (clients, players) = app.connection_counts();
+            let message = BackendMessage::ConnectionCounts { clients, players };
+
+            // Send counts to all clients.
+            app.clients.broadcast(message.clone());
+
+            // Send counts to all players.
+            for lobby in app.lobbies.values() {
+                lobby.broadcast(message.clone());
             }
-            AppMessage::SendLobbyStatusUpdate { lobby_id } => {
-                let Some(lobby) = app.lobbies.get(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                for client in app.clients.values() {
-                    let _ = client.send(BackendMessage::UpdateLobbyStatus {
-                        id: lobby_id,
-                        status: lobby.status.clone(),
-                    });
-                }
+        }
+        AppMessage::SampleConnectionCounts => {
+            let (clients, players) = app.connection_counts();
+            app.connection_history.record(clients, players);
+        }
+        AppMessage::BroadcastServerTime => {
+            let unix_millis = Utc::now().timestamp_millis();
+            for lobby in app.lobbies.values() {
+                lobby.broadcast(BackendMessage::ServerTime { unix_millis });
+            }
+        }
+        AppMessage::ProvideConnectionHistory { tx } => {
+            let _ = tx.send(app.connection_history.samples());
+        }
+        AppMessage::ProvideLobbyMetrics { tx } => {
+            let _ = tx.send(LobbyMetrics {
+                lobbies: app.lobbies.len(),
+                quickplay_ready: app.quickplay_ready_count(),
+                prewarm_target: app.prewarm_target,
+            });
+        }
+        AppMessage::PrewarmLobbies => app.prewarm_lobbies(),
+        AppMessage::ForceStart {
+            lobby_id,
+            requester_id,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.force_start(requester_id, &app.tx);
+        }
+        AppMessage::PauseRound {
+            lobby_id,
+            requester_id,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.pause_round(requester_id);
+        }
+        AppMessage::ResumeRound {
+            lobby_id,
+            requester_id,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.resume_round(requester_id, &app.tx);
+        }
+        AppMessage::ToggleReady { player, lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            if lobby.status != LobbyStatus::WaitingForPlayers {
+                warn!(
+                    "Player {} tried to toggle ready in lobby {} that is not waiting for players.",
+                    player.name, lobby.name
+                );
+                return;
+            }
+            let Some(player) = lobby.players.get_mut(&player.id) else {
+                error!(
+                    "Player {} was not found in lobby {}.",
+                    player.name, lobby.name
+                );
+                return;
+            };
+            player.ready = !player.ready;
+            let is_ready = player.ready;
+            let message = BackendMessage::PlayerReady {
+                player_id: player.id,
+                ready: player.ready,
+            };
+            lobby.broadcast(message);
+
+            // Start the game as soon as all active players are ready.
+            if lobby
+                .players
+                .values()
+                .filter(|player| !player.waiting)
+                .all(|player| player.ready)
+            {
+                lobby.ready_since = None;
+                lobby.request_countdown(&app.tx);
+                return;
             }
-            AppMessage::Finish { lobby_id } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                let LobbyStatus::InProgress(_) = lobby.status else {
-                    continue;
-                };
-                lobby.status = LobbyStatus::Finish(Utc::now() + LOBBY_FINISH_TIME);
-                // Tell clients about the finished lobby.
-                let _ = app
-                    .tx
-                    .send(AppMessage::SendLobbyStatusUpdate { lobby_id: lobby.id });
-                // Tell players in the lobby about the status update.
-                lobby.broadcast(BackendMessage::StatusUpdate {
-                    status: lobby.status.clone(),
-                });
 
-                // Put the lobby in `LobbyStatus::WaitingForPlayers` after two minutes.
+            // Give the others some time to get ready as well. Every newly
+            // ready player restarts the timer.
+            if is_ready && !app.ready_timeout.is_zero() {
+                lobby.ready_since = Some(Instant::now());
                 let app_tx = app.tx.clone();
+                let ready_timeout = app.ready_timeout;
                 tokio::spawn(async move {
-                    tokio::time::sleep(LOBBY_FINISH_TIME).await;
-                    let _ = app_tx.send(AppMessage::Reset { lobby_id });
+                    tokio::time::sleep(ready_timeout).await;
+                    let _ = app_tx.send(AppMessage::ExpireReadyPhase { lobby_id });
                 });
             }
-            AppMessage::Reset { lobby_id } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
+        }
+        AppMessage::ExpireReadyPhase { lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                return;
+            };
+            lobby.expire_ready_phase(app.ready_timeout);
+        }
+        AppMessage::EditorReady { player, lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.editor_ready(player.id, &app.tx);
+        }
+        AppMessage::SetGameMode {
+            player,
+            lobby_id,
+            game_mode,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            if !lobby.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+                warn!(
+                    "Player {} tried to change the game mode of lobby {} without being the owner.",
+                    player.name, lobby.name
+                );
+                return;
+            }
+            if lobby.status != LobbyStatus::WaitingForPlayers {
+                warn!(
+                    "Tried to change the game mode of lobby {} that is not waiting for players.",
+                    lobby.name
+                );
+                return;
+            }
+            lobby.game_mode = game_mode;
+            lobby.reset_series();
+            info!(
+                "Changed game mode of lobby {} to {}.",
+                lobby.name, game_mode
+            );
+            lobby.broadcast(BackendMessage::GameModeUpdate { game_mode });
+        }
+        AppMessage::SetLobbyGoal {
+            player,
+            lobby_id,
+            challenge_id,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let Some(challenge) = app.challenges.get(&challenge_id) else {
+                let challenge_ids = app
+                    .challenges
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                let message = format!(
+                    "Challenge {challenge_id} does not exist. Available challenges: {}.",
+                    challenge_ids.join(", ")
+                );
+                let _ = player
+                    .tx
+                    .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+                return;
+            };
+            if !lobby.set_challenge(player, challenge.clone()) {
+                return;
+            }
 
-                // Reset all players progress.
-                for player in lobby.players.values_mut() {
-                    player.progress = 0.0;
+            // Tell players about the time to beat of the new challenge.
+            if let Some(record) = app.records.get(&challenge.id) {
+                lobby.broadcast(BackendMessage::ChallengeRecord(record.clone()));
+            }
+            // Tell clients about the new language and difficulty.
+            let _ = app.tx.send(AppMessage::AddLobby { lobby_id });
+        }
+        AppMessage::SetWarmup {
+            player,
+            lobby_id,
+            challenge_id,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let warmup = match challenge_id {
+                Some(challenge_id) => {
+                    let Some(challenge) = app.challenges.get(&challenge_id) else {
+                        let message = format!("Challenge {challenge_id} does not exist.");
+                        let _ = player
+                            .tx
+                            .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+                        return;
+                    };
+                    Some(challenge.clone())
+                }
+                None => None,
+            };
+            lobby.set_warmup(player, warmup);
+        }
+        AppMessage::SetGoalPreview {
+            player,
+            lobby_id,
+            enabled,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_goal_preview(player, enabled);
+        }
+        AppMessage::SetSolutionReveal {
+            player,
+            lobby_id,
+            enabled,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_solution_reveal(player, enabled);
+        }
+        AppMessage::SetMaxSpectators {
+            player,
+            lobby_id,
+            max_spectators,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_max_spectators(player, max_spectators, &app.tx);
+        }
+        AppMessage::SetMaxPlayers {
+            player,
+            lobby_id,
+            max_players,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            // Keep the lobby browser up to date.
+            if lobby.set_max_players(player, max_players, &app.tx) {
+                let _ = app.tx.send(AppMessage::AddLobby { lobby_id });
+            }
+        }
+        AppMessage::SetTimeLimit {
+            player,
+            lobby_id,
+            time_limit,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_time_limit(player, time_limit);
+        }
+        AppMessage::SetLobbyDescription {
+            player,
+            lobby_id,
+            description,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            // Keep the lobby browser up to date.
+            if lobby.set_description(player, description) {
+                let _ = app.tx.send(AppMessage::AddLobby { lobby_id });
+            }
+        }
+        AppMessage::SetLobbyTags {
+            player,
+            lobby_id,
+            tags,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            // Keep the lobby browser up to date.
+            if lobby.set_tags(player, tags) {
+                let _ = app.tx.send(AppMessage::AddLobby { lobby_id });
+            }
+        }
+        AppMessage::SetComparison {
+            player,
+            lobby_id,
+            comparison,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_comparison(player, comparison);
+        }
+        AppMessage::SetSpectatorsAllowed {
+            player,
+            lobby_id,
+            allowed,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            if !lobby.set_spectators_allowed(player, allowed) {
+                return;
+            }
+            if !allowed {
+                app.dismiss_spectators(lobby_id);
+            }
+            app.send_spectator_count(lobby_id);
+        }
+        AppMessage::SetBot {
+            player,
+            lobby_id,
+            difficulty,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            lobby.set_bot(player, difficulty);
+        }
+        AppMessage::AdvanceBot { lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                return;
+            };
+            lobby.advance_bot(&app.tx);
+        }
+        AppMessage::Start { lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let LobbyStatus::AboutToStart(_) = lobby.status else {
+                warn!(
+                    "Tried to start lobby {} with {} players that was not about to start.",
+                    lobby.name,
+                    lobby.players.len()
+                );
+                return;
+            };
+            let play_time = match lobby.game_mode {
+                GameMode::Race => {
+                    lobby.start_warmup_round();
+                    lobby.send_goal_preview();
+                    lobby.race_time_limit(app.round_time_limit)
                 }
+                GameMode::TimeAttack => {
+                    // Hand out the first challenge to every player.
+                    lobby.start_time_attack();
+                    TIME_ATTACK_PLAY_TIME
+                }
+            };
+            lobby.started_at = Some(Utc::now());
+            lobby.replays.clear();
+            lobby.status = LobbyStatus::InProgress(Utc::now() + play_time);
+            // Tell clients about the started lobby.
+            let _ = app
+                .tx
+                .send(AppMessage::SendLobbyStatusUpdate { lobby_id: lobby.id });
+            // Tell players in the lobby about the status update.
+            lobby.broadcast(BackendMessage::StatusUpdate {
+                status: lobby.status.clone(),
+            });
+            lobby.start_round();
 
-                lobby.players.values().for_each(|player| {
-                    // Tell players in the lobby about the progress reset of each
-                    // player.
-                    lobby.broadcast(BackendMessage::UpdatePlayerProgress {
-                        player_id: player.id,
-                        progress: player.progress,
-                    });
+            // Let the bot race along by advancing it periodically until it
+            // is able to finish.
+            if let (GameMode::Race, Some(ref bot)) = (lobby.game_mode, &lobby.bot) {
+                let ticks = bot.finish_time().div_duration_f64(BOT_TICK_RATE).ceil() as u32;
+                let app_tx = app.tx.clone();
+                tokio::spawn(async move {
+                    for _ in 0..ticks {
+                        tokio::time::sleep(BOT_TICK_RATE).await;
+                        if app_tx.send(AppMessage::AdvanceBot { lobby_id }).is_err() {
+                            break;
+                        }
+                    }
                 });
+            }
 
-                lobby.status = LobbyStatus::WaitingForPlayers;
-                // Tell clients about the reset lobby.
-                let _ = app
-                    .tx
-                    .send(AppMessage::SendLobbyStatusUpdate { lobby_id: lobby.id });
-                // Tell players in the lobby about the status update.
-                lobby.broadcast(BackendMessage::StatusUpdate {
-                    status: lobby.status.clone(),
-                });
+            // Put the lobby in `LobbyStatus::Finish` after the play time
+            // of the game mode.
+            lobby.start_round_timer(play_time, &app.tx);
+        }
+        AppMessage::SendLobbyPlayerCountUpdate { lobby_id } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let count = LobbyPlayerCount {
+                lobby_id,
+                player_count: lobby.players.len(),
+                max_players: lobby.max_players,
+            };
+            app.clients
+                .broadcast(BackendMessage::UpdateLobbyPlayerCount(count));
+        }
+        AppMessage::SendLobbyAvailabilityUpdate { lobby_id } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let availability = LobbyAvailability {
+                lobby_id,
+                is_full: !lobby.has_free_seat(),
+                player_count: lobby.players.len(),
+                max_players: lobby.max_players,
+            };
+            app.clients
+                .broadcast(BackendMessage::LobbyAvailabilityChanged(availability));
+        }
+        AppMessage::SendSpectatorCount { lobby_id } => {
+            app.send_spectator_count(lobby_id);
+        }
+        AppMessage::SendLobbyStatusUpdate { lobby_id } => {
+            let Some(lobby) = app.lobbies.get(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            app.clients.broadcast(BackendMessage::UpdateLobbyStatus {
+                id: lobby_id,
+                status: lobby.status.clone(),
+            });
+        }
+        AppMessage::Finish { lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let LobbyStatus::InProgress(round_end) = lobby.status else {
+                return;
+            };
+            // Pausing moves the round end, which outdates running timers.
+            let is_outdated = (round_end - Utc::now())
+                .to_std()
+                .is_ok_and(|remaining| remaining > ROUND_TIMER_TOLERANCE);
+            if lobby.paused_at.is_some() || is_outdated {
+                return;
+            }
+            // Tell players about the final scores of the time attack round
+            // and who did not finish the race.
+            match lobby.game_mode {
+                GameMode::Race => lobby.mark_unfinished_players(),
+                GameMode::TimeAttack => lobby.announce_final_scores(),
+            }
+            // Sum up the round in a single message.
+            lobby.broadcast(lobby.round_summary());
+            // Let players compare their solution against the goal and the
+            // one of the winner.
+            lobby.reveal_goals();
+            if lobby.game_mode == GameMode::Race {
+                lobby.reveal_winning_solution();
+            }
+
+            lobby.status = LobbyStatus::Finish(Utc::now() + LOBBY_FINISH_TIME);
+            // Tell clients about the finished lobby.
+            let _ = app
+                .tx
+                .send(AppMessage::SendLobbyStatusUpdate { lobby_id: lobby.id });
+            // Tell players in the lobby about the status update.
+            lobby.broadcast(BackendMessage::StatusUpdate {
+                status: lobby.status.clone(),
+            });
+
+            // Put the lobby in `LobbyStatus::WaitingForPlayers` after two minutes.
+            let app_tx = app.tx.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(LOBBY_FINISH_TIME).await;
+                let _ = app_tx.send(AppMessage::Reset { lobby_id });
+            });
+        }
+        AppMessage::Reset { lobby_id } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+
+            lobby.reset_round(&app.tx);
+        }
+        AppMessage::ComputePlayerProgress {
+            lobby_id,
+            player_id,
+            progress,
+            received_at,
+        } => {
+            let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
+                error!("Lobby with ID {} was not found.", lobby_id);
+                return;
+            };
+            let finished_player_count = lobby.finished_player_count();
+            let Some(player) = lobby.players.get(&player_id) else {
+                error!(
+                    "Player with ID {} was not found in lobby {}.",
+                    player_id, lobby.name
+                );
+                return;
+            };
+
+            if player.waiting {
+                warn!(
+                    "Waiting player {} tried to progress in lobby {}.",
+                    player.name, lobby.name
+                );
+                return;
             }
-            AppMessage::ComputePlayerProgress {
+
+            // We only allow players to progress when the lobby is currently
+            // in progress.
+            let LobbyStatus::InProgress(_) = lobby.status else {
+                warn!(
+                    "Player {} tried to progress in lobby {} that is not in progress.",
+                    player.name, lobby.name
+                );
+                return;
+            };
+            let Some(challenge_files) = lobby.challenge_files_of(player) else {
+                warn!(
+                    "Player {} tried to progress in lobby {} after completing all challenges.",
+                    player.name, lobby.name
+                );
+                return;
+            };
+
+            // Relay the file to all clients watching this player.
+            let target = SpectatorTarget {
                 lobby_id,
                 player_id,
-                progress,
-            } => {
-                let Some(lobby) = app.lobbies.get_mut(&lobby_id) else {
-                    error!("Lobby with ID {} was not found.", lobby_id);
-                    continue;
-                };
-                let finished_player_count = lobby
-                    .players
-                    .values()
-                    .filter(|player| player.progress == 1.0)
-                    .count();
-                let Some(player) = lobby.players.get_mut(&player_id) else {
-                    error!(
-                        "Player with ID {} was not found in lobby {}.",
-                        player_id, lobby.name
-                    );
-                    continue;
-                };
+            };
+            for (client_id, _) in app
+                .spectators
+                .iter()
+                .filter(|(_, spectated)| **spectated == target)
+            {
+                let _ = app.clients.send(
+                    *client_id,
+                    BackendMessage::SpectatorFrame {
+                        player_id,
+                        file: progress.clone(),
+                    },
+                );
+            }
 
-                if player.waiting {
-                    warn!(
-                        "Waiting player {} tried to progress in lobby {}.",
-                        player.name, lobby.name
-                    );
-                    continue;
+            // Compute the levenshtein distance between goal and player
+            // file.
+            let file = progress;
+            let progress = if lobby.compares_bytes() {
+                Ok(compute_binary_progress(&challenge_files.goal_file, &file))
+            } else {
+                compute_progress(
+                    &challenge_files.goal_file,
+                    &file,
+                    lobby.normalizer().as_ref(),
+                )
+            };
+            let progress = match progress {
+                Ok(progress) => progress,
+                Err(e) => {
+                    error!("Error computing player progress: {e}");
+                    return;
                 }
+            };
+            let Some(player) = lobby.players.get_mut(&player_id) else {
+                return;
+            };
+            player.progress = progress;
+            let player_name = player.name.clone();
 
-                // We only allow players to progress when the lobby is currently
-                // in progress.
-                let LobbyStatus::InProgress(_) = lobby.status else {
-                    warn!(
-                        "Player {} tried to progress in lobby {} that is not in progress.",
-                        player.name, lobby.name
-                    );
-                    continue;
-                };
-                let goal_file = match std::str::from_utf8(&lobby.challenge_files.goal_file) {
-                    Ok(goal_file) => goal_file,
-                    Err(e) => {
-                        error!("Error converting goal file bytes to string: {e}");
-                        continue;
-                    }
-                };
-                let player_file = match std::str::from_utf8(&progress) {
-                    Ok(player_file) => player_file,
-                    Err(e) => {
-                        error!("Error converting player file bytes to string: {e}");
-                        continue;
-                    }
-                };
-
-                // Compute the levenshtein distance between goal and player
-                // file.
-                let progress = normalized_levenshtein(goal_file, player_file);
-                player.progress = progress;
+            // Tell players in the lobby about the progress update of this
+            // player.
+            lobby.broadcast(BackendMessage::UpdatePlayerProgress {
+                player_id,
+                progress,
+            });
+            lobby.update_round_roster();
 
+            if !progress.eq(&1.0) {
+                return;
+            }
+            match lobby.game_mode {
                 // If a player won we reduce the lobby lifetime and tell all
                 // players about it.
-                if progress.eq(&1.0) {
-                    lobby.status = LobbyStatus::InProgress(Utc::now() + REDUCED_LOBBY_PLAY_TIME);
-                    let app_tx = app.tx.clone();
-                    tokio::spawn(async move {
-                        tokio::time::sleep(REDUCED_LOBBY_PLAY_TIME).await;
-                        let _ = app_tx.send(AppMessage::Finish { lobby_id });
-                    });
-
-                    // Tell players that the player finished.
-                    let message = format!(
-                        "Player {} finished in position {}!",
-                        player.name,
-                        finished_player_count + 1
-                    );
-                    lobby.broadcast(BackendMessage::SendMessage(message));
+                GameMode::Race => {
+                    lobby.finish_race(&player_name, finished_player_count + 1, &app.tx);
+                    lobby.record_finish(player_id, received_at);
+                    if finished_player_count == 0 {
+                        lobby.record_winning_solution(player_id, file);
+                    }
+
+                    // Results of the warmup round do not count.
+                    if lobby.is_warmup_round {
+                        return;
+                    }
 
-                    // Tell players in the lobby about the status update.
-                    lobby.broadcast(BackendMessage::StatusUpdate {
-                        status: lobby.status.clone(),
-                    });
+                    // Check whether the player beat the challenge record.
+                    // The solve time only depends on when the backend
+                    // started the round and received the solution.
+                    let Some(solve_time) = lobby
+                        .started_at
+                        .and_then(|started_at| (received_at - started_at).to_std().ok())
+                    else {
+                        return;
+                    };
+                    if solve_time < app.min_solve_time {
+                        warn!(
+                            "Flagged solve of player {} ({}) in {:.1}s for challenge {} in lobby {}, below the floor of {}s. Not recording it.",
+                            player_name,
+                            player_id,
+                            solve_time.as_secs_f64(),
+                            lobby.challenge.id,
+                            lobby_id,
+                            app.min_solve_time.as_secs()
+                        );
+                        return;
+                    }
+                    if let Some(record) =
+                        app.records
+                            .submit(&lobby.challenge.id, solve_time, &player_name)
+                    {
+                        info!(
+                            "Player {} set a new record of {:.1}s for challenge {}.",
+                            player_name,
+                            solve_time.as_secs_f64(),
+                            lobby.challenge.id
+                        );
+                        lobby.broadcast(BackendMessage::NewRecord(record.clone()));
+                    }
                 }
+                // In time attack the shared timer keeps running, the
+                // player just moves on to the next challenge.
+                GameMode::TimeAttack => lobby.advance_time_attack_challenge(player_id),
+            }
+        }
+    }
+}
 
-                // Tell players in the lobby about the progress update of this
-                // player.
-                lobby.broadcast(BackendMessage::UpdatePlayerProgress {
-                    player_id,
-                    progress,
-                });
+#[cfg(test)]
+mod tests {
+    use tokio::sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver},
+        oneshot,
+    };
+
+    use common::{PlayerRoundState, RoundState};
+
+    use super::*;
+    use crate::config::Config;
+
+    fn test_app() -> App {
+        let (tx, rx) = unbounded_channel();
+        App::new(tx, rx, &Config::default()).unwrap()
+    }
+
+    /// Handles the message together with everything the app queued up in
+    /// response.
+    fn process(app: &mut App, msg: AppMessage) {
+        handle_message(app, msg);
+        process_pending(app);
+    }
+
+    /// Handles everything the app queued up so far, e.g., once a timer fired.
+    fn process_pending(app: &mut App) {
+        while let Ok(msg) = app.rx.try_recv() {
+            handle_message(app, msg);
+        }
+    }
+
+    /// Adds new players to the lobby, each joining from a client of its own.
+    /// Messages to the player and its client arrive on the same receiver.
+    fn join(
+        app: &mut App,
+        lobby_id: Uuid,
+        count: usize,
+    ) -> Vec<(Player, UnboundedReceiver<BackendMessage>)> {
+        (0..count)
+            .map(|_| {
+                let client_id = Uuid::new_v4();
+                let (player, rx) = test_player(Some(client_id));
+                app.add_client(client_id, player.tx.clone());
+                process(
+                    app,
+                    AppMessage::AddPlayerToLobby {
+                        lobby_id,
+                        player: player.clone(),
+                    },
+                );
+                (player, rx)
+            })
+            .collect()
+    }
+
+    fn test_player(client_id: Option<Uuid>) -> (Player, UnboundedReceiver<BackendMessage>) {
+        let (tx, rx) = unbounded_channel();
+        let mut player = Player::new(tx);
+        player.client_id = client_id;
+        (player, rx)
+    }
+
+    fn received(rx: &mut UnboundedReceiver<BackendMessage>) -> Vec<BackendMessage> {
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    #[tokio::test]
+    async fn client_joins_a_single_lobby_at_a_time() {
+        let mut app = test_app();
+        let first_lobby_id = app.create_new_lobby().unwrap();
+        let second_lobby_id = app.create_new_lobby().unwrap();
+        let client_id = Uuid::new_v4();
+        let (client_tx, _client_rx) = unbounded_channel();
+        app.add_client(client_id, client_tx);
+        let (first, _first_rx) = test_player(Some(client_id));
+        let (second, mut second_rx) = test_player(Some(client_id));
+
+        process(
+            &mut app,
+            AppMessage::AddPlayerToLobby {
+                lobby_id: first_lobby_id,
+                player: first.clone(),
+            },
+        );
+        process(
+            &mut app,
+            AppMessage::AddPlayerToLobby {
+                lobby_id: second_lobby_id,
+                player: second.clone(),
+            },
+        );
+        assert!(app.lobbies[&first_lobby_id].players.contains_key(&first.id));
+        assert!(app.lobbies[&second_lobby_id].players.is_empty());
+        assert!(received(&mut second_rx)
+            .iter()
+            .any(|msg| matches!(msg, BackendMessage::AlreadyInLobby)));
+
+        // Leaving the first lobby clears the mapping.
+        process(
+            &mut app,
+            AppMessage::RemovePlayer {
+                player: first,
+                lobby_id: first_lobby_id,
+            },
+        );
+        process(
+            &mut app,
+            AppMessage::AddPlayerToLobby {
+                lobby_id: second_lobby_id,
+                player: second.clone(),
+            },
+        );
+        assert!(app.lobbies[&second_lobby_id]
+            .players
+            .contains_key(&second.id));
+    }
+
+    #[tokio::test]
+    async fn joins_need_a_connected_client() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let (anonymous, mut anonymous_rx) = test_player(None);
+        let (unknown, mut unknown_rx) = test_player(Some(Uuid::new_v4()));
+
+        for (player, rx) in [(anonymous, &mut anonymous_rx), (unknown, &mut unknown_rx)] {
+            process(&mut app, AppMessage::AddPlayerToLobby { lobby_id, player });
+            assert!(received(rx)
+                .iter()
+                .any(|msg| matches!(msg, BackendMessage::UnknownClient)));
+        }
+        assert!(app.lobbies[&lobby_id].players.is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_stays_connected_after_leaving_a_lobby() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let client_id = Uuid::new_v4();
+        let (client_tx, _client_rx) = unbounded_channel();
+        process(
+            &mut app,
+            AppMessage::AddClient {
+                client_id,
+                client_tx,
+            },
+        );
+        let (leaving, _leaving_rx) = test_player(Some(client_id));
+        process(
+            &mut app,
+            AppMessage::AddPlayerToLobby {
+                lobby_id,
+                player: leaving.clone(),
+            },
+        );
+
+        process(
+            &mut app,
+            AppMessage::RemovePlayer {
+                player: leaving,
+                lobby_id,
+            },
+        );
+        assert!(app.clients.contains(&client_id));
+        assert!(!app.client_lobbies.contains_key(&client_id));
+
+        // The client can join again right away.
+        let (rejoining, _rejoining_rx) = test_player(Some(client_id));
+        process(
+            &mut app,
+            AppMessage::AddPlayerToLobby {
+                lobby_id,
+                player: rejoining.clone(),
+            },
+        );
+        assert!(app.lobbies[&lobby_id].players.contains_key(&rejoining.id));
+    }
+
+    #[tokio::test]
+    async fn leaving_frees_the_seat_while_a_lost_connection_holds_it() {
+        let mut app = test_app();
+        assert!(!app.reconnect_grace.is_zero());
+        let lobby_id = app.create_new_lobby().unwrap();
+        let mut players = join(&mut app, lobby_id, 2);
+        let (leaving, _leaving_rx) = players.remove(0);
+        let (disconnecting, _disconnecting_rx) = players.remove(0);
+
+        process(
+            &mut app,
+            AppMessage::RemovePlayer {
+                player: leaving.clone(),
+                lobby_id,
+            },
+        );
+        process(
+            &mut app,
+            AppMessage::DisconnectPlayer {
+                player: disconnecting.clone(),
+                lobby_id,
+            },
+        );
+        let lobby = &app.lobbies[&lobby_id];
+        assert!(!lobby.players.contains_key(&leaving.id));
+        assert!(!app.player_lobbies.contains_key(&leaving.id));
+        assert!(lobby.players[&disconnecting.id].disconnected_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn ready_states_reset_once_the_ready_phase_times_out() {
+        let mut app = test_app();
+        // Long enough that the spawned timers never fire, the test expires the
+        // ready phase itself.
+        app.ready_timeout = Duration::from_secs(60);
+        let lobby_id = app.create_new_lobby().unwrap();
+        let mut players = join(&mut app, lobby_id, 3);
+        let is_ready =
+            |app: &App, player: &Player| app.lobbies[&lobby_id].players[&player.id].ready;
+
+        let first = players[0].0.clone();
+        let second = players[1].0.clone();
+        process(
+            &mut app,
+            AppMessage::ToggleReady {
+                player: first.clone(),
+                lobby_id,
+            },
+        );
+        // Another player getting ready restarts the timer.
+        let expired = Instant::now() - app.ready_timeout;
+        app.lobbies.get_mut(&lobby_id).unwrap().ready_since = Some(expired);
+        process(
+            &mut app,
+            AppMessage::ToggleReady {
+                player: second.clone(),
+                lobby_id,
+            },
+        );
+        process(&mut app, AppMessage::ExpireReadyPhase { lobby_id });
+        assert!(is_ready(&app, &first) && is_ready(&app, &second));
+
+        app.lobbies.get_mut(&lobby_id).unwrap().ready_since = Some(expired);
+        process(&mut app, AppMessage::ExpireReadyPhase { lobby_id });
+        assert!(!is_ready(&app, &first) && !is_ready(&app, &second));
+        let (_, third_rx) = &mut players[2];
+        assert!(received(third_rx)
+            .iter()
+            .any(|msg| matches!(msg, BackendMessage::ReadyTimeout)));
+    }
+
+    #[tokio::test]
+    async fn superseded_connection_closing_keeps_the_seat() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let (old_player, mut old_rx) = join(&mut app, lobby_id, 1).remove(0);
+        let token = app.lobbies[&lobby_id].players[&old_player.id].reconnect_token;
+        let player_count = app.connection_counts().1;
+
+        let (tx, _rx) = unbounded_channel();
+        let (reply, mut resumed) = oneshot::channel();
+        process(
+            &mut app,
+            AppMessage::ResumePlayer {
+                lobby_id,
+                token,
+                tx,
+                reply,
+            },
+        );
+        assert!(resumed.try_recv().unwrap().is_some());
+        assert!(received(&mut old_rx).iter().any(|msg| matches!(
+            msg,
+            BackendMessage::CloseConnection {
+                reason: Some(CloseReason::Superseded)
             }
+        )));
+
+        // The old socket closes only after the new connection took over.
+        process(
+            &mut app,
+            AppMessage::DisconnectPlayer {
+                player: old_player.clone(),
+                lobby_id,
+            },
+        );
+        process(
+            &mut app,
+            AppMessage::RemovePlayer {
+                player: old_player.clone(),
+                lobby_id,
+            },
+        );
+        let player = &app.lobbies[&lobby_id].players[&old_player.id];
+        assert!(player.disconnected_at.is_none());
+        assert_eq!(app.connection_counts().1, player_count);
+        assert_eq!(app.player_lobbies.get(&old_player.id), Some(&lobby_id));
+    }
+
+    #[tokio::test]
+    async fn reconnecting_client_keeps_its_id_while_still_connected() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let (player, _player_rx) = join(&mut app, lobby_id, 1).remove(0);
+        let client_id = Uuid::new_v4();
+        let (client_tx, mut client_rx) = unbounded_channel();
+        process(
+            &mut app,
+            AppMessage::AddClient {
+                client_id,
+                client_tx,
+            },
+        );
+        process(
+            &mut app,
+            AppMessage::SpectatePlayer {
+                client_id,
+                lobby_id,
+                target_id: player.id,
+            },
+        );
+        let token = received(&mut client_rx)
+            .into_iter()
+            .find_map(|msg| match msg {
+                BackendMessage::ProvideSpectatorToken { token } => Some(token),
+                _ => None,
+            })
+            .unwrap();
+        let client_count = app.clients.len();
+
+        let (client_tx, _client_rx) = unbounded_channel();
+        let (reply, mut reconnected) = oneshot::channel();
+        process(
+            &mut app,
+            AppMessage::ReconnectSpectator {
+                client_id: Uuid::new_v4(),
+                token,
+                client_tx,
+                reply,
+            },
+        );
+        assert_eq!(reconnected.try_recv().unwrap(), (client_id, true));
+        assert_eq!(app.clients.len(), client_count);
+        assert_eq!(app.spectators[&client_id].player_id, player.id);
+    }
+
+    #[tokio::test]
+    async fn race_ends_after_the_finish_grace_with_dnfs() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let mut players = join(&mut app, lobby_id, 2);
+        let (finisher, _) = players.remove(0);
+        let (straggler, mut straggler_rx) = players.remove(0);
+        let lobby = app.lobbies.get_mut(&lobby_id).unwrap();
+        lobby.finish_grace = Duration::from_millis(100);
+        lobby.status = LobbyStatus::InProgress(Utc::now() + chrono::Duration::minutes(10));
+        lobby.start_round();
+
+        lobby.players.get_mut(&finisher.id).unwrap().progress = 1.0;
+        lobby.update_round_roster();
+        lobby.finish_race(&finisher.name, 1, &app.tx);
+        let LobbyStatus::InProgress(round_end) = lobby.status else {
+            panic!("The round ended before the finish grace.");
+        };
+        assert!(round_end <= Utc::now() + chrono::Duration::milliseconds(100));
+        assert!(received(&mut straggler_rx)
+            .iter()
+            .any(|msg| matches!(msg, BackendMessage::RoundEndingSoon { .. })));
+
+        // The grace is shorter than the timer tolerance, so the round timer
+        // can fire right away.
+        process(&mut app, AppMessage::Finish { lobby_id });
+        let lobby = &app.lobbies[&lobby_id];
+        assert!(matches!(lobby.status, LobbyStatus::Finish(_)));
+        let state_of = |player_id| {
+            lobby
+                .round_roster
+                .iter()
+                .find(|entry| entry.player_id == player_id)
+                .map(|entry| entry.state)
+        };
+        assert_eq!(state_of(finisher.id), Some(RoundState::Finished));
+        assert_eq!(state_of(straggler.id), Some(RoundState::DidNotFinish));
+        assert!(received(&mut straggler_rx).iter().any(|msg| matches!(
+            msg,
+            BackendMessage::SendMessage(message) if message.text.starts_with("Did not finish")
+        )));
+    }
+
+    #[tokio::test]
+    async fn burst_of_joins_seats_at_most_the_maximum() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let max_players = 3;
+        app.lobbies.get_mut(&lobby_id).unwrap().max_players = max_players;
+        let players = (0..=max_players)
+            .map(|_| {
+                let client_id = Uuid::new_v4();
+                let (player, rx) = test_player(Some(client_id));
+                app.add_client(client_id, player.tx.clone());
+                (player, rx)
+            })
+            .collect::<Vec<_>>();
+        // Queue all joins before handling any of them.
+        for (player, _) in players.iter() {
+            let _ = app.tx.send(AppMessage::AddPlayerToLobby {
+                lobby_id,
+                player: player.clone(),
+            });
+        }
+        process_pending(&mut app);
+
+        let lobby = &app.lobbies[&lobby_id];
+        assert_eq!(lobby.players.len(), max_players);
+        let mut rejected = 0;
+        for (player, mut rx) in players {
+            let is_full = received(&mut rx)
+                .iter()
+                .any(|msg| matches!(msg, BackendMessage::LobbyFull));
+            assert_eq!(is_full, !lobby.players.contains_key(&player.id));
+            rejected += usize::from(is_full);
+        }
+        assert_eq!(rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn roster_is_only_sent_to_members() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let other_lobby_id = app.create_new_lobby().unwrap();
+        let (member, mut member_rx) = join(&mut app, lobby_id, 2).remove(0);
+        let (outsider, mut outsider_rx) = join(&mut app, other_lobby_id, 1).remove(0);
+        received(&mut member_rx);
+        received(&mut outsider_rx);
+        let lobby = app.lobbies.get_mut(&lobby_id).unwrap();
+        lobby.players.get_mut(&member.id).unwrap().ready = true;
+        lobby.round_roster = vec![PlayerRoundState {
+            player_id: member.id,
+            state: RoundState::Finished,
+        }];
+
+        process(
+            &mut app,
+            AppMessage::SendRoster {
+                player: member.clone(),
+                lobby_id,
+            },
+        );
+        let messages = received(&mut member_rx);
+        let [BackendMessage::Roster(roster)] = messages.as_slice() else {
+            panic!("The member did not receive the roster.");
+        };
+        let lobby = &app.lobbies[&lobby_id];
+        assert_eq!(roster.len(), lobby.players.len());
+        for entry in roster {
+            let player = &lobby.players[&entry.player.id];
+            assert_eq!(entry.player.ready, player.ready);
+            let round_state = (player.id == member.id).then_some(RoundState::Finished);
+            assert_eq!(entry.round_state, round_state);
         }
+
+        process(
+            &mut app,
+            AppMessage::SendRoster {
+                player: outsider,
+                lobby_id,
+            },
+        );
+        assert!(received(&mut outsider_rx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn reconnecting_spectator_resumes_the_same_target() {
+        let mut app = test_app();
+        let lobby_id = app.create_new_lobby().unwrap();
+        let (watched, _watched_rx) = join(&mut app, lobby_id, 1).remove(0);
+        let client_id = Uuid::new_v4();
+        let (client_tx, mut client_rx) = unbounded_channel();
+        process(
+            &mut app,
+            AppMessage::AddClient {
+                client_id,
+                client_tx: client_tx.clone(),
+            },
+        );
+        process(
+            &mut app,
+            AppMessage::SpectatePlayer {
+                client_id,
+                lobby_id,
+                target_id: watched.id,
+            },
+        );
+        let token = received(&mut client_rx)
+            .into_iter()
+            .find_map(|msg| match msg {
+                BackendMessage::ProvideSpectatorToken { token } => Some(token),
+                _ => None,
+            })
+            .unwrap();
+
+        process(
+            &mut app,
+            AppMessage::RemoveClient {
+                client_id,
+                client_tx,
+            },
+        );
+        assert!(app.held_spectators.contains_key(&token));
+
+        let reconnected_id = Uuid::new_v4();
+        let (client_tx, _client_rx) = unbounded_channel();
+        let (reply, mut reconnected) = oneshot::channel();
+        process(
+            &mut app,
+            AppMessage::ReconnectSpectator {
+                client_id: reconnected_id,
+                token,
+                client_tx,
+                reply,
+            },
+        );
+        assert_eq!(reconnected.try_recv().unwrap(), (reconnected_id, true));
+        assert_eq!(
+            app.spectators[&reconnected_id],
+            SpectatorTarget {
+                lobby_id,
+                player_id: watched.id,
+            }
+        );
+        assert!(app.held_spectators.is_empty());
     }
 }
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+/// Backend service of keyglide.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Validates the challenges inside the given directory without starting
+    /// the server. Exits with a non-zero code if any challenge is invalid.
+    Validate {
+        /// Directory holding one folder per challenge.
+        directory: PathBuf,
+    },
+}
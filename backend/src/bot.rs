@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use common::Difficulty;
+use uuid::Uuid;
+
+use crate::constants::{EASY_BOT_FINISH_TIME, HARD_BOT_FINISH_TIME, MEDIUM_BOT_FINISH_TIME};
+
+/// A virtual opponent for solo practice. The bot has no editor, it progresses
+/// linearly and finishes the challenge after a fixed time depending on its
+/// difficulty.
+#[derive(Clone, Debug)]
+pub struct Bot {
+    pub id: Uuid,
+    pub name: String,
+    pub difficulty: Difficulty,
+    pub progress: f64,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: format!("Bot ({difficulty})"),
+            difficulty,
+            progress: 0.0,
+        }
+    }
+
+    /// # Finish time
+    ///
+    /// Returns the time the bot needs to finish the challenge.
+    pub fn finish_time(&self) -> Duration {
+        match self.difficulty {
+            Difficulty::Easy => EASY_BOT_FINISH_TIME,
+            Difficulty::Medium => MEDIUM_BOT_FINISH_TIME,
+            Difficulty::Hard => HARD_BOT_FINISH_TIME,
+        }
+    }
+
+    pub fn to_common_player(&self) -> common::Player {
+        common::Player {
+            id: self.id,
+            name: self.name.clone(),
+            progress: self.progress,
+            waiting: false,
+            ready: true,
+            score: 0,
+            bot: Some(self.difficulty),
+            muted: false,
+            joined_at: None,
+        }
+    }
+}
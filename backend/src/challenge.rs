@@ -0,0 +1,275 @@
+use std::{collections::BTreeMap, fs, path::Path, time::Duration};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use common::{ChallengeFiles, ChallengeInformation, Comparison, Difficulty};
+
+use crate::{
+    comparison::{compute_binary_progress, compute_progress, normalizer},
+    constants::{DEFAULT_CHALLENGE_ID, MAX_CHALLENGE_FILE_SIZE},
+};
+
+/// A race challenge players can pick for their lobby.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Challenge {
+    pub id: String,
+    pub name: String,
+    /// Programming language of the challenge, used to filter the lobby list.
+    pub language: String,
+    pub difficulty: Difficulty,
+    pub description: Option<String>,
+    /// How player files are compared against the goal file.
+    pub comparison: Comparison,
+    /// How long races of this challenge last. Falls back to the round time
+    /// limit of the backend if not set.
+    pub time_limit: Option<Duration>,
+    /// Whether the files are binary. They are compared byte by byte, no
+    /// matter the comparison.
+    pub binary: bool,
+    pub files: ChallengeFiles,
+}
+
+/// Describes a challenge inside the `challenge.toml` of its folder.
+#[derive(Deserialize)]
+struct ChallengeManifest {
+    name: String,
+    language: String,
+    difficulty: Difficulty,
+    /// Short summary shown in the challenge list.
+    description: Option<String>,
+    /// File extension of the start and goal file, e.g., `rs`.
+    extension: String,
+    /// Skips the UTF-8 validation of the start and goal file.
+    #[serde(default)]
+    binary: bool,
+    /// Either `exact`, `ignore-whitespace` (default) or `tokens`.
+    #[serde(default)]
+    comparison: Comparison,
+    /// Time limit of races in seconds.
+    time_limit: Option<u64>,
+}
+
+impl Challenge {
+    /// # Load challenge
+    ///
+    /// Reads the manifest together with the start and goal file of the
+    /// challenge inside the given folder. The folder name is used as ID. Both
+    /// files have to be valid UTF-8, unless the challenge opts into binary
+    /// files, and must not exceed `MAX_CHALLENGE_FILE_SIZE`. A start file that
+    /// already matches the goal file under the comparison of the challenge is
+    /// rejected as well.
+    pub fn load(folder: &Path) -> Result<Self> {
+        let Some(id) = folder.file_name().and_then(|name| name.to_str()) else {
+            return Err(anyhow!("Invalid challenge folder {}.", folder.display()));
+        };
+        let manifest = fs::read_to_string(folder.join("challenge.toml"))
+            .map_err(|e| anyhow!("Error reading challenge manifest: {e}"))?;
+        let manifest: ChallengeManifest = toml::from_str(&manifest)
+            .map_err(|e| anyhow!("Error parsing challenge manifest: {e}"))?;
+        if manifest.time_limit == Some(0) {
+            return Err(anyhow!(
+                "The time limit of challenge {id} has to be positive."
+            ));
+        }
+
+        let read_file = |name: &str| -> Result<Vec<u8>> {
+            let path = folder.join(format!("{name}.{}", manifest.extension));
+            let file =
+                fs::read(&path).map_err(|e| anyhow!("Error reading {}: {e}", path.display()))?;
+            if file.len() > MAX_CHALLENGE_FILE_SIZE {
+                return Err(anyhow!(
+                    "{} has {} bytes, at most {MAX_CHALLENGE_FILE_SIZE} are allowed.",
+                    path.display(),
+                    file.len()
+                ));
+            }
+            if !manifest.binary {
+                if let Err(e) = std::str::from_utf8(&file) {
+                    return Err(anyhow!("{} is not valid UTF-8: {e}", path.display()));
+                }
+            }
+            Ok(file)
+        };
+        let start_file = read_file("start")?;
+        let goal_file = read_file("goal")?;
+        // Everyone would win the race without a single keystroke. Binary
+        // files can only be compared byte by byte.
+        let solved = if manifest.binary {
+            compute_binary_progress(&goal_file, &start_file).eq(&1.0)
+        } else {
+            let normalizer = normalizer(manifest.comparison, &manifest.language);
+            compute_progress(&goal_file, &start_file, normalizer.as_ref())?.eq(&1.0)
+        };
+        if solved {
+            return Err(anyhow!(
+                "The start file of challenge {id} already matches the goal file ({:?} comparison).",
+                manifest.comparison
+            ));
+        }
+
+        Ok(Self {
+            id: id.to_string(),
+            name: manifest.name,
+            language: manifest.language,
+            difficulty: manifest.difficulty,
+            description: manifest.description,
+            comparison: manifest.comparison,
+            time_limit: manifest.time_limit.map(Duration::from_secs),
+            binary: manifest.binary,
+            files: ChallengeFiles {
+                start_file,
+                goal_file,
+            },
+        })
+    }
+
+    pub fn to_information(&self) -> ChallengeInformation {
+        ChallengeInformation {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            language: self.language.clone(),
+            difficulty: self.difficulty,
+            description: self.description.clone(),
+        }
+    }
+}
+
+/// # Load catalog
+///
+/// Loads every challenge folder inside the given directory. Malformed
+/// challenges are skipped, unless `current` holds a challenge with the same
+/// ID, which is kept instead. This way a broken edit never removes a working
+/// challenge.
+pub fn load_catalog(
+    directory: &Path,
+    current: &BTreeMap<String, Challenge>,
+) -> Result<BTreeMap<String, Challenge>> {
+    let mut catalog = BTreeMap::new();
+    for entry in fs::read_dir(directory)? {
+        let folder = entry?.path();
+        if !folder.is_dir() {
+            continue;
+        }
+        match Challenge::load(&folder) {
+            Ok(challenge) => {
+                catalog.insert(challenge.id.clone(), challenge);
+            }
+            Err(e) => {
+                error!("Rejecting challenge {}: {e}", folder.display());
+                let id = folder.file_name().and_then(|name| name.to_str());
+                if let Some(challenge) = id.and_then(|id| current.get(id)) {
+                    catalog.insert(challenge.id.clone(), challenge.clone());
+                }
+            }
+        }
+    }
+    Ok(catalog)
+}
+
+/// # Check catalog
+///
+/// Makes sure the catalog loaded at startup can be served. Fails if not a
+/// single valid challenge loaded, as no lobby could be created. Only warns if
+/// fewer than `min_challenges` loaded or the default challenge is missing.
+pub fn check_catalog(
+    catalog: &BTreeMap<String, Challenge>,
+    directory: &Path,
+    min_challenges: usize,
+) -> Result<()> {
+    if catalog.is_empty() {
+        return Err(anyhow!(
+            "No valid challenge loaded from {}. Check it with `backend validate {}`.",
+            directory.display(),
+            directory.display()
+        ));
+    }
+    if catalog.len() < min_challenges {
+        warn!(
+            "Only {} challenge(s) loaded from {}, expected at least {min_challenges}.",
+            catalog.len(),
+            directory.display()
+        );
+    }
+    if !catalog.contains_key(DEFAULT_CHALLENGE_ID) {
+        warn!(
+            "Default challenge {DEFAULT_CHALLENGE_ID} is missing, new lobbies start with {} instead.",
+            catalog.keys().next().expect("Catalog is not empty")
+        );
+    }
+    Ok(())
+}
+
+/// # Log catalog changes
+///
+/// Logs which challenges were added, removed, or changed between the current
+/// and the new catalog.
+pub fn log_catalog_changes(
+    current: &BTreeMap<String, Challenge>,
+    new: &BTreeMap<String, Challenge>,
+) {
+    let added = new
+        .keys()
+        .filter(|id| !current.contains_key(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+    let removed = current
+        .keys()
+        .filter(|id| !new.contains_key(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+    let changed = new
+        .iter()
+        .filter(|(id, challenge)| {
+            current
+                .get(*id)
+                .is_some_and(|current| current.ne(challenge))
+        })
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<_>>();
+    info!(
+        "Reloaded {} challenge(s). Added: [{}]. Removed: [{}]. Changed: [{}].",
+        new.len(),
+        added.join(", "),
+        removed.join(", "),
+        changed.join(", ")
+    );
+}
+
+/// # Validate catalog
+///
+/// Loads every challenge folder inside the given directory like the backend
+/// does on startup and prints a summary line per challenge. Returns whether
+/// all challenges are valid.
+pub fn validate_catalog(directory: &Path) -> Result<bool> {
+    let mut folders = fs::read_dir(directory)
+        .map_err(|e| anyhow!("Error reading {}: {e}", directory.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    folders.retain(|folder| folder.is_dir());
+    folders.sort();
+
+    let mut invalid = 0;
+    for folder in folders.iter() {
+        let id = folder
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        match Challenge::load(folder) {
+            Ok(challenge) => println!(
+                "ok      {id}: {} ({}, {})",
+                challenge.name, challenge.language, challenge.difficulty
+            ),
+            Err(e) => {
+                invalid += 1;
+                println!("invalid {id}: {e}");
+            }
+        }
+    }
+    println!(
+        "{} challenge(s) valid, {invalid} invalid.",
+        folders.len() - invalid
+    );
+    Ok(invalid == 0)
+}
@@ -0,0 +1,110 @@
+use std::{collections::BTreeSet, fmt::Debug, fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tracing::info;
+
+/// What happens to a chat message after it passed a filter.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FilterOutcome {
+    /// The message is broadcast as is.
+    Pass,
+    /// The message is broadcast with offending parts replaced.
+    Mask(String),
+    /// The message is not broadcast at all.
+    Reject,
+}
+
+/// Decides whether chat messages may be broadcast. Implement this to plug in
+/// a more sophisticated filter than the word list.
+pub trait ChatFilter: Debug + Send {
+    fn filter(&self, message: &str) -> FilterOutcome;
+}
+
+/// How the word list filter treats offending messages.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterAction {
+    /// Replaces every character of an offending word with `*`.
+    #[default]
+    Mask,
+    /// Drops messages containing an offending word.
+    Reject,
+}
+
+/// Describes the word list filter inside its TOML file.
+#[derive(Deserialize)]
+struct WordListConfig {
+    #[serde(default)]
+    action: FilterAction,
+    words: Vec<String>,
+}
+
+/// Matches whole words of a message case-insensitively against a word list.
+#[derive(Debug)]
+pub struct WordListFilter {
+    words: BTreeSet<String>,
+    action: FilterAction,
+}
+
+impl WordListFilter {
+    pub fn new(words: impl IntoIterator<Item = String>, action: FilterAction) -> Self {
+        Self {
+            words: words.into_iter().map(|word| word.to_lowercase()).collect(),
+            action,
+        }
+    }
+}
+
+impl ChatFilter for WordListFilter {
+    fn filter(&self, message: &str) -> FilterOutcome {
+        let mut filtered = String::with_capacity(message.len());
+        let mut is_offending = false;
+        let mut word = String::new();
+        // A trailing separator makes sure the last word is checked as well.
+        for c in message.chars().chain(std::iter::once(' ')) {
+            if c.is_alphanumeric() {
+                word.push(c);
+                continue;
+            }
+            if self.words.contains(&word.to_lowercase()) {
+                is_offending = true;
+                filtered.extend(word.chars().map(|_| '*'));
+            } else {
+                filtered.push_str(&word);
+            }
+            word.clear();
+            filtered.push(c);
+        }
+        filtered.pop();
+
+        match (is_offending, self.action) {
+            (false, _) => FilterOutcome::Pass,
+            (true, FilterAction::Mask) => FilterOutcome::Mask(filtered),
+            (true, FilterAction::Reject) => FilterOutcome::Reject,
+        }
+    }
+}
+
+/// # Load chat filter
+///
+/// Loads the word list filter from the TOML file at the given path. Returns
+/// `None` without a path, which leaves the chat unfiltered.
+pub fn load(path: Option<&Path>) -> Result<Option<Box<dyn ChatFilter>>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let config = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Error reading chat filter {}: {e}", path.display()))?;
+    let config: WordListConfig = toml::from_str(&config)
+        .map_err(|e| anyhow!("Error parsing chat filter {}: {e}", path.display()))?;
+    info!(
+        "Filtering chat with {} word(s) from {}.",
+        config.words.len(),
+        path.display()
+    );
+    Ok(Some(Box::new(WordListFilter::new(
+        config.words,
+        config.action,
+    ))))
+}
@@ -0,0 +1,236 @@
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Result};
+use strsim::{generic_levenshtein, normalized_levenshtein};
+
+use common::{normalize::normalize_line_endings, Comparison};
+
+/// # Get normalizer
+///
+/// Returns the normalizer implementing the given comparison for challenges
+/// of the given language.
+pub fn normalizer(comparison: Comparison, language: &str) -> Box<dyn Normalizer> {
+    match comparison {
+        Comparison::Exact => Box::new(ExactNormalizer),
+        Comparison::IgnoreWhitespace => Box::new(WhitespaceNormalizer),
+        Comparison::Tokens => match token_normalizer(language) {
+            Some(normalizer) => Box::new(normalizer),
+            None => Box::new(WhitespaceNormalizer),
+        },
+    }
+}
+
+/// Brings a file into the form it is compared in. Implement this to plug in
+/// another comparison strategy.
+pub trait Normalizer: Debug + Send {
+    fn normalize(&self, file: &str) -> String;
+}
+
+/// Keeps files as they are.
+#[derive(Debug)]
+pub struct ExactNormalizer;
+
+impl Normalizer for ExactNormalizer {
+    fn normalize(&self, file: &str) -> String {
+        file.to_string()
+    }
+}
+
+/// Collapses every run of whitespace into a single space and trims both ends.
+/// Indentation and line breaks do not count, but whether there is whitespace
+/// between two characters does, so `let x` does not match `letx`.
+#[derive(Debug)]
+pub struct WhitespaceNormalizer;
+
+impl Normalizer for WhitespaceNormalizer {
+    fn normalize(&self, file: &str) -> String {
+        file.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Collapses whitespace outside of string and char literals. A single space
+/// is kept between two word characters, e.g., in `let x`, all other
+/// whitespace is dropped. Literals are kept as they are.
+#[derive(Debug)]
+pub struct TokenNormalizer {
+    /// Characters that open and close a string literal.
+    string_delimiters: &'static [char],
+    /// Whether `'` opens a char literal, e.g., `'"'`, or a lifetime, e.g.,
+    /// `'a`, instead of a string literal.
+    char_literals: bool,
+}
+
+impl Normalizer for TokenNormalizer {
+    fn normalize(&self, file: &str) -> String {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let chars = file.chars().collect::<Vec<_>>();
+        let mut normalized = String::with_capacity(file.len());
+        let mut string_delimiter = None;
+        let mut is_escaped = false;
+        let mut has_pending_space = false;
+        let mut index = 0;
+
+        while let Some(&c) = chars.get(index) {
+            index += 1;
+            if let Some(delimiter) = string_delimiter {
+                normalized.push(c);
+                if is_escaped {
+                    is_escaped = false;
+                } else if c == '\\' {
+                    is_escaped = true;
+                } else if c == delimiter {
+                    string_delimiter = None;
+                }
+                continue;
+            }
+            if c.is_whitespace() {
+                has_pending_space = true;
+                continue;
+            }
+            if has_pending_space && normalized.chars().last().is_some_and(is_word) && is_word(c) {
+                normalized.push(' ');
+            }
+            has_pending_space = false;
+            normalized.push(c);
+            if c == '\'' && self.char_literals {
+                // Lifetimes and labels have no closing quote and continue as
+                // a plain word.
+                if let Some(length) = char_literal_length(&chars[index..]) {
+                    normalized.extend(&chars[index..index + length]);
+                    index += length;
+                }
+            } else if self.string_delimiters.contains(&c) {
+                string_delimiter = Some(c);
+            }
+        }
+        normalized
+    }
+}
+
+/// # Get char literal length
+///
+/// Returns the number of characters of the char literal following an opening
+/// `'`, including the closing one. Returns `None` if the quote does not open
+/// a char literal, e.g., for the lifetime `'a`.
+fn char_literal_length(rest: &[char]) -> Option<usize> {
+    match rest {
+        // Escapes like `'\''` or `'\u{1F600}'` end at the next quote.
+        ['\\', _, tail @ ..] => tail
+            .iter()
+            .take_while(|&&c| c != '\n')
+            .position(|&c| c == '\'')
+            .map(|position| position + 3),
+        [c, '\'', ..] if *c != '\'' && *c != '\n' => Some(2),
+        _ => None,
+    }
+}
+
+/// # Get token normalizer
+///
+/// Returns the token normalizer for the string and char literals of the given
+/// language, if the language is known.
+fn token_normalizer(language: &str) -> Option<TokenNormalizer> {
+    let (string_delimiters, char_literals): (&'static [char], bool) =
+        match language.to_lowercase().as_str() {
+            "rust" | "c" | "c++" | "java" | "c#" => (&['"'], true),
+            "javascript" | "typescript" => (&['"', '\'', '`'], false),
+            "go" => (&['"', '`'], true),
+            _ => return None,
+        };
+    Some(TokenNormalizer {
+        string_delimiters,
+        char_literals,
+    })
+}
+
+/// # Compute progress
+///
+/// Computes the normalized levenshtein distance between the goal file and the
+/// current state of a player's start file. A progress of `1.0` means the
+/// player reached the goal.
+///
+/// Line endings of both files are normalized before comparing them, so that
+/// differences between CRLF and LF do not count against the player. Both
+/// files are brought into shape by the normalizer of the challenge
+/// afterwards.
+pub fn compute_progress(
+    goal_file: &[u8],
+    player_file: &[u8],
+    normalizer: &dyn Normalizer,
+) -> Result<f64> {
+    let goal_file = normalize_line_endings(goal_file);
+    let player_file = normalize_line_endings(player_file);
+
+    let goal_file = std::str::from_utf8(&goal_file)
+        .map_err(|e| anyhow!("Error converting goal file bytes to string: {e}"))?;
+    let player_file = std::str::from_utf8(&player_file)
+        .map_err(|e| anyhow!("Error converting player file bytes to string: {e}"))?;
+
+    Ok(normalized_levenshtein(
+        &normalizer.normalize(goal_file),
+        &normalizer.normalize(player_file),
+    ))
+}
+
+/// # Compute binary progress
+///
+/// Computes the normalized levenshtein distance between the bytes of the goal
+/// file and a player's start file. Challenges with binary files can not be
+/// normalized, so every byte counts.
+pub fn compute_binary_progress(goal_file: &[u8], player_file: &[u8]) -> f64 {
+    let length = goal_file.len().max(player_file.len());
+    if length == 0 {
+        return 1.0;
+    }
+    1.0 - generic_levenshtein(&goal_file.to_vec(), &player_file.to_vec()) as f64 / length as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_runs_count_as_a_single_space() {
+        let normalizer = WhitespaceNormalizer;
+        let goal_file = b"fn main() {\n    let x = 1;\n}\n";
+        let indented = b"  fn main() {\r\n\tlet  x = 1;\r\n}";
+        let joined = b"fn main() {\n    letx = 1;\n}\n";
+        assert_eq!(
+            compute_progress(goal_file, indented, &normalizer).unwrap(),
+            1.0
+        );
+        assert!(compute_progress(goal_file, joined, &normalizer).unwrap() < 1.0);
+    }
+
+    #[test]
+    fn binary_files_are_compared_byte_by_byte() {
+        let goal_file = [0xff, 0xfe, 0x00, 0x01];
+        assert_eq!(compute_binary_progress(&goal_file, &goal_file), 1.0);
+        assert_eq!(compute_binary_progress(&goal_file, &[0xff, 0xfe]), 0.5);
+    }
+
+    #[test]
+    fn tokens_ignore_formatting_outside_of_literals() {
+        let normalizer = normalizer(Comparison::Tokens, "Rust");
+        let goal_file = b"fn quote<'a>(s: &'a str) -> char {\n    let c = '\"';\n    let e = '\\'';\n    let s = \"a  b\";\n    c\n}\n";
+        let reformatted = b"fn quote<'a>(s:&'a str)->char{let c='\"';let e='\\'';let s=\"a  b\";c}";
+        assert_eq!(
+            compute_progress(goal_file, reformatted, normalizer.as_ref()).unwrap(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn tokens_detect_differences_in_tokens_and_literals() {
+        let normalizer = normalizer(Comparison::Tokens, "Rust");
+        let goal_file = b"let c = '\"';\nlet x = 1;\nlet s = \"a  b\";\n";
+        for changed in [
+            b"let c = '\"';\nlet y = 1;\nlet s = \"a  b\";\n".as_slice(),
+            b"let c = '\"';\nletx = 1;\nlet s = \"a  b\";\n",
+            b"let c = '\"';\nlet x = 1;\nlet s = \"a b\";\n",
+            b"let c = '\\'';\nlet x = 1;\nlet s = \"a  b\";\n",
+        ] {
+            assert!(compute_progress(goal_file, changed, normalizer.as_ref()).unwrap() < 1.0);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::{env, fmt::Display, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use tracing::info;
+
+use common::{constants::MAX_LOBBY_SIZE, Difficulty};
+
+use crate::{
+    constants::{
+        CHALLENGE_DIRECTORY, CONNECTION_RATE_WINDOW, DEFAULT_CLIENT_IDLE_TIMEOUT,
+        DEFAULT_EMPTY_LOBBY_GRACE, DEFAULT_FINISH_GRACE, DEFAULT_MAX_MESSAGE_SIZE,
+        DEFAULT_MAX_SPECTATORS, DEFAULT_MIN_CHALLENGES, DEFAULT_MIN_SOLVE_TIME,
+        DEFAULT_READY_TIMEOUT, DEFAULT_RECONNECT_GRACE, DEFAULT_ROUND_TIME_LIMIT,
+        DEFAULT_SERVER_TIME_INTERVAL, MAX_CONNECTIONS_PER_IP,
+    },
+    rotation::RotationWeights,
+};
+
+/// Prefix of all environment variables overriding the configuration.
+pub static ENV_PREFIX: &str = "KEYGLIDE_";
+
+/// Settings of the backend service. Every field can be overridden by an
+/// environment variable named after the field in upper snake case with the
+/// `KEYGLIDE_` prefix, e.g., `KEYGLIDE_READY_TIMEOUT` for `ready_timeout`.
+/// Durations are given in seconds.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Port the HTTP and WebSocket routes are served on.
+    pub port: u16,
+    /// Directory holding one folder per race challenge.
+    pub challenge_directory: PathBuf,
+    /// Startup warns if fewer challenges than this loaded. It fails without
+    /// any valid challenge regardless.
+    pub min_challenges: usize,
+    /// Path of the chat filter word list. The chat is not filtered without
+    /// one.
+    pub chat_filter: Option<PathBuf>,
+    /// How long the seat of a disconnected player is held. Zero removes
+    /// disconnected players right away.
+    pub reconnect_grace: Duration,
+    /// How long ready players wait for the others before their ready state is
+    /// reset. Zero keeps ready states forever.
+    pub ready_timeout: Duration,
+    /// How long races last if their challenge has no time limit of its own.
+    pub round_time_limit: Duration,
+    /// How long the other players can still finish a race after the first
+    /// one did.
+    pub finish_grace: Duration,
+    /// Race solves faster than this are flagged for review and not recorded.
+    /// Zero trusts every solve time.
+    pub min_solve_time: Duration,
+    /// How long empty lobbies are kept, so that a reconnecting or rejoining
+    /// player still finds them.
+    pub empty_lobby_grace: Duration,
+    /// Seats of new lobbies, between one and `MAX_LOBBY_SIZE`.
+    pub max_players: usize,
+    /// Spectator cap of new lobbies until the owner changes it.
+    pub max_spectators: usize,
+    /// How long lobby browser clients may stay idle. Zero keeps them forever.
+    pub client_idle_timeout: Duration,
+    /// How often players are sent the server time to sync their clocks with.
+    /// Zero never sends it.
+    pub server_time_interval: Duration,
+    /// How many connections a single IP address can open within the
+    /// connection rate window.
+    pub max_connections_per_ip: usize,
+    /// Size in bytes of the largest WebSocket message accepted from clients
+    /// and players. Connections sending larger ones are closed.
+    pub max_message_size: usize,
+    /// Window of the per IP connection rate limit.
+    pub connection_rate_window: Duration,
+    /// Challenges quickplay lobbies rotate through with their weights, e.g.,
+    /// `hello_world:2,fizzbuzz`. Empty rotates through the whole catalog.
+    pub quickplay_rotation: RotationWeights,
+    /// Limits the quickplay rotation to challenges of this difficulty.
+    pub quickplay_difficulty: Option<Difficulty>,
+    /// How many lobbies with a free seat are kept ready for quickplay, so
+    /// that the first joiners do not wait for a new lobby. Zero creates
+    /// lobbies on demand only.
+    pub prewarmed_lobbies: usize,
+    /// Bearer token of the admin routes. They are disabled without one.
+    pub admin_token: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: 3030,
+            challenge_directory: PathBuf::from(CHALLENGE_DIRECTORY),
+            min_challenges: DEFAULT_MIN_CHALLENGES,
+            chat_filter: None,
+            reconnect_grace: DEFAULT_RECONNECT_GRACE,
+            ready_timeout: DEFAULT_READY_TIMEOUT,
+            round_time_limit: DEFAULT_ROUND_TIME_LIMIT,
+            finish_grace: DEFAULT_FINISH_GRACE,
+            min_solve_time: DEFAULT_MIN_SOLVE_TIME,
+            empty_lobby_grace: DEFAULT_EMPTY_LOBBY_GRACE,
+            max_players: MAX_LOBBY_SIZE,
+            max_spectators: DEFAULT_MAX_SPECTATORS,
+            client_idle_timeout: DEFAULT_CLIENT_IDLE_TIMEOUT,
+            server_time_interval: DEFAULT_SERVER_TIME_INTERVAL,
+            max_connections_per_ip: MAX_CONNECTIONS_PER_IP,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            connection_rate_window: CONNECTION_RATE_WINDOW,
+            quickplay_rotation: RotationWeights::default(),
+            quickplay_difficulty: None,
+            prewarmed_lobbies: 0,
+            admin_token: None,
+        }
+    }
+}
+
+impl Config {
+    /// # Load config
+    ///
+    /// Starts from the defaults and applies all environment variable
+    /// overrides. Fails with the name of the variable if a value can not be
+    /// parsed or is out of range.
+    pub fn load() -> Result<Self> {
+        let mut config = Self::default();
+        override_from_env(&mut config.port, "port")?;
+        override_from_env(&mut config.challenge_directory, "challenge_directory")?;
+        override_from_env(&mut config.min_challenges, "min_challenges")?;
+        if let Some(chat_filter) = parse_env("chat_filter")? {
+            config.chat_filter = Some(chat_filter);
+        }
+        override_seconds_from_env(&mut config.reconnect_grace, "reconnect_grace")?;
+        override_seconds_from_env(&mut config.ready_timeout, "ready_timeout")?;
+        override_seconds_from_env(&mut config.round_time_limit, "round_time_limit")?;
+        if config.round_time_limit.is_zero() {
+            return Err(anyhow!(
+                "{} has to be positive.",
+                env_name("round_time_limit")
+            ));
+        }
+        override_seconds_from_env(&mut config.finish_grace, "finish_grace")?;
+        override_seconds_from_env(&mut config.min_solve_time, "min_solve_time")?;
+        override_seconds_from_env(&mut config.empty_lobby_grace, "empty_lobby_grace")?;
+        override_from_env(&mut config.max_players, "max_players")?;
+        if !(1..=MAX_LOBBY_SIZE).contains(&config.max_players) {
+            return Err(anyhow!(
+                "{} has to be between 1 and {MAX_LOBBY_SIZE}.",
+                env_name("max_players")
+            ));
+        }
+        override_from_env(&mut config.max_spectators, "max_spectators")?;
+        override_seconds_from_env(&mut config.client_idle_timeout, "client_idle_timeout")?;
+        override_seconds_from_env(&mut config.server_time_interval, "server_time_interval")?;
+        override_from_env(&mut config.max_connections_per_ip, "max_connections_per_ip")?;
+        override_from_env(&mut config.max_message_size, "max_message_size")?;
+        if config.max_message_size == 0 {
+            return Err(anyhow!(
+                "{} has to be positive.",
+                env_name("max_message_size")
+            ));
+        }
+        override_seconds_from_env(&mut config.connection_rate_window, "connection_rate_window")?;
+        override_from_env(&mut config.quickplay_rotation, "quickplay_rotation")?;
+        if let Some(difficulty) = parse_env("quickplay_difficulty")? {
+            config.quickplay_difficulty = Some(difficulty);
+        }
+        override_from_env(&mut config.prewarmed_lobbies, "prewarmed_lobbies")?;
+        // Read without `parse_env`, which logs the value.
+        if let Ok(admin_token) = env::var(env_name("admin_token")) {
+            if admin_token.trim().is_empty() {
+                return Err(anyhow!("{} has to be non-empty.", env_name("admin_token")));
+            }
+            info!("Admin routes are enabled.");
+            config.admin_token = Some(admin_token);
+        }
+        Ok(config)
+    }
+}
+
+/// # Get environment variable name
+///
+/// Maps a config field to the environment variable overriding it.
+pub fn env_name(field: &str) -> String {
+    format!("{ENV_PREFIX}{}", field.to_uppercase())
+}
+
+/// # Parse environment variable
+///
+/// Parses the environment variable of the given config field. Returns `None`
+/// if the variable is not set.
+fn parse_env<T>(field: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let name = env_name(field);
+    let Ok(value) = env::var(&name) else {
+        return Ok(None);
+    };
+    let parsed = value
+        .parse()
+        .map_err(|e| anyhow!("Invalid value '{value}' of {name}: {e}"))?;
+    info!("Using {name}={value}.");
+    Ok(Some(parsed))
+}
+
+fn override_from_env<T>(value: &mut T, field: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Some(parsed) = parse_env(field)? {
+        *value = parsed;
+    }
+    Ok(())
+}
+
+fn override_seconds_from_env(value: &mut Duration, field: &str) -> Result<()> {
+    if let Some(seconds) = parse_env(field)? {
+        *value = Duration::from_secs(seconds);
+    }
+    Ok(())
+}
@@ -1,12 +1,87 @@
 use std::time::Duration;
 
-/// Empty lobbies survive 30 seconds before being removed.
-pub static EMPTY_LOBBY_LIFETIME: Duration = Duration::from_secs(30);
+/// Empty lobbies survive 30 seconds before being removed by default.
+pub static DEFAULT_EMPTY_LOBBY_GRACE: Duration = Duration::from_secs(30);
 /// Lobbies start ten seconds after a start request.
 pub static LOBBY_START_TIMER: Duration = Duration::from_secs(10);
-/// Lobbies are up to two minutes in progress.
-pub static MAX_LOBBY_PLAY_TIME: Duration = Duration::from_secs(60 * 2);
-/// After one player finished, the lobby play time is reduced.
-pub static REDUCED_LOBBY_PLAY_TIME: Duration = Duration::from_secs(10);
+/// The lobby owner can start a round once this many players are active.
+pub static MIN_PLAYERS_TO_START: usize = 1;
+/// Lobbies allow up to 20 spectators by default, unless the owner changes the
+/// cap.
+pub static DEFAULT_MAX_SPECTATORS: usize = 20;
+/// Races last up to two minutes by default, unless their challenge sets its
+/// own time limit.
+pub static DEFAULT_ROUND_TIME_LIMIT: Duration = Duration::from_secs(60 * 2);
+/// Hosts can limit races to at most an hour.
+pub static MAX_LOBBY_TIME_LIMIT: Duration = Duration::from_secs(60 * 60);
+/// After the first player finished a race, the others have ten seconds left
+/// by default.
+pub static DEFAULT_FINISH_GRACE: Duration = Duration::from_secs(10);
+/// Race solves faster than three seconds are flagged and do not count as
+/// records by default.
+pub static DEFAULT_MIN_SOLVE_TIME: Duration = Duration::from_secs(3);
+/// Round timers firing more than a quarter second before the round end were
+/// outdated by a pause and are ignored.
+pub static ROUND_TIMER_TOLERANCE: Duration = Duration::from_millis(250);
 /// Lobbies are ten seconds in the finish state.
 pub static LOBBY_FINISH_TIME: Duration = Duration::from_secs(10);
+/// A single IP address can open up to 20 connections within the connection
+/// rate window.
+pub static MAX_CONNECTIONS_PER_IP: usize = 20;
+/// Window of the per IP connection rate limit.
+pub static CONNECTION_RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Clients are removed after three consecutive failed sends.
+pub static MAX_CLIENT_SEND_FAILURES: usize = 3;
+/// Idle addresses are forgotten once the rate limit tracks this many.
+pub static MAX_TRACKED_ADDRESSES: usize = 1024;
+/// Incoming WebSocket messages are capped at 2 MiB unless configured
+/// otherwise, which leaves room for a replay at the replay limits.
+pub static DEFAULT_MAX_MESSAGE_SIZE: usize = 2 * 1024 * 1024;
+/// Connection counts are sampled every ten seconds for the connection history.
+pub static CONNECTION_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+/// The pre-warmed quickplay lobbies are topped up every five seconds.
+pub static PREWARM_INTERVAL: Duration = Duration::from_secs(5);
+/// Players are sent the server time every ten seconds for their clock sync,
+/// unless configured otherwise.
+pub static DEFAULT_SERVER_TIME_INTERVAL: Duration = Duration::from_secs(10);
+/// Sockets get half a second to deliver the shutdown notice before the
+/// backend exits.
+pub static SHUTDOWN_FLUSH_DELAY: Duration = Duration::from_millis(500);
+/// Health checks fail if the app does not answer within a second.
+pub static HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+/// The connection history covers the last hour.
+pub static MAX_CONNECTION_SAMPLES: usize = 360;
+/// Time attack rounds last three minutes regardless of finished challenges.
+pub static TIME_ATTACK_PLAY_TIME: Duration = Duration::from_secs(60 * 3);
+/// Bots update their progress once per second.
+pub static BOT_TICK_RATE: Duration = Duration::from_secs(1);
+/// Time an easy bot needs to finish the challenge.
+pub static EASY_BOT_FINISH_TIME: Duration = Duration::from_secs(100);
+/// Time a medium bot needs to finish the challenge.
+pub static MEDIUM_BOT_FINISH_TIME: Duration = Duration::from_secs(70);
+/// Time a hard bot needs to finish the challenge.
+pub static HARD_BOT_FINISH_TIME: Duration = Duration::from_secs(40);
+/// Directory holding one folder per race challenge.
+pub static CHALLENGE_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/challenges");
+/// Start and goal files of a challenge may be up to 64 KiB large.
+pub static MAX_CHALLENGE_FILE_SIZE: usize = 64 * 1024;
+/// Startup warns about catalogs with fewer than three challenges by default,
+/// as rotations repeat quickly with so few.
+pub static DEFAULT_MIN_CHALLENGES: usize = 3;
+/// Challenge new lobbies start with.
+pub static DEFAULT_CHALLENGE_ID: &str = "hello_world";
+/// Clients that neither browse nor spectate are disconnected after ten
+/// minutes, unless configured otherwise.
+pub static DEFAULT_CLIENT_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+/// Ready states are reset if not all players got ready within a minute after
+/// the last player got ready, unless configured otherwise.
+pub static DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(60);
+/// Reason shown when the lobby owner kicks a player without giving one.
+pub static DEFAULT_KICK_REASON: &str = "No reason given";
+/// Reason shown when a majority of the other players voted to kick a player.
+pub static VOTE_KICK_REASON: &str = "vote-kicked";
+/// Reason shown to the members of a lobby closed by an operator.
+pub static ADMIN_CLOSE_REASON: &str = "The lobby was closed by an operator";
+/// Seats of disconnected players are held for 30 seconds, unless configured
+/// otherwise.
+pub static DEFAULT_RECONNECT_GRACE: Duration = Duration::from_secs(30);
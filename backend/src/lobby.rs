@@ -1,68 +1,244 @@
-use std::collections::BTreeMap;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, Instant},
+};
 
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use fake::{faker::company::en::CompanyName, Fake};
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use common::{
-    constants::MAX_LOBBY_SIZE, BackendMessage, ChallengeFiles, LobbyInformation, LobbyListItem,
-    LobbyStatus,
+    constants::{MAX_LOBBY_DESCRIPTION_LENGTH, MAX_LOBBY_SIZE, MAX_LOBBY_TAGS},
+    BackendMessage, ChallengeFiles, ChatMessage, CloseReason, Comparison, Difficulty, GameMode,
+    LobbyInformation, LobbyListItem, LobbyStatus, LobbyTag, PlayerInfo, PlayerRoundState,
+    ReplayFrame, RoundResult, RoundState, RoundSummary,
+};
+
+use crate::{
+    app::message::AppMessage,
+    bot::Bot,
+    challenge::Challenge,
+    chat_filter::{ChatFilter, FilterOutcome},
+    comparison::{normalizer, Normalizer},
+    constants::{
+        DEFAULT_KICK_REASON, LOBBY_START_TIMER, MAX_CHALLENGE_FILE_SIZE, MAX_LOBBY_TIME_LIMIT,
+        MIN_PLAYERS_TO_START, VOTE_KICK_REASON,
+    },
+    player::{validate_player_name, Player},
 };
 
-use crate::{app::message::AppMessage, constants::EMPTY_LOBBY_LIFETIME, player::Player};
+/// What happened to the seat of a player whose connection closed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisconnectOutcome {
+    /// The seat is held for the reconnect grace period.
+    Held,
+    /// The player resumed the seat through another connection, the closed one
+    /// was stale.
+    Superseded,
+    /// The seat is released right away, as the player is not part of the
+    /// lobby anymore or seats are not held at all.
+    Released,
+}
 
 #[derive(Clone, Debug)]
 pub struct Lobby {
     pub id: Uuid,
     pub name: String,
+    /// What the host describes the lobby with, if anything.
+    pub description: Option<String>,
+    /// Categories the host tagged the lobby with.
+    pub tags: BTreeSet<LobbyTag>,
     /// The current owner of the lobby. It's not guaranteed that there always is
     /// an owner (e.g., in an empty lobby). The first player joining the lobby
     /// is assigned the owner role. If this player leaves the next available
     /// player is assigned.
     pub owner: Option<Uuid>,
     pub players: BTreeMap<Uuid, Player>,
-    pub challenge_files: ChallengeFiles,
+    /// An optional bot opponent for solo practice. The bot is not part of
+    /// `players` as it has no connection, but shows up in the roster.
+    pub bot: Option<Bot>,
+    /// The challenge of a race.
+    pub challenge: Challenge,
+    /// How the host wants player files to be compared against the goal file.
+    /// Falls back to the comparison of the challenge if not set.
+    pub comparison: Option<Comparison>,
+    /// How long races last according to the host. Falls back to the time
+    /// limit of the challenge if not set.
+    pub time_limit: Option<Duration>,
+    /// An optional warmup challenge the host picked. It is raced once before
+    /// the scored rounds.
+    pub warmup: Option<Challenge>,
+    /// Whether the warmup challenge was already raced.
+    pub warmup_played: bool,
+    /// Whether the current round is the warmup round. Results of a warmup
+    /// round are not recorded.
+    pub is_warmup_round: bool,
+    /// The number of the current or last scored round of the series. Zero
+    /// before the first round. Warmup rounds are not counted.
+    pub round_number: usize,
+    /// Small challenges players work through one after another in a time
+    /// attack round.
+    pub time_attack_challenges: Vec<ChallengeFiles>,
     pub status: LobbyStatus,
+    /// The amount of seats, at most `MAX_LOBBY_SIZE`.
+    pub max_players: usize,
+    /// The amount of clients that can spectate players of this lobby at the
+    /// same time. Independent of `max_players`.
+    pub max_spectators: usize,
+    /// Whether clients may watch the players of this lobby at all.
+    pub spectators_allowed: bool,
+    /// Whether players see the goal file. Without the preview, players race
+    /// blind and only the progress tells them how close they are.
+    pub goal_preview: bool,
+    /// Whether everyone sees the solution of the race winner after a round.
+    /// Owners turn it off for rematches the solution would spoil.
+    pub solution_reveal: bool,
+    /// The winner of the current or last race together with the solution.
+    pub winning_solution: Option<(Uuid, Vec<u8>)>,
+    /// The time the owner paused the current round, if it is paused.
+    pub paused_at: Option<DateTime<Utc>>,
+    /// Whether a start was requested while some editors were still starting
+    /// up. The countdown begins once all of them are ready.
+    pub start_requested: bool,
+    /// The time a player last got ready while others were not, if the lobby
+    /// is waiting for the others.
+    pub ready_since: Option<Instant>,
+    /// The round state of every active player that was last broadcast.
+    pub round_roster: Vec<PlayerRoundState>,
+    /// How long every player who reached the goal of the current race took.
+    pub finish_times: BTreeMap<Uuid, Duration>,
+    /// The time the current round started, if any.
+    pub started_at: Option<DateTime<Utc>>,
+    /// Editor inputs of the last round by player name.
+    pub replays: BTreeMap<String, Vec<ReplayFrame>>,
+    pub game_mode: GameMode,
+    /// How long the other players can still finish a race after the first
+    /// one did.
+    pub finish_grace: Duration,
+    /// How long the lobby is kept once it is empty.
+    pub empty_lobby_grace: Duration,
+    /// When the last player left, if the lobby is empty. Rejoining cancels
+    /// the removal.
+    pub empty_since: Option<Instant>,
+    /// Players voting to kick a player by the ID of the target.
+    pub kick_votes: BTreeMap<Uuid, BTreeSet<Uuid>>,
 }
 
-impl Default for Lobby {
-    fn default() -> Self {
-        let start_file =
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/start.rs")).to_vec();
-        let goal_file =
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/goal.rs")).to_vec();
+/// # Include time attack challenge
+///
+/// Embeds the start and goal file of the time attack challenge inside the
+/// given asset folder during compile time.
+macro_rules! include_time_attack_challenge {
+    ($folder:literal) => {
+        ChallengeFiles {
+            start_file: include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/assets/time_attack/",
+                $folder,
+                "/start.rs"
+            ))
+            .to_vec(),
+            goal_file: include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/assets/time_attack/",
+                $folder,
+                "/goal.rs"
+            ))
+            .to_vec(),
+        }
+    };
+}
+
+impl Lobby {
+    /// # Create a new lobby
+    ///
+    /// Creates an empty lobby with a random name racing the given challenge.
+    pub fn new(
+        challenge: Challenge,
+        finish_grace: Duration,
+        empty_lobby_grace: Duration,
+        max_players: usize,
+        max_spectators: usize,
+    ) -> Self {
+        let time_attack_challenges = vec![
+            include_time_attack_challenge!("1"),
+            include_time_attack_challenge!("2"),
+            include_time_attack_challenge!("3"),
+            include_time_attack_challenge!("4"),
+            include_time_attack_challenge!("5"),
+        ];
 
-        let challenge_files = ChallengeFiles {
-            start_file,
-            goal_file,
-        };
         let id = Uuid::new_v4();
         Self {
             id,
             name: CompanyName().fake(),
+            description: None,
+            tags: BTreeSet::new(),
             owner: None,
             players: BTreeMap::new(),
-            challenge_files,
+            bot: None,
+            challenge,
+            comparison: None,
+            time_limit: None,
+            warmup: None,
+            warmup_played: false,
+            is_warmup_round: false,
+            round_number: 0,
+            time_attack_challenges,
             status: LobbyStatus::WaitingForPlayers,
+            max_players: max_players.min(MAX_LOBBY_SIZE),
+            max_spectators,
+            spectators_allowed: true,
+            goal_preview: true,
+            solution_reveal: true,
+            winning_solution: None,
+            paused_at: None,
+            start_requested: false,
+            ready_since: None,
+            round_roster: Vec::new(),
+            finish_times: BTreeMap::new(),
+            started_at: None,
+            replays: BTreeMap::new(),
+            game_mode: GameMode::default(),
+            finish_grace,
+            empty_lobby_grace,
+            empty_since: None,
+            kick_votes: BTreeMap::new(),
         }
     }
-}
 
-impl Lobby {
     /// # Broadcast message
     ///
     /// Sends a message to every player inside the lobby.
     pub fn broadcast(&self, msg: BackendMessage) {
-        for Player {
-            id: _,
-            name: _,
-            tx,
-            progress: _,
-            waiting: _,
-        } in self.players.values()
-        {
-            let _ = tx.send(msg.clone());
+        self.broadcast_to(msg, |_| true);
+    }
+
+    /// # Broadcast message except
+    ///
+    /// Sends a message to every player inside the lobby but the excluded one,
+    /// e.g., the player a notice is about. Spectators are not included, as
+    /// they are clients of the app rather than members of the lobby and only
+    /// receive the frames of the player they watch.
+    pub fn broadcast_except(&self, exclude_id: Uuid, msg: BackendMessage) {
+        self.broadcast_to(msg, |player| player.id != exclude_id);
+    }
+
+    /// Sends a message to the players matching the filter. A failed send only
+    /// means the connection of that player is gone, the others still get the
+    /// message.
+    fn broadcast_to(&self, msg: BackendMessage, filter: impl Fn(&Player) -> bool) {
+        for player in self.players.values().filter(|player| filter(player)) {
+            if player.tx.send(msg.clone()).is_err() {
+                debug!(
+                    "Could not send to player {} of lobby {}, the connection is gone.",
+                    player.name, self.name
+                );
+            }
         }
     }
 
@@ -70,8 +246,42 @@ impl Lobby {
         LobbyListItem {
             name: self.name.clone(),
             player_count: self.players.len(),
+            max_players: self.max_players,
             status: self.status.clone(),
+            language: self.challenge.language.clone(),
+            difficulty: self.challenge.difficulty,
+            spectators_allowed: self.spectators_allowed,
+            description: self.description.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+
+    /// # Roster
+    ///
+    /// Lists every player and the bot together with their current round
+    /// state.
+    pub fn roster(&self) -> Vec<PlayerInfo> {
+        let round_state = |player_id: Uuid| {
+            self.round_roster
+                .iter()
+                .find(|round_state| round_state.player_id == player_id)
+                .map(|round_state| round_state.state)
+        };
+        let mut roster = self
+            .players
+            .values()
+            .map(|player| PlayerInfo {
+                player: player.to_common_player(),
+                round_state: round_state(player.id),
+            })
+            .collect::<Vec<_>>();
+        if let Some(ref bot) = self.bot {
+            roster.push(PlayerInfo {
+                player: bot.to_common_player(),
+                round_state: round_state(bot.id),
+            });
         }
+        roster
     }
 
     pub fn to_information(&self) -> LobbyInformation {
@@ -79,13 +289,80 @@ impl Lobby {
         for (id, player) in self.players.iter() {
             players.insert(*id, player.to_common_player());
         }
+        if let Some(ref bot) = self.bot {
+            players.insert(bot.id, bot.to_common_player());
+        }
         LobbyInformation {
             id: self.id,
             name: self.name.clone(),
+            description: self.description.clone(),
+            tags: self.tags.clone(),
             status: self.status.clone(),
+            game_mode: self.game_mode,
             owner: self.owner,
             players,
-            challenge_files: self.challenge_files.clone(),
+            max_players: self.max_players,
+            challenge: self.challenge.to_information(),
+            challenge_files: shared_files(&self.challenge.files, self.goal_preview),
+            goal_preview: self.goal_preview,
+            solution_reveal: self.solution_reveal,
+            warmup: self.warmup.as_ref().map(Challenge::to_information),
+            round_number: self.round_number,
+            quickplay: None,
+        }
+    }
+
+    /// # Get challenge files of player
+    ///
+    /// Returns the challenge files the player is currently working on. In a
+    /// time attack round this is the challenge at the player's cursor, which
+    /// is `None` once the player completed all challenges.
+    pub fn challenge_files_of(&self, player: &Player) -> Option<&ChallengeFiles> {
+        match self.game_mode {
+            GameMode::Race => Some(&self.race_challenge().files),
+            GameMode::TimeAttack => self.time_attack_challenges.get(player.challenge_index),
+        }
+    }
+
+    /// # Get comparison
+    ///
+    /// Returns how player files are compared against the goal file in the
+    /// current round. The override of the host wins over the comparison of
+    /// the race challenge. Time attack challenges use the default otherwise.
+    pub fn comparison(&self) -> Comparison {
+        match (self.comparison, self.game_mode) {
+            (Some(comparison), _) => comparison,
+            (None, GameMode::Race) => self.race_challenge().comparison,
+            (None, GameMode::TimeAttack) => Comparison::default(),
+        }
+    }
+
+    /// # Get normalizer
+    ///
+    /// Returns the normalizer implementing the comparison of the current
+    /// round.
+    pub fn normalizer(&self) -> Box<dyn Normalizer> {
+        let language = match self.game_mode {
+            GameMode::Race => self.race_challenge().language.as_str(),
+            GameMode::TimeAttack => "",
+        };
+        normalizer(self.comparison(), language)
+    }
+
+    /// Whether player files are compared byte by byte in the current round,
+    /// which is the case for races of binary challenges.
+    pub fn compares_bytes(&self) -> bool {
+        self.game_mode == GameMode::Race && self.race_challenge().binary
+    }
+
+    /// # Get race challenge
+    ///
+    /// Returns the challenge of the current race, which is the warmup
+    /// challenge during the warmup round.
+    pub fn race_challenge(&self) -> &Challenge {
+        match (self.is_warmup_round, &self.warmup) {
+            (true, Some(warmup)) => warmup,
+            _ => &self.challenge,
         }
     }
 
@@ -93,10 +370,18 @@ impl Lobby {
     ///
     /// Adds a player to the lobby. If the lobby is full, tell the player about
     /// that and prevent the addition. If the player successfully joined the
-    /// lobby tell him the lobby name.
-    pub fn add_player(&mut self, mut player: Player, app_tx: &UnboundedSender<AppMessage>) {
+    /// lobby tell him the lobby name. Returns whether the player took a seat.
+    /// Only a lobby waiting for players takes new players. During a round,
+    /// the client of the player watches it instead, if the lobby allows
+    /// spectators.
+    ///
+    /// The capacity is checked against the players of the lobby right before
+    /// the insertion. Joins are handled one after another by the app, so of
+    /// concurrent joins for the last seat only the first one succeeds, no
+    /// matter which lobby list or quickplay decision led to them.
+    pub fn add_player(&mut self, mut player: Player, app_tx: &UnboundedSender<AppMessage>) -> bool {
         // Return early if the lobby is full.
-        if self.players.len() >= MAX_LOBBY_SIZE {
+        if !self.has_free_seat() {
             warn!(
                 "Tried to add player {} to full lobby {}.",
                 player.name, self.name
@@ -104,15 +389,64 @@ impl Lobby {
             let _ = app_tx.send(AppMessage::LobbyFull {
                 player_tx: player.tx,
             });
-            return;
+            return false;
         }
 
-        // Flag the player as waiting as the lobby is currently not waiting for
-        // any active players.
-        if self.status != LobbyStatus::WaitingForPlayers {
-            player.waiting = true;
+        // Reject late joins, so that all players start the round at the same
+        // time.
+        match self.status {
+            LobbyStatus::WaitingForPlayers => {}
+            LobbyStatus::AboutToStart(_) => {
+                warn!(
+                    "Tried to add player {} to lobby {} which is about to start.",
+                    player.name, self.name
+                );
+                let _ = player.tx.send(BackendMessage::RoundStarting);
+                return false;
+            }
+            LobbyStatus::InProgress(_) => {
+                warn!(
+                    "Tried to add player {} to lobby {} with a round in progress.",
+                    player.name, self.name
+                );
+                // Let the client watch the leading player instead, if the
+                // lobby allows spectators.
+                let target = self
+                    .players
+                    .values()
+                    .max_by(|a, b| a.progress.total_cmp(&b.progress))
+                    .map(|target| target.id);
+                let spectating = match (player.client_id, target) {
+                    (Some(client_id), Some(target_id)) if self.spectators_allowed => {
+                        let _ = app_tx.send(AppMessage::SpectatePlayer {
+                            client_id,
+                            lobby_id: self.id,
+                            target_id,
+                        });
+                        true
+                    }
+                    _ => false,
+                };
+                let _ = player
+                    .tx
+                    .send(BackendMessage::RoundInProgress { spectating });
+                return false;
+            }
+            LobbyStatus::Finish(_) => {
+                warn!(
+                    "Tried to add player {} to lobby {} which just finished a round.",
+                    player.name, self.name
+                );
+                let _ = player.tx.send(BackendMessage::LobbyNotWaitingForPlayers);
+                return false;
+            }
         }
-        // Add the player to the actual player room.
+        // Names are unique within a lobby, so that commands addressing players
+        // by name stay unambiguous.
+        player.name = self.unique_player_name(&player.name, player.id);
+        // Add the player to the actual player room. This cancels the removal
+        // of an empty lobby.
+        self.empty_since = None;
         self.players.insert(player.id, player.clone());
         info!("Added player {} to lobby {}.", player.name, self.name);
 
@@ -123,6 +457,9 @@ impl Lobby {
         // Tell non-playing clients about the new player taking up a seat in
         // this lobby.
         let _ = app_tx.send(AppMessage::SendLobbyPlayerCountUpdate { lobby_id: self.id });
+        if !self.has_free_seat() {
+            let _ = app_tx.send(AppMessage::SendLobbyAvailabilityUpdate { lobby_id: self.id });
+        }
 
         // Tell everyone about the update in connections.
         let _ = app_tx.send(AppMessage::SendConnectionCounts);
@@ -142,6 +479,120 @@ impl Lobby {
         let _ = player
             .tx
             .send(BackendMessage::ProvidePlayerId { id: player.id });
+        let _ = player.tx.send(BackendMessage::ProvideReconnectToken {
+            token: player.reconnect_token,
+        });
+        true
+    }
+
+    /// Whether another player can join the lobby.
+    pub fn has_free_seat(&self) -> bool {
+        self.players.len() < self.max_players
+    }
+
+    /// # Disconnect player
+    ///
+    /// Holds the seat of a player whose connection was lost. Progress, score
+    /// and ready state are kept until the player resumes or the seat is
+    /// released. Connections the player already replaced by resuming leave
+    /// the seat untouched.
+    pub fn disconnect_player(&mut self, player: &Player, grace: Duration) -> DisconnectOutcome {
+        if self.is_superseded(player) {
+            return DisconnectOutcome::Superseded;
+        }
+        let Some(lobby_player) = self
+            .players
+            .get_mut(&player.id)
+            .filter(|_| !grace.is_zero())
+        else {
+            return DisconnectOutcome::Released;
+        };
+        lobby_player.disconnected_at = Some(Instant::now());
+        info!(
+            "Holding seat of disconnected player {} in lobby {}.",
+            lobby_player.name, self.name
+        );
+        let message = format!(
+            "{} lost the connection. Their seat is held for {}s.",
+            lobby_player.name,
+            grace.as_secs()
+        );
+        let player_id = lobby_player.id;
+        self.broadcast_except(
+            player_id,
+            BackendMessage::SendMessage(ChatMessage::new(message)),
+        );
+        self.update_round_roster();
+        DisconnectOutcome::Held
+    }
+
+    /// Whether the player resumed their seat through another connection than
+    /// the one of the given player.
+    pub fn is_superseded(&self, player: &Player) -> bool {
+        self.players
+            .get(&player.id)
+            .is_some_and(|lobby_player| !lobby_player.tx.same_channel(&player.tx))
+    }
+
+    /// # Resume player
+    ///
+    /// Hands the seat belonging to the reconnect token to the new connection.
+    /// A previous connection that is still open, e.g., a half-open socket the
+    /// backend did not notice dropping yet, is closed. Tells the player about
+    /// the current state of the lobby and returns the resumed player.
+    pub fn resume_player(
+        &mut self,
+        token: Uuid,
+        tx: UnboundedSender<BackendMessage>,
+    ) -> Option<Player> {
+        let player = self
+            .players
+            .values_mut()
+            .find(|player| player.reconnect_token.eq(&token))?;
+        let previous_tx = std::mem::replace(&mut player.tx, tx);
+        let _ = previous_tx.send(BackendMessage::CloseConnection {
+            reason: Some(CloseReason::Superseded),
+        });
+        player.disconnected_at = None;
+        let player = player.clone();
+        info!("Player {} resumed in lobby {}.", player.name, self.name);
+
+        let _ = player
+            .tx
+            .send(BackendMessage::ProvidePlayerId { id: player.id });
+        let _ = player.tx.send(BackendMessage::ProvideReconnectToken {
+            token: player.reconnect_token,
+        });
+        let _ = player.tx.send(BackendMessage::StatusUpdate {
+            status: self.status.clone(),
+        });
+        if let Some(files) = self.challenge_files_of(&player) {
+            let _ = player
+                .tx
+                .send(BackendMessage::ProvideChallengeFiles(shared_files(
+                    files,
+                    self.goal_preview,
+                )));
+        }
+        let message = format!("{} is back.", player.name);
+        self.broadcast_except(
+            player.id,
+            BackendMessage::SendMessage(ChatMessage::new(message)),
+        );
+        self.update_round_roster();
+        Some(player)
+    }
+
+    /// # Held seat expired
+    ///
+    /// Returns the player if their connection was lost at least `grace` ago
+    /// and they did not resume since.
+    pub fn held_seat_expired(&self, player_id: Uuid, grace: Duration) -> Option<&Player> {
+        self.players.get(&player_id).filter(|player| {
+            player
+                .disconnected_at
+                .is_some_and(|disconnected_at| disconnected_at.elapsed() >= grace)
+        })
     }
 
     /// # Remove player
@@ -152,6 +603,11 @@ impl Lobby {
             return;
         };
         info!("Removed player {} from lobby {}.", player.name, self.name);
+        // Votes of and against the player are void.
+        self.kick_votes.remove(&player.id);
+        for voters in self.kick_votes.values_mut() {
+            voters.remove(&player.id);
+        }
         // Tell connected players about the removal of this player.
         let message = BackendMessage::RemovePlayer(player.id);
         self.broadcast(message);
@@ -168,42 +624,1928 @@ impl Lobby {
 
         // Tell non-playing clients about the free seat in this lobby.
         let _ = app_tx.send(AppMessage::SendLobbyPlayerCountUpdate { lobby_id: self.id });
+        if self.players.len() + 1 == self.max_players {
+            let _ = app_tx.send(AppMessage::SendLobbyAvailabilityUpdate { lobby_id: self.id });
+        }
 
         // Tell everyone about the update in connections.
         let _ = app_tx.send(AppMessage::SendConnectionCounts);
+        self.update_round_roster();
+
+        // The removed player might have been the last one with a loading
+        // editor.
+        if self.start_requested && !self.players.is_empty() {
+            self.request_countdown(app_tx);
+        }
 
         // Now, if the lobby is empty, tell the app to remove this lobby.
         if self.players.is_empty() {
             let app_tx = app_tx.clone();
             let lobby_id = self.id;
 
-            // Remove the owner, as there are no players in the lobby.
+            // Remove the owner and the bot, as there are no players in the
+            // lobby.
             self.owner = None;
+            self.bot = None;
+            self.start_requested = false;
+            self.reset_series();
             // Also, reset the status and tell the clients about it.
             self.status = LobbyStatus::WaitingForPlayers;
             let _ = app_tx.send(AppMessage::SendLobbyStatusUpdate { lobby_id: self.id });
 
-            // Tell the app to remove the lobby after 30 seconds.
+            // Tell the app to remove the lobby after the grace period, unless
+            // someone rejoins in the meantime.
+            self.empty_since = Some(Instant::now());
+            let empty_lobby_grace = self.empty_lobby_grace;
             tokio::spawn(async move {
-                tokio::time::sleep(EMPTY_LOBBY_LIFETIME).await;
+                tokio::time::sleep(empty_lobby_grace).await;
                 let _ = app_tx.send(AppMessage::RemoveLobby { lobby_id });
             });
         }
     }
 
-    /// # Send message
+    /// # Set challenge
     ///
-    /// Broadcasts a message from a player to all connnected players if the
-    /// player exists.
-    pub fn send_message(&self, player: Player, message: String) {
-        if let Some(player) = self.players.get(&player.id) {
-            let message = BackendMessage::SendMessage(format!("{}: {message}", player.name));
+    /// Replaces the race challenge if the provided player is the lobby owner
+    /// and the lobby is waiting for players. Tells all players about the new
+    /// challenge and hands out its files. Returns whether the challenge was
+    /// changed.
+    pub fn set_challenge(&mut self, player: Player, challenge: Challenge) -> bool {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the challenge of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return false;
+        }
+        if self.status != LobbyStatus::WaitingForPlayers {
+            let message = "The challenge can only be changed between rounds.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return false;
+        }
+
+        info!(
+            "Changed challenge of lobby {} to {}.",
+            self.name, challenge.name
+        );
+        self.challenge = challenge;
+        self.reset_series();
+        self.broadcast(BackendMessage::ChallengeChanged(
+            self.challenge.to_information(),
+        ));
+        self.broadcast(BackendMessage::ProvideChallengeFiles(shared_files(
+            &self.challenge.files,
+            self.goal_preview,
+        )));
+        true
+    }
+
+    /// # Set warmup
+    ///
+    /// Replaces or removes the warmup challenge if the provided player is the
+    /// lobby owner and the lobby is waiting for players. A new warmup
+    /// challenge is raced once before the next scored round.
+    pub fn set_warmup(&mut self, player: Player, warmup: Option<Challenge>) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the warmup of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        if self.status != LobbyStatus::WaitingForPlayers {
+            let message = "The warmup can only be changed between rounds.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+
+        match warmup {
+            Some(ref warmup) => info!("Changed warmup of lobby {} to {}.", self.name, warmup.name),
+            None => info!("Removed warmup of lobby {}.", self.name),
+        }
+        self.warmup = warmup;
+        self.warmup_played = false;
+        self.broadcast(BackendMessage::WarmupChanged(
+            self.warmup.as_ref().map(Challenge::to_information),
+        ));
+    }
+
+    /// # Start round
+    ///
+    /// Counts the round that is about to start and tells the players about
+    /// it. Warmup rounds keep the round number of the series.
+    pub fn start_round(&mut self) {
+        if !self.is_warmup_round {
+            self.round_number += 1;
+        }
+        info!(
+            "Starting round {} in lobby {}.",
+            self.round_number, self.name
+        );
+        self.broadcast(BackendMessage::RoundStarted {
+            warmup: self.is_warmup_round,
+            round_number: self.round_number,
+            comparison: self.comparison(),
+        });
+        self.winning_solution = None;
+        self.paused_at = None;
+        self.finish_times.clear();
+        self.round_roster.clear();
+        self.update_round_roster();
+    }
+
+    /// # Update round roster
+    ///
+    /// Recomputes what every active player and the bot are doing in the
+    /// current round. The roster is only broadcast if a state changed, so
+    /// that plain progress updates do not resend it.
+    pub fn update_round_roster(&mut self) {
+        if !matches!(self.status, LobbyStatus::InProgress(_)) {
+            return;
+        }
+        let mut round_roster = self
+            .players
+            .values()
+            .filter(|player| !player.waiting)
+            .map(|player| {
+                let has_finished = match self.game_mode {
+                    GameMode::Race => player.progress.eq(&1.0),
+                    GameMode::TimeAttack => {
+                        player.challenge_index >= self.time_attack_challenges.len()
+                    }
+                };
+                let state = if player.disconnected_at.is_some() {
+                    RoundState::Disconnected
+                } else if has_finished {
+                    RoundState::Finished
+                } else {
+                    RoundState::Editing
+                };
+                PlayerRoundState {
+                    player_id: player.id,
+                    state,
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(ref bot) = self.bot {
+            let state = if bot.progress.eq(&1.0) {
+                RoundState::Finished
+            } else {
+                RoundState::Editing
+            };
+            round_roster.push(PlayerRoundState {
+                player_id: bot.id,
+                state,
+            });
+        }
+        if round_roster.eq(&self.round_roster) {
+            return;
+        }
+        self.round_roster = round_roster;
+        self.broadcast(BackendMessage::RoundRoster(self.round_roster.clone()));
+    }
+
+    /// # Reset series
+    ///
+    /// Starts a new series, so that the next round is round one again. This
+    /// happens once the lobby empties or the host changes the challenge or
+    /// the game mode, but not between the rounds of a series.
+    pub fn reset_series(&mut self) {
+        self.round_number = 0;
+    }
+
+    /// # Reset round
+    ///
+    /// Brings the lobby back to waiting for players after a round. Only the
+    /// state of the finished round is reset, i.e., progress, ready states and
+    /// time attack scores. Everything the host configured carries over into
+    /// the next round, as does the round number of the series.
+    pub fn reset_round(&mut self, app_tx: &UnboundedSender<AppMessage>) {
+        for player in self.players.values_mut() {
+            player.progress = 0.0;
+            player.ready = false;
+            player.challenge_index = 0;
+            player.score = 0;
+        }
+
+        self.players.values().for_each(|player| {
+            self.broadcast(BackendMessage::UpdatePlayerScore {
+                player_id: player.id,
+                score: player.score,
+            });
+            self.broadcast(BackendMessage::PlayerReady {
+                player_id: player.id,
+                ready: player.ready,
+            });
+        });
+
+        // Bring the players back to the lobby challenge after a warmup or
+        // time attack round.
+        self.finish_warmup_round();
+        if self.game_mode == GameMode::TimeAttack {
+            self.broadcast(BackendMessage::ProvideChallengeFiles(shared_files(
+                &self.challenge.files,
+                self.goal_preview,
+            )));
+        }
+
+        self.players.values().for_each(|player| {
+            // Tell players in the lobby about the progress reset of each
+            // player.
+            self.broadcast(BackendMessage::UpdatePlayerProgress {
+                player_id: player.id,
+                progress: player.progress,
+            });
+        });
+        if let Some(ref mut bot) = self.bot {
+            bot.progress = 0.0;
+            let message = BackendMessage::UpdatePlayerProgress {
+                player_id: bot.id,
+                progress: bot.progress,
+            };
             self.broadcast(message);
-        } else {
-            error!(
-                "Player {} was not found in lobby {}.",
+        }
+
+        self.status = LobbyStatus::WaitingForPlayers;
+        self.started_at = None;
+        // Tell clients about the reset lobby.
+        let _ = app_tx.send(AppMessage::SendLobbyStatusUpdate { lobby_id: self.id });
+        // Tell players in the lobby about the status update.
+        self.broadcast(BackendMessage::StatusUpdate {
+            status: self.status.clone(),
+        });
+    }
+
+    /// # Get race time limit
+    ///
+    /// Returns how long the upcoming race lasts. The limit of the host wins
+    /// over the one of the race challenge, which might be the warmup one.
+    pub fn race_time_limit(&self, default: Duration) -> Duration {
+        self.time_limit
+            .or(self.race_challenge().time_limit)
+            .unwrap_or(default)
+    }
+
+    /// # Start warmup round
+    ///
+    /// Turns the upcoming race into the warmup round if the lobby has a warmup
+    /// challenge that was not raced yet. Hands out the warmup challenge files
+    /// in that case.
+    pub fn start_warmup_round(&mut self) {
+        self.is_warmup_round =
+            self.game_mode == GameMode::Race && self.warmup.is_some() && !self.warmup_played;
+        if !self.is_warmup_round {
+            return;
+        }
+        info!("Starting warmup round in lobby {}.", self.name);
+        self.broadcast(BackendMessage::ProvideChallengeFiles(shared_files(
+            &self.race_challenge().files,
+            self.goal_preview,
+        )));
+    }
+
+    /// # Finish warmup round
+    ///
+    /// Marks the warmup as raced and brings the players back to the lobby
+    /// challenge if the current round was the warmup round.
+    pub fn finish_warmup_round(&mut self) {
+        if !self.is_warmup_round {
+            return;
+        }
+        self.is_warmup_round = false;
+        self.warmup_played = true;
+        self.broadcast(BackendMessage::ProvideChallengeFiles(shared_files(
+            &self.challenge.files,
+            self.goal_preview,
+        )));
+    }
+
+    /// # Set goal preview
+    ///
+    /// Shows or hides the goal file if the provided player is the lobby owner
+    /// and the lobby is waiting for players. Hands out the challenge files
+    /// again, so that the change takes effect right away.
+    pub fn set_goal_preview(&mut self, player: Player, enabled: bool) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to toggle the goal preview of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        if self.status != LobbyStatus::WaitingForPlayers {
+            let message = "The goal preview can only be changed between rounds.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+
+        info!(
+            "Turned goal preview of lobby {} {}.",
+            self.name,
+            if enabled { "on" } else { "off" }
+        );
+        self.goal_preview = enabled;
+        self.broadcast(BackendMessage::GoalPreviewChanged { enabled });
+        self.broadcast(BackendMessage::ProvideChallengeFiles(shared_files(
+            &self.challenge.files,
+            self.goal_preview,
+        )));
+    }
+
+    /// # Set solution reveal
+    ///
+    /// Turns showing the winning solution after a race on or off if the
+    /// provided player is the lobby owner.
+    pub fn set_solution_reveal(&mut self, player: Player, enabled: bool) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to toggle the solution reveal of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+
+        info!(
+            "Turned solution reveal of lobby {} {}.",
+            self.name,
+            if enabled { "on" } else { "off" }
+        );
+        self.solution_reveal = enabled;
+        self.broadcast(BackendMessage::SolutionRevealChanged { enabled });
+    }
+
+    /// # Set description
+    ///
+    /// Replaces the description of the lobby if the provided player is the
+    /// lobby owner. Invalid descriptions are rejected with a message to the
+    /// player. Returns whether the description changed.
+    pub fn set_description(&mut self, player: Player, description: Option<String>) -> bool {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the description of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return false;
+        }
+        let description = match description.as_deref().map(validate_lobby_description) {
+            Some(Ok(description)) => description,
+            Some(Err(e)) => {
+                let _ = player
+                    .tx
+                    .send(BackendMessage::SendMessage(ChatMessage::new(e.to_string())));
+                return false;
+            }
+            None => None,
+        };
+        if self.description == description {
+            return false;
+        }
+        info!(
+            "Changed description of lobby {} to {:?}.",
+            self.name, description
+        );
+        self.description = description.clone();
+        self.broadcast(BackendMessage::LobbyDescriptionChanged { description });
+        true
+    }
+
+    /// # Set tags
+    ///
+    /// Replaces the tags of the lobby if the provided player is the lobby
+    /// owner and there are at most `MAX_LOBBY_TAGS` of them. Returns whether
+    /// the tags changed.
+    pub fn set_tags(&mut self, player: Player, tags: BTreeSet<LobbyTag>) -> bool {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the tags of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return false;
+        }
+        if tags.len() > MAX_LOBBY_TAGS {
+            let message = format!("Lobbies can have at most {MAX_LOBBY_TAGS} tags.");
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return false;
+        }
+        if self.tags == tags {
+            return false;
+        }
+        info!("Changed tags of lobby {} to {:?}.", self.name, tags);
+        self.tags = tags.clone();
+        self.broadcast(BackendMessage::LobbyTagsChanged { tags });
+        true
+    }
+
+    /// # Set comparison
+    ///
+    /// Overrides how player files are compared against the goal file if the
+    /// provided player is the lobby owner. Only possible between rounds, so
+    /// that a running race is judged consistently.
+    pub fn set_comparison(&mut self, player: Player, comparison: Option<Comparison>) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the comparison of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        if self.status != LobbyStatus::WaitingForPlayers {
+            let message = "The comparison can only be changed between rounds.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+        if self.comparison == comparison {
+            return;
+        }
+        info!(
+            "Changed comparison of lobby {} to {:?}.",
+            self.name, comparison
+        );
+        self.comparison = comparison;
+        self.broadcast(BackendMessage::ComparisonChanged { comparison });
+    }
+
+    /// # Record winning solution
+    ///
+    /// Keeps the solution of the race winner for the end of the round.
+    /// Solutions larger than a challenge file may be are not kept.
+    pub fn record_winning_solution(&mut self, player_id: Uuid, content: Vec<u8>) {
+        if content.len() > MAX_CHALLENGE_FILE_SIZE {
+            debug!(
+                "Not keeping the winning solution of {} bytes in lobby {}.",
+                content.len(),
+                self.name
+            );
+            return;
+        }
+        self.winning_solution = Some((player_id, content));
+    }
+
+    /// # Reveal winning solution
+    ///
+    /// Shows everyone the solution of the race winner, unless the owner
+    /// turned the reveal off.
+    pub fn reveal_winning_solution(&self) {
+        if !self.solution_reveal {
+            return;
+        }
+        let Some((player_id, content)) = &self.winning_solution else {
+            return;
+        };
+        self.broadcast(BackendMessage::WinningSolution {
+            player_id: *player_id,
+            content: content.clone(),
+        });
+    }
+
+    /// # Send goal preview
+    ///
+    /// Tells all players about the goal of the starting race, or that the
+    /// goal is hidden.
+    pub fn send_goal_preview(&self) {
+        let content = self
+            .goal_preview
+            .then(|| self.race_challenge().files.goal_file.clone());
+        self.broadcast(BackendMessage::GoalPreview { content });
+    }
+
+    /// # Reveal goals
+    ///
+    /// Tells every active player about the goal of the race he finished, even
+    /// if the goal was hidden. During time attack this is the goal of the
+    /// challenge the player was working on when the round ended.
+    pub fn reveal_goals(&self) {
+        for player in self.players.values().filter(|player| !player.waiting) {
+            let files = match self.game_mode {
+                GameMode::TimeAttack => self
+                    .time_attack_challenges
+                    .get(player.challenge_index)
+                    .or(self.time_attack_challenges.last()),
+                _ => None,
+            }
+            .unwrap_or(&self.race_challenge().files);
+            let _ = player.tx.send(BackendMessage::RevealGoal {
+                content: files.goal_file.clone(),
+            });
+        }
+    }
+
+    /// # Set max spectators
+    ///
+    /// Changes the spectator cap if the provided player is the lobby owner.
+    /// Clients already watching are not affected by a lower cap.
+    pub fn set_max_spectators(
+        &mut self,
+        player: Player,
+        max_spectators: usize,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the spectator cap of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        info!(
+            "Changed spectator cap of lobby {} to {}.",
+            self.name, max_spectators
+        );
+        self.max_spectators = max_spectators;
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(format!(
+            "Up to {max_spectators} spectator(s) can watch this lobby."
+        ))));
+        let _ = app_tx.send(AppMessage::SendSpectatorCount { lobby_id: self.id });
+    }
+
+    /// # Set max players
+    ///
+    /// Changes the seats of the lobby if the provided player is the lobby
+    /// owner. Only possible between rounds and never below the current amount
+    /// of players. Returns whether the seats changed.
+    pub fn set_max_players(
+        &mut self,
+        player: Player,
+        max_players: usize,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) -> bool {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the seats of lobby {} without being the owner.",
                 player.name, self.name
             );
+            return false;
+        }
+        let message = if self.status != LobbyStatus::WaitingForPlayers {
+            Some("The seats can only be changed between rounds.".to_string())
+        } else if !(1..=MAX_LOBBY_SIZE).contains(&max_players) {
+            Some(format!(
+                "Lobbies have between 1 and {MAX_LOBBY_SIZE} seats."
+            ))
+        } else if max_players < self.players.len() {
+            Some(format!(
+                "There are already {} players in the lobby.",
+                self.players.len()
+            ))
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return false;
+        }
+        if self.max_players == max_players {
+            return false;
         }
+        info!("Changed seats of lobby {} to {}.", self.name, max_players);
+        let had_free_seat = self.has_free_seat();
+        self.max_players = max_players;
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(format!(
+            "The lobby now has {max_players} seat(s)."
+        ))));
+        if had_free_seat != self.has_free_seat() {
+            let _ = app_tx.send(AppMessage::SendLobbyAvailabilityUpdate { lobby_id: self.id });
+        }
+        true
+    }
+
+    /// # Set time limit
+    ///
+    /// Changes how long races last if the provided player is the lobby owner.
+    /// Only possible between rounds. `None` falls back to the time limit of
+    /// the challenge.
+    pub fn set_time_limit(&mut self, player: Player, time_limit: Option<Duration>) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the time limit of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        let message = if self.status != LobbyStatus::WaitingForPlayers {
+            Some("The time limit can only be changed between rounds.".to_string())
+        } else if time_limit
+            .is_some_and(|time_limit| time_limit.is_zero() || time_limit > MAX_LOBBY_TIME_LIMIT)
+        {
+            Some(format!(
+                "The time limit has to be between 1 and {} seconds.",
+                MAX_LOBBY_TIME_LIMIT.as_secs()
+            ))
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+        if self.time_limit == time_limit {
+            return;
+        }
+        info!(
+            "Changed time limit of lobby {} to {:?}.",
+            self.name, time_limit
+        );
+        self.time_limit = time_limit;
+        let message = match time_limit {
+            Some(time_limit) => format!("Races now last {}s.", time_limit.as_secs()),
+            None => "Races last as long as the challenge allows again.".to_string(),
+        };
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+    }
+
+    /// # Set spectators allowed
+    ///
+    /// Allows or forbids spectators if the provided player is the lobby owner.
+    /// Returns whether the setting changed.
+    pub fn set_spectators_allowed(&mut self, player: Player, allowed: bool) -> bool {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the spectator setting of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return false;
+        }
+        if self.spectators_allowed == allowed {
+            return false;
+        }
+        info!(
+            "{} spectators in lobby {}.",
+            if allowed { "Allowed" } else { "Forbade" },
+            self.name
+        );
+        self.spectators_allowed = allowed;
+        let text = if allowed {
+            "Spectators can watch this lobby."
+        } else {
+            "Spectators are no longer allowed in this lobby."
+        };
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(
+            text.to_string(),
+        )));
+        true
+    }
+
+    /// # Set bot
+    ///
+    /// Replaces the bot opponent of the lobby if the provided player is the
+    /// lobby owner. New bots can only be added to solo lobbies.
+    pub fn set_bot(&mut self, player: Player, difficulty: Option<Difficulty>) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to change the bot of lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        if self.status != LobbyStatus::WaitingForPlayers {
+            warn!(
+                "Tried to change the bot of lobby {} that is not waiting for players.",
+                self.name
+            );
+            return;
+        }
+        if difficulty.is_some() && self.players.len() > 1 {
+            let message = "Bots are only available in solo lobbies.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+
+        if let Some(bot) = self.bot.take() {
+            self.broadcast(BackendMessage::RemovePlayer(bot.id));
+        }
+        if let Some(difficulty) = difficulty {
+            let bot = Bot::new(difficulty);
+            info!("Added {} to lobby {}.", bot.name, self.name);
+            self.broadcast(BackendMessage::AddPlayer(bot.to_common_player()));
+            self.bot = Some(bot);
+        }
+    }
+
+    /// # Advance bot
+    ///
+    /// Updates the progress of the bot based on the elapsed round time and
+    /// tells all players about it. Once the bot reaches the goal, it finishes
+    /// like a real player would.
+    pub fn advance_bot(&mut self, app_tx: &UnboundedSender<AppMessage>) {
+        let (LobbyStatus::InProgress(_), GameMode::Race, Some(started_at)) =
+            (&self.status, self.game_mode, self.started_at)
+        else {
+            return;
+        };
+        let position = self.finished_player_count() + 1;
+        let Some(bot) = self.bot.as_mut() else {
+            return;
+        };
+        if bot.progress.eq(&1.0) {
+            return;
+        }
+        let elapsed = (Utc::now() - started_at).to_std().unwrap_or_default();
+        bot.progress = elapsed.div_duration_f64(bot.finish_time()).min(1.0);
+        let (player_id, progress, name) = (bot.id, bot.progress, bot.name.clone());
+
+        self.broadcast(BackendMessage::UpdatePlayerProgress {
+            player_id,
+            progress,
+        });
+        if progress.eq(&1.0) {
+            self.finish_race(&name, position, app_tx);
+            self.record_finish(player_id, Utc::now());
+            self.update_round_roster();
+        }
+    }
+
+    /// # Finished player count
+    ///
+    /// Returns the amount of players, including the bot, that reached the
+    /// goal.
+    pub fn finished_player_count(&self) -> usize {
+        let bot_finished = self.bot.as_ref().is_some_and(|bot| bot.progress.eq(&1.0));
+        self.players
+            .values()
+            .filter(|player| player.progress.eq(&1.0))
+            .count()
+            + usize::from(bot_finished)
+    }
+
+    /// # Finish race
+    ///
+    /// Tells all players about a player who reached the goal. The first
+    /// finisher starts the finish grace window.
+    pub fn finish_race(
+        &mut self,
+        player_name: &str,
+        position: usize,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) {
+        // Tell players that the player finished.
+        let message = format!("Player {} finished in position {}!", player_name, position);
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+
+        if position == 1 {
+            self.start_finish_grace(app_tx);
+        }
+    }
+
+    /// # Start finish grace
+    ///
+    /// Shortens the remaining play time to the finish grace window, unless
+    /// the round ends earlier anyway. Tells all players how long they have
+    /// left.
+    fn start_finish_grace(&mut self, app_tx: &UnboundedSender<AppMessage>) {
+        let LobbyStatus::InProgress(round_end) = self.status else {
+            return;
+        };
+        // The clock stands still while the round is paused.
+        let now = self.paused_at.unwrap_or_else(Utc::now);
+        let round_end = round_end.min(now + self.finish_grace);
+        let remaining = (round_end - now).to_std().unwrap_or_default();
+        self.status = LobbyStatus::InProgress(round_end);
+        self.start_round_timer(remaining, app_tx);
+
+        self.broadcast(BackendMessage::RoundEndingSoon {
+            seconds: remaining.as_secs(),
+        });
+        // Tell players in the lobby about the status update.
+        self.broadcast(BackendMessage::StatusUpdate {
+            status: self.status.clone(),
+        });
+    }
+
+    /// # Mark unfinished players
+    ///
+    /// Scores everyone still editing at the end of a race as did not finish
+    /// and tells all players about them.
+    pub fn mark_unfinished_players(&mut self) {
+        let mut names = Vec::new();
+        for entry in self
+            .round_roster
+            .iter_mut()
+            .filter(|entry| entry.state == RoundState::Editing)
+        {
+            entry.state = RoundState::DidNotFinish;
+            names.extend(roster_name(&self.players, &self.bot, entry.player_id));
+        }
+        if names.is_empty() {
+            return;
+        }
+        self.broadcast(BackendMessage::RoundRoster(self.round_roster.clone()));
+        let message = format!("Did not finish: {}.", names.join(", "));
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+    }
+
+    /// # Record finish
+    ///
+    /// Keeps how long a player took to reach the goal of the race for the
+    /// round summary.
+    pub fn record_finish(&mut self, player_id: Uuid, finished_at: DateTime<Utc>) {
+        let Some(finish_time) = self
+            .started_at
+            .and_then(|started_at| (finished_at - started_at).to_std().ok())
+        else {
+            return;
+        };
+        self.finish_times.entry(player_id).or_insert(finish_time);
+    }
+
+    /// # Round summary
+    ///
+    /// Collects the result of every player of the round roster. Race
+    /// finishers are ranked by their finish time, time attack players by
+    /// their score.
+    pub fn round_summary(&self) -> BackendMessage {
+        let mut results = self
+            .round_roster
+            .iter()
+            .filter_map(|entry| {
+                let name = roster_name(&self.players, &self.bot, entry.player_id)?;
+                Some(RoundResult {
+                    player_id: entry.player_id,
+                    name,
+                    finish_time: self.finish_times.get(&entry.player_id).copied(),
+                    rank: None,
+                    score: self
+                        .players
+                        .get(&entry.player_id)
+                        .map_or(0, |player| player.score),
+                })
+            })
+            .collect::<Vec<_>>();
+        match self.game_mode {
+            GameMode::Race => {
+                results.sort_by_key(|result| result.finish_time.unwrap_or(Duration::MAX))
+            }
+            GameMode::TimeAttack => results.sort_by_key(|result| Reverse(result.score)),
+        }
+        for (i, result) in results.iter_mut().enumerate() {
+            if self.game_mode == GameMode::TimeAttack || result.finish_time.is_some() {
+                result.rank = Some(i + 1);
+            }
+        }
+        BackendMessage::RoundSummary(RoundSummary {
+            challenge_id: self.race_challenge().id.clone(),
+            round_number: self.round_number,
+            results,
+        })
+    }
+
+    /// # Start time attack
+    ///
+    /// Resets the challenge cursor and score of every active player and hands
+    /// out the first time attack challenge.
+    pub fn start_time_attack(&mut self) {
+        let Some(challenge_files) = self.time_attack_challenges.first() else {
+            error!("Lobby {} has no time attack challenges.", self.name);
+            return;
+        };
+        let challenge_files = shared_files(challenge_files, self.goal_preview);
+        for player in self.players.values_mut().filter(|player| !player.waiting) {
+            player.challenge_index = 0;
+            player.score = 0;
+            let _ = player.tx.send(BackendMessage::ProvideChallengeFiles(
+                challenge_files.clone(),
+            ));
+        }
+    }
+
+    /// # Advance time attack challenge
+    ///
+    /// Increments the score of a player who completed the current time attack
+    /// challenge and moves him on to the next one. Tells all players about the
+    /// new score.
+    pub fn advance_time_attack_challenge(&mut self, player_id: Uuid) {
+        let Some(player) = self.players.get_mut(&player_id) else {
+            error!(
+                "Player with ID {} was not found in lobby {}.",
+                player_id, self.name
+            );
+            return;
+        };
+        player.score += 1;
+        player.challenge_index += 1;
+        player.progress = 0.0;
+        let score = player.score;
+
+        if let Some(challenge_files) = self.time_attack_challenges.get(player.challenge_index) {
+            let _ = player
+                .tx
+                .send(BackendMessage::ProvideChallengeFiles(shared_files(
+                    challenge_files,
+                    self.goal_preview,
+                )));
+        } else {
+            let message = format!("Player {} completed all challenges!", player.name);
+            self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+        }
+
+        self.broadcast(BackendMessage::UpdatePlayerScore { player_id, score });
+        self.broadcast(BackendMessage::UpdatePlayerProgress {
+            player_id,
+            progress: 0.0,
+        });
+        self.update_round_roster();
+    }
+
+    /// # Announce final scores
+    ///
+    /// Tells all players about the scores of the finished time attack round
+    /// and its winner.
+    pub fn announce_final_scores(&self) {
+        let scores = self
+            .players
+            .values()
+            .filter(|player| !player.waiting)
+            .map(|player| (player.id, player.score))
+            .collect();
+        self.broadcast(BackendMessage::FinalScores(scores));
+
+        if let Some(winner) = self
+            .players
+            .values()
+            .filter(|player| !player.waiting)
+            .max_by_key(|player| player.score)
+        {
+            let message = format!(
+                "Player {} won with a score of {}!",
+                winner.name, winner.score
+            );
+            self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+        }
+    }
+
+    /// # Start round timer
+    ///
+    /// Tells the app to finish the round after the given time. Timers that
+    /// were outdated in the meantime, e.g., by a pause, are ignored by the
+    /// app.
+    pub fn start_round_timer(&self, remaining: Duration, app_tx: &UnboundedSender<AppMessage>) {
+        let app_tx = app_tx.clone();
+        let lobby_id = self.id;
+        tokio::spawn(async move {
+            tokio::time::sleep(remaining).await;
+            let _ = app_tx.send(AppMessage::Finish { lobby_id });
+        });
+    }
+
+    /// # Pause round
+    ///
+    /// Freezes the round timer if the requesting player is the lobby owner.
+    /// Players can still finish while the round is paused.
+    pub fn pause_round(&mut self, requester_id: Uuid) {
+        if !self.is_requested_by_owner(requester_id, "pause") {
+            return;
+        }
+        if !matches!(self.status, LobbyStatus::InProgress(_)) || self.paused_at.is_some() {
+            return;
+        }
+        info!("Paused round {} of lobby {}.", self.round_number, self.name);
+        self.paused_at = Some(Utc::now());
+        self.broadcast(BackendMessage::RoundPaused);
+    }
+
+    /// # Resume round
+    ///
+    /// Moves the round end back by the time the round was paused and restarts
+    /// the round timer if the requesting player is the lobby owner.
+    pub fn resume_round(&mut self, requester_id: Uuid, app_tx: &UnboundedSender<AppMessage>) {
+        if !self.is_requested_by_owner(requester_id, "resume") {
+            return;
+        }
+        let (LobbyStatus::InProgress(round_end), Some(paused_at)) = (&self.status, self.paused_at)
+        else {
+            return;
+        };
+        let now = Utc::now();
+        let round_end = *round_end + (now - paused_at);
+        info!(
+            "Resumed round {} of lobby {} after {}s.",
+            self.round_number,
+            self.name,
+            (now - paused_at).num_seconds()
+        );
+        self.paused_at = None;
+        self.status = LobbyStatus::InProgress(round_end);
+        self.start_round_timer((round_end - now).to_std().unwrap_or_default(), app_tx);
+
+        self.broadcast(BackendMessage::RoundResumed);
+        // Tell players in the lobby about the new round end.
+        self.broadcast(BackendMessage::StatusUpdate {
+            status: self.status.clone(),
+        });
+        let _ = app_tx.send(AppMessage::SendLobbyStatusUpdate { lobby_id: self.id });
+    }
+
+    /// # Is requested by owner
+    ///
+    /// Checks whether the requesting player owns the lobby. Tells the player
+    /// otherwise that only the owner can do the requested action.
+    fn is_requested_by_owner(&self, requester_id: Uuid, action: &str) -> bool {
+        let Some(requester) = self.players.get(&requester_id) else {
+            error!(
+                "Player with ID {} was not found in lobby {}.",
+                requester_id, self.name
+            );
+            return false;
+        };
+        if self.owner == Some(requester_id) {
+            return true;
+        }
+        warn!(
+            "Player {} tried to {action} the round of lobby {} without being the owner.",
+            requester.name, self.name
+        );
+        let message = format!("Only the lobby owner can {action} the round.");
+        let _ = requester
+            .tx
+            .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+        false
+    }
+
+    /// # Force start
+    ///
+    /// Starts the countdown without waiting for all players to be ready if the
+    /// requester is the lobby owner and enough players are active. Players
+    /// that are not ready take part in the round anyway.
+    pub fn force_start(&mut self, requester_id: Uuid, app_tx: &UnboundedSender<AppMessage>) {
+        let Some(requester) = self.players.get(&requester_id) else {
+            error!(
+                "Player with ID {} was not found in lobby {}.",
+                requester_id, self.name
+            );
+            return;
+        };
+        if !self
+            .owner
+            .is_some_and(|owner_id| owner_id.eq(&requester_id))
+        {
+            warn!(
+                "Player {} tried to force the start of lobby {} without being the owner.",
+                requester.name, self.name
+            );
+            let message = "Only the lobby owner can start the round.".to_string();
+            let _ = requester
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+        if self.status != LobbyStatus::WaitingForPlayers {
+            return;
+        }
+        let active_players = self.players.values().filter(|player| !player.waiting);
+        if active_players.clone().count() < MIN_PLAYERS_TO_START {
+            let message =
+                format!("At least {MIN_PLAYERS_TO_START} player(s) are needed to start the round.");
+            let _ = requester
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+
+        // Tell players why the countdown began without them being ready.
+        if !active_players.clone().all(|player| player.ready) {
+            info!(
+                "Owner {} forced the start of lobby {}.",
+                requester.name, self.name
+            );
+            let message = format!(
+                "{} started the round without waiting for everyone to be ready.",
+                requester.name
+            );
+            self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+        }
+        self.request_countdown(app_tx);
+    }
+
+    /// # Request countdown
+    ///
+    /// Starts the countdown if the editors of all active players are ready.
+    /// Otherwise, the countdown is deferred until the last editor is ready.
+    pub fn request_countdown(&mut self, app_tx: &UnboundedSender<AppMessage>) {
+        if self
+            .players
+            .values()
+            .filter(|player| !player.waiting)
+            .all(|player| player.editor_ready)
+        {
+            self.start_requested = false;
+            self.start_countdown(app_tx);
+        } else if !self.start_requested {
+            self.start_requested = true;
+            let message = "Waiting for all editors to be ready...".to_string();
+            self.broadcast(BackendMessage::SendMessage(ChatMessage::new(message)));
+        }
+    }
+
+    /// # Expire ready phase
+    ///
+    /// Resets the ready state of every player if the last player got ready at
+    /// least `ready_timeout` ago and the round still did not start. Tells the
+    /// players about the timeout.
+    pub fn expire_ready_phase(&mut self, ready_timeout: Duration) {
+        let Some(ready_since) = self.ready_since else {
+            return;
+        };
+        if ready_since.elapsed() < ready_timeout
+            || self.status != LobbyStatus::WaitingForPlayers
+            || self.start_requested
+        {
+            return;
+        }
+        self.ready_since = None;
+        if !self.players.values().any(|player| player.ready) {
+            return;
+        }
+        info!(
+            "Resetting ready states in lobby {} after {}s.",
+            self.name,
+            ready_timeout.as_secs()
+        );
+        let ready_player_ids = self
+            .players
+            .values_mut()
+            .filter(|player| player.ready)
+            .map(|player| {
+                player.ready = false;
+                player.id
+            })
+            .collect::<Vec<_>>();
+        for player_id in ready_player_ids {
+            self.broadcast(BackendMessage::PlayerReady {
+                player_id,
+                ready: false,
+            });
+        }
+        self.broadcast(BackendMessage::ReadyTimeout);
+    }
+
+    /// # Editor ready
+    ///
+    /// Marks the editor of a player as ready and starts a deferred countdown
+    /// if this was the last editor to wait for.
+    pub fn editor_ready(&mut self, player_id: Uuid, app_tx: &UnboundedSender<AppMessage>) {
+        let Some(player) = self.players.get_mut(&player_id) else {
+            error!(
+                "Player with ID {} was not found in lobby {}.",
+                player_id, self.name
+            );
+            return;
+        };
+        player.editor_ready = true;
+        if self.start_requested && self.status == LobbyStatus::WaitingForPlayers {
+            self.request_countdown(app_tx);
+        }
+    }
+
+    /// # Start countdown
+    ///
+    /// Puts the lobby into `LobbyStatus::AboutToStart` and tells the
+    /// application to start the game after `LOBBY_START_TIMER`.
+    pub fn start_countdown(&mut self, app_tx: &UnboundedSender<AppMessage>) {
+        // Change the lobby status and tell clients about it.
+        self.status = LobbyStatus::AboutToStart(Utc::now() + LOBBY_START_TIMER);
+        let _ = app_tx.send(AppMessage::SendLobbyStatusUpdate { lobby_id: self.id });
+        // Tell players in the lobby about the status update.
+        self.broadcast(BackendMessage::StatusUpdate {
+            status: self.status.clone(),
+        });
+
+        // Wait for a duration of `LOBBY_START_TIMER` and tell the application
+        // to start the lobby.
+        let app_tx = app_tx.clone();
+        let lobby_id = self.id;
+        tokio::spawn(async move {
+            tokio::time::sleep(LOBBY_START_TIMER).await;
+            let _ = app_tx.send(AppMessage::Start { lobby_id });
+        });
+    }
+
+    /// # Find player by name
+    ///
+    /// Returns the player with the given name. Names are compared case
+    /// insensitively.
+    pub fn find_player_by_name(&self, name: &str) -> Option<&Player> {
+        self.players
+            .values()
+            .find(|player| player.name.eq_ignore_ascii_case(name))
+    }
+
+    /// # Unique player name
+    ///
+    /// Returns the given name if no other player or the bot carries it
+    /// already, compared case insensitively. Appends the lowest free number
+    /// otherwise, e.g., `Jane Doe 2`.
+    pub fn unique_player_name(&self, name: &str, player_id: Uuid) -> String {
+        let is_taken = |candidate: &str| {
+            self.players
+                .values()
+                .any(|player| player.id != player_id && player.name.eq_ignore_ascii_case(candidate))
+                || self
+                    .bot
+                    .as_ref()
+                    .is_some_and(|bot| bot.name.eq_ignore_ascii_case(candidate))
+        };
+        if !is_taken(name) {
+            return name.to_string();
+        }
+        (2..)
+            .map(|number| format!("{name} {number}"))
+            .find(|candidate| !is_taken(candidate))
+            .expect("Finding a free player name")
+    }
+
+    /// # Rename player
+    ///
+    /// Changes the name of a player following the same rules as on join. The
+    /// name is deduplicated against the other players of the lobby. Tells the
+    /// player if the name is invalid. Chat messages sent before keep the old
+    /// name.
+    pub fn rename_player(&mut self, player: Player, new_name: String) {
+        let new_name = match validate_player_name(&new_name) {
+            Ok(new_name) => self.unique_player_name(&new_name, player.id),
+            Err(e) => {
+                let _ = player
+                    .tx
+                    .send(BackendMessage::SendMessage(ChatMessage::new(e.to_string())));
+                return;
+            }
+        };
+        let Some(player) = self.players.get_mut(&player.id) else {
+            error!(
+                "Player {} was not found in lobby {}.",
+                player.name, self.name
+            );
+            return;
+        };
+        if player.name == new_name {
+            return;
+        }
+        let old_name = std::mem::replace(&mut player.name, new_name.clone());
+        let player_id = player.id;
+        info!(
+            "Player {} renamed to {} in lobby {}.",
+            old_name, new_name, self.name
+        );
+
+        self.broadcast(BackendMessage::PlayerRenamed {
+            player_id,
+            new_name: new_name.clone(),
+        });
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(format!(
+            "{old_name} is now known as {new_name}."
+        ))));
+    }
+
+    /// # Whisper
+    ///
+    /// Sends a private message from a player to the player with the given
+    /// name. Tells the sender if there is no such player. Muted players can not
+    /// whisper either, and whispers pass the chat filter like other messages.
+    pub fn whisper(
+        &self,
+        player: Player,
+        name: &str,
+        message: String,
+        chat_filter: Option<&dyn ChatFilter>,
+    ) {
+        let Some(sender) = self.players.get(&player.id) else {
+            error!(
+                "Player {} was not found in lobby {}.",
+                player.name, self.name
+            );
+            return;
+        };
+        if sender.muted {
+            let _ = sender.tx.send(BackendMessage::YouAreMuted);
+            return;
+        }
+        let Some(receiver) = self.find_player_by_name(name) else {
+            let message = format!("Player {name} was not found.");
+            let _ = sender
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        };
+        let Some(message) = self.filter_message(sender, message, chat_filter) else {
+            return;
+        };
+        let _ = receiver
+            .tx
+            .send(BackendMessage::SendMessage(ChatMessage::new(format!(
+                "{} whispers: {message}",
+                sender.name
+            ))));
+        let _ = sender
+            .tx
+            .send(BackendMessage::SendMessage(ChatMessage::new(format!(
+                "To {}: {message}",
+                receiver.name
+            ))));
+    }
+
+    /// # Set player muted
+    ///
+    /// Mutes or unmutes the chat of the target player if the provided player is
+    /// the lobby owner. Muted players stay muted until the owner unmutes them or
+    /// they leave the lobby.
+    pub fn set_player_muted(&mut self, player: Player, target_id: Uuid, muted: bool) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to mute a player in lobby {} without being the owner.",
+                player.name, self.name
+            );
+            return;
+        }
+        if target_id.eq(&player.id) {
+            let message = "You can not mute yourself.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+        let Some(target) = self.players.get_mut(&target_id) else {
+            let message = "Player was not found.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        };
+        target.muted = muted;
+        let name = target.name.clone();
+        info!(
+            "Player {} set muted of {} to {} in lobby {}.",
+            player.name, name, muted, self.name
+        );
+
+        self.broadcast(BackendMessage::PlayerMuted {
+            player_id: target_id,
+            muted,
+        });
+        let action = if muted { "muted" } else { "unmuted" };
+        self.broadcast(BackendMessage::SendMessage(ChatMessage::new(format!(
+            "{name} was {action} by the lobby owner."
+        ))));
+    }
+
+    /// # Submit replay
+    ///
+    /// Stores the editor inputs of a player for the last round. Replays are
+    /// only accepted once the round is over.
+    pub fn submit_replay(&mut self, player: Player, frames: Vec<ReplayFrame>) {
+        let Some(player) = self.players.get(&player.id) else {
+            error!(
+                "Player {} was not found in lobby {}.",
+                player.name, self.name
+            );
+            return;
+        };
+        if let LobbyStatus::InProgress(_) = self.status {
+            warn!(
+                "Ignoring replay of player {} in lobby {}.",
+                player.name, self.name
+            );
+            return;
+        }
+        info!(
+            "Stored replay with {} frames of player {} in lobby {}.",
+            frames.len(),
+            player.name,
+            self.name
+        );
+        self.replays.insert(player.name.clone(), frames);
+    }
+
+    /// # Send replay
+    ///
+    /// Sends the last round replay of the player with the given name to the
+    /// provided player. Tells the player if there is no such replay.
+    pub fn send_replay(&self, player: Player, name: &str) {
+        let message = match self.replays.get(name) {
+            Some(frames) => BackendMessage::Replay {
+                frames: frames.clone(),
+            },
+            None => BackendMessage::SendMessage(ChatMessage::new(format!(
+                "There is no replay of {name}."
+            ))),
+        };
+        let _ = player.tx.send(message);
+    }
+
+    /// # Kick player
+    ///
+    /// Removes the player with the given name from the lobby if the provided
+    /// player is the lobby owner. The owner can not kick himself. The kicked
+    /// player and the remaining lobby are told about the reason, which falls
+    /// back to `DEFAULT_KICK_REASON`.
+    pub fn kick_player(
+        &mut self,
+        player: Player,
+        name: &str,
+        reason: Option<String>,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) {
+        if !self.owner.is_some_and(|owner_id| owner_id.eq(&player.id)) {
+            warn!(
+                "Player {} tried to kick {} from lobby {} without being the owner.",
+                player.name, name, self.name
+            );
+            return;
+        }
+        let Some(target) = self.find_player_by_name(name).cloned() else {
+            let message = format!("Player {name} was not found.");
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        };
+        if target.id.eq(&player.id) {
+            let message = "You can not kick yourself.".to_string();
+            let _ = player
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+
+        let reason = reason.unwrap_or_else(|| DEFAULT_KICK_REASON.to_string());
+        info!(
+            "Player {} kicked {} from lobby {}: {reason}",
+            player.name, target.name, self.name
+        );
+        self.remove_kicked_player(target, reason, app_tx);
+    }
+
+    /// # Vote kick
+    ///
+    /// Counts the vote of a player to kick the target player and tells the
+    /// lobby about the tally. Every player votes once per target and can not
+    /// vote against himself. The target is kicked with `VOTE_KICK_REASON` as
+    /// soon as a majority of the other players voted for it. The target does
+    /// not count towards the electorate, as it can not vote for its own kick,
+    /// so a single vote kicks in a lobby of two.
+    pub fn vote_kick(
+        &mut self,
+        voter_id: Uuid,
+        target_id: Uuid,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) {
+        let Some(voter) = self.players.get(&voter_id) else {
+            warn!(
+                "Player with ID {} tried to vote in lobby {} without being part of it.",
+                voter_id, self.name
+            );
+            return;
+        };
+        if voter_id.eq(&target_id) {
+            let message = "You can not vote to kick yourself.".to_string();
+            let _ = voter
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        }
+        let Some(target) = self.players.get(&target_id).cloned() else {
+            let message = "The player you voted against is not in the lobby.".to_string();
+            let _ = voter
+                .tx
+                .send(BackendMessage::SendMessage(ChatMessage::new(message)));
+            return;
+        };
+
+        let voters = self.kick_votes.entry(target_id).or_default();
+        if !voters.insert(voter_id) {
+            debug!(
+                "Player {} already voted to kick {} in lobby {}.",
+                voter.name, target.name, self.name
+            );
+            return;
+        }
+        let votes = voters.len();
+        let needed = (self.players.len() - 1) / 2 + 1;
+        info!(
+            "Player {} voted to kick {} from lobby {} ({votes}/{needed}).",
+            voter.name, target.name, self.name
+        );
+        self.broadcast(BackendMessage::VoteKickProgress {
+            target_id,
+            votes,
+            needed,
+        });
+        if votes >= needed {
+            info!("Lobby {} voted to kick {}.", self.name, target.name);
+            self.remove_kicked_player(target, VOTE_KICK_REASON.to_string(), app_tx);
+        }
+    }
+
+    /// # Remove kicked player
+    ///
+    /// Tells the kicked player and the remaining lobby about the reason and
+    /// removes the player.
+    fn remove_kicked_player(
+        &mut self,
+        target: Player,
+        reason: String,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) {
+        let _ = target.tx.send(BackendMessage::Kicked {
+            reason: reason.clone(),
+        });
+        let _ = target.tx.send(BackendMessage::CloseConnection {
+            reason: Some(CloseReason::Kicked),
+        });
+        // Announce the kick before the removal, so that players can still
+        // look up the name of the kicked player. The kicked player already
+        // knows.
+        self.broadcast_except(
+            target.id,
+            BackendMessage::PlayerKicked {
+                player_id: target.id,
+                reason,
+            },
+        );
+        self.remove_player(target, app_tx);
+    }
+
+    /// # Send message
+    ///
+    /// Broadcasts a message from a player to all connnected players if the
+    /// player exists. Muted players are told that they are muted instead.
+    /// Messages caught by the chat filter are masked or dropped, and the
+    /// sender is told about it.
+    pub fn send_message(
+        &self,
+        player: Player,
+        message: String,
+        chat_filter: Option<&dyn ChatFilter>,
+    ) {
+        if let Some(player) = self.players.get(&player.id) {
+            if player.muted {
+                let _ = player.tx.send(BackendMessage::YouAreMuted);
+                return;
+            }
+            let Some(message) = self.filter_message(player, message, chat_filter) else {
+                return;
+            };
+            let message = BackendMessage::SendMessage(ChatMessage::new(format!(
+                "{}: {message}",
+                player.name
+            )));
+            self.broadcast(message);
+        } else {
+            error!(
+                "Player {} was not found in lobby {}.",
+                player.name, self.name
+            );
+        }
+    }
+
+    /// # Filter message
+    ///
+    /// Runs a chat message of the given player through the chat filter. Returns
+    /// the message to send, if any, and tells the player if it was filtered.
+    fn filter_message(
+        &self,
+        player: &Player,
+        message: String,
+        chat_filter: Option<&dyn ChatFilter>,
+    ) -> Option<String> {
+        match chat_filter.map(|chat_filter| chat_filter.filter(&message)) {
+            None | Some(FilterOutcome::Pass) => Some(message),
+            Some(FilterOutcome::Mask(masked)) => {
+                let _ = player.tx.send(BackendMessage::MessageFiltered);
+                Some(masked)
+            }
+            Some(FilterOutcome::Reject) => {
+                info!(
+                    "Rejected chat message of player {} in lobby {}.",
+                    player.name, self.name
+                );
+                let _ = player.tx.send(BackendMessage::MessageFiltered);
+                None
+            }
+        }
+    }
+}
+
+/// # Shared files
+///
+/// Returns the challenge files as handed out to players. The goal file is
+/// left empty if the goal preview is disabled.
+pub fn shared_files(files: &ChallengeFiles, goal_preview: bool) -> ChallengeFiles {
+    ChallengeFiles {
+        start_file: files.start_file.clone(),
+        goal_file: if goal_preview {
+            files.goal_file.clone()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// # Validate lobby description
+///
+/// Trims the given description and checks that it is at most
+/// `MAX_LOBBY_DESCRIPTION_LENGTH` characters long and free of control
+/// characters. Returns the trimmed description, or `None` if it is empty.
+pub fn validate_lobby_description(description: &str) -> Result<Option<String>> {
+    let description = description.trim();
+    if description.is_empty() {
+        return Ok(None);
+    }
+    if description.chars().count() > MAX_LOBBY_DESCRIPTION_LENGTH {
+        return Err(anyhow!(
+            "Descriptions can be at most {MAX_LOBBY_DESCRIPTION_LENGTH} characters long."
+        ));
+    }
+    if description.chars().any(char::is_control) {
+        return Err(anyhow!("Descriptions can not contain control characters."));
+    }
+    Ok(Some(description.to_string()))
+}
+
+/// # Roster name
+///
+/// Returns the name of a player or the bot of the round roster.
+fn roster_name(
+    players: &BTreeMap<Uuid, Player>,
+    bot: &Option<Bot>,
+    player_id: Uuid,
+) -> Option<String> {
+    match players.get(&player_id) {
+        Some(player) => Some(player.name.clone()),
+        None => bot
+            .as_ref()
+            .filter(|bot| bot.id == player_id)
+            .map(|bot| bot.name.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+
+    fn test_lobby(max_players: usize) -> Lobby {
+        let challenge = Challenge {
+            id: String::from("test"),
+            name: String::from("Test"),
+            language: String::from("rust"),
+            difficulty: Difficulty::default(),
+            description: None,
+            comparison: Comparison::default(),
+            time_limit: None,
+            binary: false,
+            files: ChallengeFiles {
+                start_file: b"fn main() {}\n".to_vec(),
+                goal_file: b"fn main() {\n    println!(\"Hello\");\n}\n".to_vec(),
+            },
+        };
+        Lobby::new(
+            challenge,
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+            max_players,
+            4,
+        )
+    }
+
+    /// Adds a new player to the lobby. Returns the player as stored in the
+    /// lobby and its receiver without the messages of the join.
+    fn join(
+        lobby: &mut Lobby,
+        app_tx: &UnboundedSender<AppMessage>,
+    ) -> (Player, UnboundedReceiver<BackendMessage>) {
+        let (tx, mut rx) = unbounded_channel();
+        let player = Player::new(tx);
+        assert!(lobby.add_player(player.clone(), app_tx));
+        received(&mut rx);
+        (lobby.players[&player.id].clone(), rx)
+    }
+
+    fn received(rx: &mut UnboundedReceiver<BackendMessage>) -> Vec<BackendMessage> {
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    fn chat_lines(messages: &[BackendMessage]) -> Vec<&str> {
+        messages
+            .iter()
+            .filter_map(|msg| match msg {
+                BackendMessage::SendMessage(message) => Some(message.text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn muted_players_are_not_relayed() {
+        let (app_tx, _app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (owner, _owner_rx) = join(&mut lobby, &app_tx);
+        let (muted, mut muted_rx) = join(&mut lobby, &app_tx);
+        let (_, mut other_rx) = join(&mut lobby, &app_tx);
+
+        lobby.set_player_muted(owner.clone(), muted.id, true);
+        received(&mut muted_rx);
+        received(&mut other_rx);
+        lobby.send_message(muted.clone(), String::from("spam"), None);
+        assert!(chat_lines(&received(&mut other_rx)).is_empty());
+        assert!(matches!(
+            received(&mut muted_rx).as_slice(),
+            [BackendMessage::YouAreMuted]
+        ));
+
+        // Unmuting relays the chat again.
+        lobby.set_player_muted(owner, muted.id, false);
+        received(&mut other_rx);
+        lobby.send_message(muted.clone(), String::from("hi"), None);
+        assert_eq!(
+            chat_lines(&received(&mut other_rx)),
+            [format!("{}: hi", muted.name)]
+        );
+    }
+
+    #[test]
+    fn excluded_player_receives_nothing() {
+        let (app_tx, _app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (excluded, mut excluded_rx) = join(&mut lobby, &app_tx);
+        let (_, mut other_rx) = join(&mut lobby, &app_tx);
+        received(&mut excluded_rx);
+
+        lobby.broadcast_except(
+            excluded.id,
+            BackendMessage::SendMessage(ChatMessage::new(String::from("hi"))),
+        );
+        assert!(received(&mut excluded_rx).is_empty());
+        assert_eq!(chat_lines(&received(&mut other_rx)), ["hi"]);
+    }
+
+    #[test]
+    fn renaming_to_a_taken_name_appends_a_number() {
+        let (app_tx, _app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (taken, _taken_rx) = join(&mut lobby, &app_tx);
+        let (renamed, mut renamed_rx) = join(&mut lobby, &app_tx);
+        lobby.players.get_mut(&taken.id).unwrap().name = String::from("Jane");
+
+        lobby.rename_player(renamed.clone(), String::from(" jane "));
+        assert_eq!(lobby.players[&renamed.id].name, "jane 2");
+        assert!(received(&mut renamed_rx).iter().any(|msg| matches!(
+            msg,
+            BackendMessage::PlayerRenamed { player_id, new_name }
+                if *player_id == renamed.id && new_name == "jane 2"
+        )));
+
+        // Keeping the own name does not count as a clash.
+        lobby.rename_player(renamed.clone(), String::from("Jane 2"));
+        assert_eq!(lobby.players[&renamed.id].name, "Jane 2");
+    }
+
+    #[test]
+    fn renaming_to_a_blank_name_is_rejected() {
+        let (app_tx, _app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (renamed, mut renamed_rx) = join(&mut lobby, &app_tx);
+
+        lobby.rename_player(renamed.clone(), String::from("   "));
+        assert_eq!(lobby.players[&renamed.id].name, renamed.name);
+        assert_eq!(
+            chat_lines(&received(&mut renamed_rx)),
+            ["Names can not be empty."]
+        );
+    }
+
+    #[test]
+    fn majority_of_the_other_players_kicks() {
+        let (app_tx, _app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (first, _first_rx) = join(&mut lobby, &app_tx);
+        let (second, _second_rx) = join(&mut lobby, &app_tx);
+        let (_, _third_rx) = join(&mut lobby, &app_tx);
+        let (target, mut target_rx) = join(&mut lobby, &app_tx);
+
+        // Three players vote, so two votes are a majority.
+        lobby.vote_kick(first.id, target.id, &app_tx);
+        assert!(lobby.players.contains_key(&target.id));
+        assert!(received(&mut target_rx).iter().any(|msg| matches!(
+            msg,
+            BackendMessage::VoteKickProgress {
+                votes: 1,
+                needed: 2,
+                ..
+            }
+        )));
+
+        lobby.vote_kick(second.id, target.id, &app_tx);
+        assert!(!lobby.players.contains_key(&target.id));
+        assert!(received(&mut target_rx)
+            .iter()
+            .any(|msg| matches!(msg, BackendMessage::Kicked { .. })));
+    }
+
+    #[test]
+    fn settings_survive_a_round_reset() {
+        let (app_tx, _app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (player, _rx) = join(&mut lobby, &app_tx);
+        lobby.max_players = 3;
+        lobby.game_mode = GameMode::TimeAttack;
+        lobby.time_limit = Some(Duration::from_secs(90));
+        lobby.comparison = Some(Comparison::Tokens);
+        lobby.spectators_allowed = false;
+        lobby.goal_preview = true;
+        lobby.round_number = 2;
+        lobby.started_at = Some(Utc::now());
+        lobby.status = LobbyStatus::Finish(Utc::now());
+        let stored = lobby.players.get_mut(&player.id).unwrap();
+        stored.ready = true;
+        stored.progress = 1.0;
+        stored.score = 3;
+        stored.challenge_index = 2;
+
+        lobby.reset_round(&app_tx);
+        assert_eq!(lobby.max_players, 3);
+        assert_eq!(lobby.game_mode, GameMode::TimeAttack);
+        assert_eq!(lobby.time_limit, Some(Duration::from_secs(90)));
+        assert_eq!(lobby.comparison, Some(Comparison::Tokens));
+        assert!(!lobby.spectators_allowed);
+        assert!(lobby.goal_preview);
+        // The series goes on, so the next round counts up from here.
+        assert_eq!(lobby.round_number, 2);
+
+        assert_eq!(lobby.status, LobbyStatus::WaitingForPlayers);
+        assert!(lobby.started_at.is_none());
+        let stored = &lobby.players[&player.id];
+        assert!(!stored.ready);
+        assert_eq!(stored.progress, 0.0);
+        assert_eq!(stored.score, 0);
+        assert_eq!(stored.challenge_index, 0);
+    }
+
+    #[test]
+    fn joins_are_rejected_once_the_lobby_left_waiting() {
+        let (app_tx, mut app_rx) = unbounded_channel();
+        let mut lobby = test_lobby(4);
+        let (leader, _leader_rx) = join(&mut lobby, &app_tx);
+        while app_rx.try_recv().is_ok() {}
+        let late_player = || {
+            let (tx, rx) = unbounded_channel();
+            let mut player = Player::new(tx);
+            player.client_id = Some(Uuid::new_v4());
+            (player, rx)
+        };
+
+        lobby.status = LobbyStatus::AboutToStart(Utc::now());
+        let (player, mut rx) = late_player();
+        assert!(!lobby.add_player(player, &app_tx));
+        assert!(matches!(
+            received(&mut rx).as_slice(),
+            [BackendMessage::RoundStarting]
+        ));
+
+        // The client watches the round instead.
+        lobby.status = LobbyStatus::InProgress(Utc::now());
+        let (player, mut rx) = late_player();
+        assert!(!lobby.add_player(player.clone(), &app_tx));
+        assert!(matches!(
+            received(&mut rx).as_slice(),
+            [BackendMessage::RoundInProgress { spectating: true }]
+        ));
+        assert!(matches!(
+            app_rx.try_recv(),
+            Ok(AppMessage::SpectatePlayer { client_id, target_id, .. })
+                if Some(client_id) == player.client_id && target_id == leader.id
+        ));
+
+        lobby.spectators_allowed = false;
+        let (player, mut rx) = late_player();
+        assert!(!lobby.add_player(player, &app_tx));
+        assert!(matches!(
+            received(&mut rx).as_slice(),
+            [BackendMessage::RoundInProgress { spectating: false }]
+        ));
+        assert!(app_rx.try_recv().is_err());
+
+        lobby.status = LobbyStatus::Finish(Utc::now());
+        let (player, mut rx) = late_player();
+        assert!(!lobby.add_player(player, &app_tx));
+        assert!(matches!(
+            received(&mut rx).as_slice(),
+            [BackendMessage::LobbyNotWaitingForPlayers]
+        ));
+        assert_eq!(lobby.players.len(), 1);
     }
 }
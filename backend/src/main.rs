@@ -1,5 +1,11 @@
+use std::process::ExitCode;
+
+use clap::Parser;
 use routes::lobbies;
-use tokio::sync::mpsc::unbounded_channel;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedSender},
+    oneshot,
+};
 use warp::{reply, Filter};
 
 use crate::{
@@ -7,32 +13,173 @@ use crate::{
         message::{handle_app_message, AppMessage},
         App,
     },
-    routes::{clients, players},
+    args::{Args, Command},
+    config::Config,
+    constants::{CONNECTION_SAMPLE_INTERVAL, PREWARM_INTERVAL, SHUTDOWN_FLUSH_DELAY},
+    rate_limit::ConnectionLimiter,
+    routes::{clients, health, players},
 };
 
 mod app;
+mod args;
+mod bot;
+mod challenge;
+mod chat_filter;
+mod comparison;
+mod config;
 mod constants;
 mod lobby;
+mod metrics;
 mod player;
+mod rate_limit;
+mod record;
+mod rotation;
 mod routes;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
+    let args = Args::parse();
+
+    // Check the challenges of the given directory instead of serving.
+    if let Some(Command::Validate { directory }) = args.command {
+        return match challenge::validate_catalog(&directory) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Error loading config: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
 
     // Setup app, communication channel and message handler.
     let (app_tx, app_rx) = unbounded_channel();
-    let app = App::new(app_tx.clone(), app_rx);
+    let app = match App::new(app_tx.clone(), app_rx, &config) {
+        Ok(app) => app,
+        Err(e) => {
+            tracing::error!("Error loading chat filter: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // Fail fast instead of on the first lobby if no challenge can be raced.
+    if let Err(e) = challenge::check_catalog(
+        &app.challenges,
+        &config.challenge_directory,
+        config.min_challenges,
+    ) {
+        tracing::error!("Error loading challenge catalog: {e}");
+        return ExitCode::FAILURE;
+    }
     tokio::spawn(handle_app_message(app));
 
-    let health = warp::path("health").map(reply);
+    // Periodically sample the connection counts for the connection history.
+    let sample_tx = app_tx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CONNECTION_SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if sample_tx.send(AppMessage::SampleConnectionCounts).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Periodically top up the lobbies kept ready for quickplay.
+    if config.prewarmed_lobbies > 0 {
+        let prewarm_tx = app_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PREWARM_INTERVAL);
+            loop {
+                interval.tick().await;
+                if prewarm_tx.send(AppMessage::PrewarmLobbies).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    // Periodically send the server time for the clock sync of players.
+    if !config.server_time_interval.is_zero() {
+        let time_tx = app_tx.clone();
+        let period = config.server_time_interval;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if time_tx.send(AppMessage::BroadcastServerTime).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    // Reload the challenge catalog whenever the process receives a SIGHUP.
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let reload_tx = app_tx.clone();
+        match signal(SignalKind::hangup()) {
+            Ok(mut hangup) => {
+                tokio::spawn(async move {
+                    while hangup.recv().await.is_some() {
+                        if reload_tx.send(AppMessage::ReloadChallenges).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            Err(e) => tracing::error!("Error listening for SIGHUP: {e}"),
+        }
+    }
+
+    let health = warp::path("health")
+        .map(reply)
+        .or(health::routes(app_tx.clone()));
 
     // Build routes.
-    let player_routes = players::routes(app_tx.clone());
-    let client_routes = clients::routes(app_tx.clone());
+    let limiter =
+        ConnectionLimiter::new(config.max_connections_per_ip, config.connection_rate_window);
+    let player_routes = players::routes(app_tx.clone(), limiter.clone(), config.max_message_size);
+    let client_routes = clients::routes(
+        app_tx.clone(),
+        limiter,
+        config.client_idle_timeout,
+        config.max_message_size,
+    );
     let lobby_routes = lobbies::routes(app_tx.clone());
+    let metric_routes = routes::metrics::routes(app_tx.clone());
+    let admin_routes = routes::admin::routes(app_tx.clone(), config.admin_token.clone());
 
     // Serve routes.
-    let routes = health.or(client_routes.or(player_routes.or(lobby_routes)));
-    warp::serve(routes).run(([0, 0, 0, 0], 3030)).await;
+    let routes = health
+        .or(client_routes.or(player_routes.or(lobby_routes.or(metric_routes).or(admin_routes))));
+    tokio::select! {
+        _ = warp::serve(routes).run(([0, 0, 0, 0], config.port)) => {}
+        _ = tokio::signal::ctrl_c() => shut_down(app_tx).await,
+    }
+    ExitCode::SUCCESS
+}
+
+/// # Shut down
+///
+/// Announces the shutdown to every connected client and player and gives
+/// their sockets a moment to deliver it.
+async fn shut_down(app_tx: UnboundedSender<AppMessage>) {
+    tracing::info!("Received interrupt signal, shutting down.");
+    let (tx, rx) = oneshot::channel();
+    if app_tx.send(AppMessage::Shutdown { tx }).is_err() || rx.await.is_err() {
+        tracing::error!("Error announcing the shutdown.");
+        return;
+    }
+    tokio::time::sleep(SHUTDOWN_FLUSH_DELAY).await;
 }
@@ -0,0 +1,48 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::constants::MAX_CONNECTION_SAMPLES;
+
+/// Amount of connected clients and players at a point in time.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConnectionSample {
+    pub timestamp: DateTime<Utc>,
+    pub clients: usize,
+    pub players: usize,
+}
+
+/// Lobby counts of the quickplay pre-warming.
+#[derive(Clone, Debug, Serialize)]
+pub struct LobbyMetrics {
+    pub lobbies: usize,
+    /// Lobbies quickplay could put a player into right now.
+    pub quickplay_ready: usize,
+    /// Configured amount of lobbies kept ready for quickplay.
+    pub prewarm_target: usize,
+}
+
+/// Rolling history of connection counts for operator dashboards. Holds at most
+/// `MAX_CONNECTION_SAMPLES` samples, the oldest ones are dropped first.
+#[derive(Debug, Default)]
+pub struct ConnectionHistory {
+    samples: VecDeque<ConnectionSample>,
+}
+
+impl ConnectionHistory {
+    pub fn record(&mut self, clients: usize, players: usize) {
+        if self.samples.len() >= MAX_CONNECTION_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ConnectionSample {
+            timestamp: Utc::now(),
+            clients,
+            players,
+        });
+    }
+
+    pub fn samples(&self) -> Vec<ConnectionSample> {
+        self.samples.iter().cloned().collect()
+    }
+}
@@ -1,8 +1,12 @@
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use fake::{faker::name::raw::Name, locales::EN, Fake};
 use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
-use common::BackendMessage;
+use common::{constants::MAX_PLAYER_NAME_LENGTH, BackendMessage};
 
 #[derive(Clone, Debug)]
 pub struct Player {
@@ -11,6 +15,27 @@ pub struct Player {
     pub tx: UnboundedSender<BackendMessage>,
     pub progress: f64,
     pub waiting: bool,
+    /// Whether the player is ready to start the game. The game starts as soon
+    /// as all active players are ready.
+    pub ready: bool,
+    /// Whether the editor of the player finished starting up.
+    pub editor_ready: bool,
+    /// Index of the time attack challenge the player is currently working on.
+    pub challenge_index: usize,
+    /// Amount of time attack challenges the player completed.
+    pub score: usize,
+    /// Whether the lobby owner muted the player in the chat.
+    pub muted: bool,
+    /// The time the player connected.
+    pub joined_at: DateTime<Utc>,
+    /// Secret the player presents to take back the seat after a disconnect.
+    pub reconnect_token: Uuid,
+    /// The time the connection of the player was lost, if it currently is.
+    pub disconnected_at: Option<Instant>,
+    /// ID of the lobby browser connection the player joined from. Players
+    /// without the ID of a connected client are not seated. Stays the same
+    /// across all player connections of a client.
+    pub client_id: Option<Uuid>,
 }
 
 impl Player {
@@ -21,6 +46,15 @@ impl Player {
             tx,
             progress: 0.0,
             waiting: false,
+            ready: false,
+            editor_ready: false,
+            challenge_index: 0,
+            score: 0,
+            muted: false,
+            joined_at: Utc::now(),
+            reconnect_token: Uuid::new_v4(),
+            disconnected_at: None,
+            client_id: None,
         }
     }
 
@@ -30,6 +64,32 @@ impl Player {
             name: self.name.clone(),
             progress: self.progress,
             waiting: self.waiting,
+            ready: self.ready,
+            score: self.score,
+            bot: None,
+            muted: self.muted,
+            joined_at: Some(self.joined_at),
         }
     }
 }
+
+/// # Validate player name
+///
+/// Trims the given name and checks that it is neither empty nor longer than
+/// `MAX_PLAYER_NAME_LENGTH` characters and free of control characters.
+/// Returns the trimmed name.
+pub fn validate_player_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Names can not be empty."));
+    }
+    if name.chars().count() > MAX_PLAYER_NAME_LENGTH {
+        return Err(anyhow!(
+            "Names can be at most {MAX_PLAYER_NAME_LENGTH} characters long."
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(anyhow!("Names can not contain control characters."));
+    }
+    Ok(name.to_string())
+}
@@ -0,0 +1,72 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tracing::warn;
+
+use crate::constants::MAX_TRACKED_ADDRESSES;
+
+/// Limits how many connections a single IP address can open within a sliding
+/// window.
+#[derive(Clone, Debug)]
+pub struct ConnectionLimiter {
+    attempts: Arc<Mutex<BTreeMap<IpAddr, VecDeque<Instant>>>>,
+    max_connections: usize,
+    window: Duration,
+}
+
+impl ConnectionLimiter {
+    pub fn new(max_connections: usize, window: Duration) -> Self {
+        Self {
+            attempts: Arc::default(),
+            max_connections,
+            window,
+        }
+    }
+
+    /// # Allow connection
+    ///
+    /// Records a connection attempt of the given peer and returns whether it
+    /// stays within the limit. Rejected attempts do not count towards the
+    /// limit. Peers without a known address are always allowed.
+    pub fn allow(&self, peer: Option<SocketAddr>) -> bool {
+        let Some(peer) = peer else {
+            return true;
+        };
+        let now = Instant::now();
+        let Ok(mut attempts) = self.attempts.lock() else {
+            return true;
+        };
+
+        // Forget addresses that did not connect within the window.
+        if attempts.len() > MAX_TRACKED_ADDRESSES {
+            attempts.retain(|_, timestamps| {
+                timestamps
+                    .back()
+                    .is_some_and(|last| now.duration_since(*last) < self.window)
+            });
+        }
+
+        let timestamps = attempts.entry(peer.ip()).or_default();
+        while timestamps
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= self.window)
+        {
+            timestamps.pop_front();
+        }
+        if timestamps.len() >= self.max_connections {
+            warn!(
+                "Rejected connection of {} after {} connections within {}s.",
+                peer.ip(),
+                timestamps.len(),
+                self.window.as_secs()
+            );
+            return false;
+        }
+        timestamps.push_back(now);
+        true
+    }
+}
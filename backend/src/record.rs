@@ -0,0 +1,42 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use common::Record;
+
+/// Fastest solve times per challenge. There is no persistence layer yet, so
+/// records only live as long as the backend process.
+#[derive(Debug, Default)]
+pub struct Records {
+    records: BTreeMap<String, Record>,
+}
+
+impl Records {
+    pub fn get(&self, challenge_id: &str) -> Option<&Record> {
+        self.records.get(challenge_id)
+    }
+
+    /// # Submit time
+    ///
+    /// Stores the solve time of a player if it beats the current record of the
+    /// challenge or there is no record yet. Returns the new record, if any.
+    pub fn submit(
+        &mut self,
+        challenge_id: &str,
+        time: Duration,
+        holder_name: &str,
+    ) -> Option<&Record> {
+        if self
+            .records
+            .get(challenge_id)
+            .is_some_and(|record| record.best_time <= time)
+        {
+            return None;
+        }
+        let record = Record {
+            challenge_id: challenge_id.to_string(),
+            best_time: time,
+            holder_name: holder_name.to_string(),
+        };
+        self.records.insert(challenge_id.to_string(), record);
+        self.records.get(challenge_id)
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use anyhow::{anyhow, Error};
+
+use common::Difficulty;
+
+use crate::challenge::Challenge;
+
+/// Challenge IDs with their weight inside the quickplay rotation, parsed from
+/// a comma separated list like `hello_world:2,fizzbuzz`. Challenges without a
+/// weight count once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RotationWeights(pub BTreeMap<String, usize>);
+
+impl FromStr for RotationWeights {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = BTreeMap::new();
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (id, weight) = match entry.split_once(':') {
+                Some((id, weight)) => (
+                    id.trim(),
+                    weight
+                        .trim()
+                        .parse()
+                        .map_err(|e| anyhow!("Invalid weight of {id}: {e}"))?,
+                ),
+                None => (entry, 1),
+            };
+            weights.insert(id.to_string(), weight);
+        }
+        Ok(Self(weights))
+    }
+}
+
+/// Picks the challenges of lobbies created by quickplay. Uses a smooth
+/// weighted round-robin over the eligible challenges of the catalog, so that
+/// heavier challenges come up more often without clustering, and never picks
+/// the same challenge twice in a row if there is another one.
+#[derive(Debug, Default)]
+pub struct ChallengeRotation {
+    /// Challenges pinned into the rotation with their weights. An empty map
+    /// rotates through the whole catalog.
+    weights: RotationWeights,
+    /// Only challenges of this difficulty are rotated, if any.
+    difficulty: Option<Difficulty>,
+    /// Accumulated weight of every challenge since it was last picked.
+    credit: BTreeMap<String, i64>,
+    last_pick: Option<String>,
+}
+
+impl ChallengeRotation {
+    pub fn new(weights: RotationWeights, difficulty: Option<Difficulty>) -> Self {
+        Self {
+            weights,
+            difficulty,
+            ..Default::default()
+        }
+    }
+
+    /// # Next challenge
+    ///
+    /// Picks the next challenge of the given catalog. Falls back to the whole
+    /// catalog if no challenge matches the configured rotation.
+    pub fn next<'a>(&mut self, catalog: &'a BTreeMap<String, Challenge>) -> Option<&'a Challenge> {
+        let mut candidates = catalog
+            .values()
+            .filter(|challenge| {
+                self.weights.0.is_empty() || self.weights.0.contains_key(&challenge.id)
+            })
+            .filter(|challenge| {
+                self.difficulty
+                    .is_none_or(|difficulty| challenge.difficulty == difficulty)
+            })
+            .map(|challenge| (challenge, self.weight(&challenge.id)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            candidates = catalog.values().map(|challenge| (challenge, 1)).collect();
+        }
+        // Forget challenges that left the rotation.
+        self.credit
+            .retain(|id, _| candidates.iter().any(|(challenge, _)| challenge.id == *id));
+
+        let total = candidates.iter().map(|(_, weight)| *weight).sum::<i64>();
+        for (challenge, weight) in candidates.iter() {
+            *self.credit.entry(challenge.id.clone()).or_default() += weight;
+        }
+        let is_repeat = |challenge: &Challenge| {
+            candidates.len() > 1 && self.last_pick.as_ref() == Some(&challenge.id)
+        };
+        let (challenge, _) = candidates
+            .iter()
+            .filter(|(challenge, _)| !is_repeat(challenge))
+            .max_by_key(|(challenge, _)| self.credit[&challenge.id])?;
+
+        *self.credit.entry(challenge.id.clone()).or_default() -= total;
+        self.last_pick = Some(challenge.id.clone());
+        Some(challenge)
+    }
+
+    fn weight(&self, challenge_id: &str) -> i64 {
+        self.weights.0.get(challenge_id).copied().unwrap_or(1) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{ChallengeFiles, Comparison};
+
+    use super::*;
+
+    fn catalog(ids: &[&str]) -> BTreeMap<String, Challenge> {
+        ids.iter()
+            .map(|id| {
+                let challenge = Challenge {
+                    id: id.to_string(),
+                    name: id.to_string(),
+                    language: String::from("rust"),
+                    difficulty: Difficulty::default(),
+                    description: None,
+                    comparison: Comparison::default(),
+                    time_limit: None,
+                    binary: false,
+                    files: ChallengeFiles {
+                        start_file: Vec::new(),
+                        goal_file: Vec::new(),
+                    },
+                };
+                (id.to_string(), challenge)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn picks_by_weight_without_repeats() {
+        let catalog = catalog(&["a", "b", "c"]);
+        let weights = "a:2,b,c".parse().unwrap();
+        let mut rotation = ChallengeRotation::new(weights, None);
+
+        let picks = (0..8)
+            .map(|_| rotation.next(&catalog).unwrap().id.clone())
+            .collect::<Vec<_>>();
+        let count = |id: &str| picks.iter().filter(|pick| *pick == id).count();
+        assert_eq!((count("a"), count("b"), count("c")), (4, 2, 2));
+        assert!(picks.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn repeats_the_only_challenge() {
+        let catalog = catalog(&["a", "b"]);
+        let weights = "a".parse().unwrap();
+        let mut rotation = ChallengeRotation::new(weights, None);
+
+        assert_eq!(rotation.next(&catalog).unwrap().id, "a");
+        assert_eq!(rotation.next(&catalog).unwrap().id, "a");
+    }
+}
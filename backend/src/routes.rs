@@ -1,3 +1,24 @@
+use warp::filters::ws::Message;
+
+use common::BackendMessage;
+
+pub(crate) mod admin;
 pub(crate) mod clients;
+pub(crate) mod health;
 pub(crate) mod lobbies;
+pub(crate) mod metrics;
 pub(crate) mod players;
+
+/// # To WebSocket message
+///
+/// Serializes a backend message into a text message. `CloseConnection`
+/// becomes a close frame carrying the code of its reason instead.
+pub(crate) fn to_ws_message(msg: BackendMessage) -> Message {
+    match msg {
+        BackendMessage::CloseConnection {
+            reason: Some(reason),
+        } => Message::close_with(reason.code(), reason.description()),
+        BackendMessage::CloseConnection { reason: None } => Message::close(),
+        msg => Message::text(serde_json::to_string(&msg).expect("Converting message to JSON")),
+    }
+}
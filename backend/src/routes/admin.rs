@@ -0,0 +1,81 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use uuid::Uuid;
+use warp::{http::StatusCode, reply::Response, Filter, Reply};
+
+use crate::app::message::AppMessage;
+
+/// Operator actions on a running server. Every request has to carry the
+/// configured admin token as `Authorization: Bearer <token>`. Without a
+/// configured token the routes answer as if they did not exist.
+pub fn routes(
+    app_tx: UnboundedSender<AppMessage>,
+    admin_token: Option<String>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Allow warp route handlers to take in the app sending channel as input.
+    let app_tx = warp::any().map(move || app_tx.clone());
+    let admin_token = warp::any().map(move || admin_token.clone());
+
+    warp::path!("admin" / "lobbies" / Uuid / "close")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(admin_token)
+        .and(warp::addr::remote())
+        .and(app_tx)
+        .and_then(close_lobby)
+}
+
+/// # Close lobby
+///
+/// Sends every member of the lobby back to the lobby browser and removes the
+/// lobby, no matter who is still inside.
+pub async fn close_lobby(
+    lobby_id: Uuid,
+    authorization: Option<String>,
+    admin_token: Option<String>,
+    address: Option<SocketAddr>,
+    app_tx: UnboundedSender<AppMessage>,
+) -> Result<Response, Infallible> {
+    if let Err(status) = authorize(authorization.as_deref(), admin_token.as_deref()) {
+        return Ok(status.into_response());
+    }
+
+    let origin = address.map_or_else(
+        || "admin request".to_string(),
+        |address| format!("admin request from {address}"),
+    );
+    let (tx, rx) = oneshot::channel();
+    let _ = app_tx.send(AppMessage::AdminCloseLobby {
+        lobby_id,
+        origin,
+        tx,
+    });
+
+    // The app drops the sender if the lobby does not exist.
+    let status = match rx.await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(_) => StatusCode::NOT_FOUND,
+    };
+    Ok(status.into_response())
+}
+
+/// # Authorize
+///
+/// Checks the bearer token of a request against the configured admin token.
+fn authorize(authorization: Option<&str>, admin_token: Option<&str>) -> Result<(), StatusCode> {
+    let Some(admin_token) = admin_token else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let token = authorization.and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) => Ok(()),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// Compares both byte strings in time only depending on their lengths, so
+/// that the admin token can not be guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
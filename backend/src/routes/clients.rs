@@ -1,66 +1,234 @@
+use std::{collections::BTreeMap, net::SocketAddr, time::Duration};
+
 use anyhow::Result;
-use common::BackendMessage;
+use common::{BackendMessage, ClientMessage, CloseReason, LobbyFilter};
 use futures_util::{future::ready, SinkExt, StreamExt};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
-use tracing::error;
+use serde::Deserialize;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedSender},
+    oneshot,
+};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 use warp::{
     filters::ws::{Message, WebSocket},
-    Filter,
+    http::StatusCode,
+    Filter, Reply,
 };
 
-use crate::app::message::AppMessage;
+use crate::{app::message::AppMessage, rate_limit::ConnectionLimiter, routes::to_ws_message};
+
+/// Query of the client route. Spectators presenting a reconnect token resume
+/// watching their held target.
+#[derive(Deserialize)]
+struct ConnectQuery {
+    token: Option<Uuid>,
+}
 
 pub fn routes(
     app_tx: UnboundedSender<AppMessage>,
+    limiter: ConnectionLimiter,
+    idle_timeout: Duration,
+    max_message_size: usize,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     // Allow warp route handlers to take in the app sending channel as input.
     let app_tx = warp::any().map(move || app_tx.clone());
+    let limiter = warp::any().map(move || limiter.clone());
 
-    // Setup client routes.
+    // Setup client routes. Connections exceeding the rate limit are rejected
+    // before the client is registered. Oversized messages are rejected before
+    // they are deserialized.
     warp::path("clients")
+        .and(warp::query::<ConnectQuery>())
         .and(warp::ws())
+        .and(warp::addr::remote())
+        .and(limiter)
         .and(app_tx.clone())
-        .map(|ws: warp::ws::Ws, app_tx: UnboundedSender<AppMessage>| {
-            ws.on_upgrade(|ws| handle_connection(ws, app_tx))
-        })
+        .map(
+            move |query: ConnectQuery,
+                  ws: warp::ws::Ws,
+                  peer: Option<SocketAddr>,
+                  limiter: ConnectionLimiter,
+                  app_tx: UnboundedSender<AppMessage>| {
+                if !limiter.allow(peer) {
+                    return StatusCode::TOO_MANY_REQUESTS.into_response();
+                }
+                ws.max_message_size(max_message_size)
+                    .max_frame_size(max_message_size)
+                    .on_upgrade(move |ws| handle_connection(ws, app_tx, idle_timeout, query.token))
+                    .into_response()
+            },
+        )
 }
 
-pub async fn handle_connection(ws: WebSocket, app_tx: UnboundedSender<AppMessage>) {
+pub async fn handle_connection(
+    ws: WebSocket,
+    app_tx: UnboundedSender<AppMessage>,
+    idle_timeout: Duration,
+    token: Option<Uuid>,
+) {
     let (to_ws, mut from_ws) = ws.split();
 
     // Typecast the websocket sending part to use `BackendMessage directly`.
     let mut to_ws = to_ws.with(|msg: BackendMessage| {
-        let res: Result<Message, warp::Error> = Ok(Message::text(
-            serde_json::to_string(&msg).expect("Converting message to JSON"),
-        ));
+        let res: Result<Message, warp::Error> = Ok(to_ws_message(msg));
         ready(res)
     });
 
     // Register the new client connection.
     let (client_tx, mut client_rx) = unbounded_channel();
-    let client_id = Uuid::new_v4();
-    let _ = app_tx.send(AppMessage::AddClient {
+    let mut client_id = Uuid::new_v4();
+    // Try to resume a held watch position. A client that is still connected
+    // continues under its ID. Unknown tokens connect as a new client.
+    let mut is_spectating = false;
+    let reply_tx = client_tx.clone();
+    if let Some(token) = token {
+        let (reply, resumed) = oneshot::channel();
+        let _ = app_tx.send(AppMessage::ReconnectSpectator {
+            client_id,
+            token,
+            client_tx,
+            reply,
+        });
+        (client_id, is_spectating) = resumed.await.unwrap_or((client_id, false));
+    } else {
+        let _ = app_tx.send(AppMessage::AddClient {
+            client_id,
+            client_tx,
+        });
+    }
+    // The client passes its ID on when joining a lobby.
+    let _ = reply_tx.send(BackendMessage::ProvideClientId { id: client_id });
+    // Tell the client about all lobbies it can join right now.
+    let _ = app_tx.send(AppMessage::CurrentLobbies {
         client_id,
-        client_tx,
+        filter: LobbyFilter::joinable(),
     });
-    // Tell the client about all current lobbies.
-    let _ = app_tx.send(AppMessage::CurrentLobbies { client_id });
 
-    // If the client closes his WS connection this task will signal the app to
-    // remove him from the current clients.
+    // Handle lobby list and spectate requests of the client. If the client closes his WS
+    // connection or stays idle for too long this task will signal the app to
+    // remove him from the current clients. Spectating counts as activity.
     tokio::spawn(async move {
-        while from_ws.next().await.is_some() {}
-        let _ = app_tx.send(AppMessage::RemoveClient { client_id });
+        loop {
+            let msg = if is_spectating || idle_timeout.is_zero() {
+                from_ws.next().await
+            } else {
+                match tokio::time::timeout(idle_timeout, from_ws.next()).await {
+                    Ok(msg) => msg,
+                    Err(_) => {
+                        info!(
+                            "Disconnecting client with ID {} after {}s without activity.",
+                            client_id,
+                            idle_timeout.as_secs()
+                        );
+                        let _ = reply_tx.send(BackendMessage::CloseConnection {
+                            reason: Some(CloseReason::IdleTimeout),
+                        });
+                        break;
+                    }
+                }
+            };
+            let msg = match msg {
+                Some(Ok(msg)) => msg,
+                Some(Err(e)) => {
+                    warn!("Closing connection of client with ID {}: {e}", client_id);
+                    let _ = reply_tx.send(BackendMessage::CloseConnection {
+                        reason: Some(CloseReason::ProtocolError),
+                    });
+                    break;
+                }
+                None => break,
+            };
+            if msg.is_close() {
+                break;
+            }
+            let Ok(text) = msg.to_str() else {
+                continue;
+            };
+            match serde_json::from_str::<ClientMessage>(text) {
+                Ok(ClientMessage::ListLobbies(filter)) => {
+                    let _ = app_tx.send(AppMessage::CurrentLobbies { client_id, filter });
+                }
+                Ok(ClientMessage::SpectatePlayer {
+                    lobby_id,
+                    target_id,
+                }) => {
+                    is_spectating = true;
+                    let _ = app_tx.send(AppMessage::SpectatePlayer {
+                        client_id,
+                        lobby_id,
+                        target_id,
+                    });
+                }
+                Ok(ClientMessage::WhoAmI) => {
+                    let _ = app_tx.send(AppMessage::SendClientSessionInfo { client_id });
+                }
+                Ok(ClientMessage::ListChallenges) => {
+                    let _ = app_tx.send(AppMessage::SendChallenges {
+                        tx: reply_tx.clone(),
+                    });
+                }
+                Ok(message) => warn!("Ignoring unexpected client message {:?}.", message),
+                Err(e) => error!("Error deserializing client message: {e}"),
+            }
+        }
+        let _ = app_tx.send(AppMessage::RemoveClient {
+            client_id,
+            client_tx: reply_tx,
+        });
     });
 
     // Forward messages received through the applicaton channel to the client
-    // WS connection.
+    // WS connection. Everything that queued up while the previous send was
+    // pending goes out at once, without outdated spectator frames.
     tokio::spawn(async move {
         while let Some(msg) = client_rx.recv().await {
-            if let Err(e) = to_ws.send(msg).await {
-                error!("Error sending message via websocket: {e}");
+            let mut queued = vec![msg];
+            while let Ok(msg) = client_rx.try_recv() {
+                queued.push(msg);
+            }
+            for msg in drop_stale_frames(queued) {
+                let is_close = matches!(msg, BackendMessage::CloseConnection { .. });
+                if let Err(e) = to_ws.send(msg).await {
+                    error!("Error sending message via websocket: {e}");
+                }
+                if is_close {
+                    let _ = to_ws.close().await;
+                    return;
+                }
             }
         }
     });
 }
+
+/// # Drop stale frames
+///
+/// Keeps only the latest spectator frame of every watched player among the
+/// queued messages of a slow client, so that it catches up with the current
+/// editor state instead of replaying old ones. All other messages, e.g., chat
+/// messages, are kept in order.
+fn drop_stale_frames(queued: Vec<BackendMessage>) -> Vec<BackendMessage> {
+    let mut latest_frames = BTreeMap::new();
+    for (i, msg) in queued.iter().enumerate() {
+        if let BackendMessage::SpectatorFrame { player_id, .. } = msg {
+            latest_frames.insert(*player_id, i);
+        }
+    }
+    let queued_count = queued.len();
+    let kept = queued
+        .into_iter()
+        .enumerate()
+        .filter(|(i, msg)| match msg {
+            BackendMessage::SpectatorFrame { player_id, .. } => latest_frames[player_id] == *i,
+            _ => true,
+        })
+        .map(|(_, msg)| msg)
+        .collect::<Vec<_>>();
+    if kept.len() < queued_count {
+        debug!(
+            "Dropped {} stale spectator frames of a slow client.",
+            queued_count - kept.len()
+        );
+    }
+    kept
+}
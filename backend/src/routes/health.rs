@@ -0,0 +1,56 @@
+use std::convert::Infallible;
+
+use tokio::{
+    sync::{mpsc::UnboundedSender, oneshot},
+    time::timeout,
+};
+use warp::{http::StatusCode, reply::Response, Filter, Reply};
+
+use crate::{app::message::AppMessage, constants::HEALTH_CHECK_TIMEOUT};
+
+/// Liveness and readiness probes for orchestration. Both answer with a plain
+/// status code and never touch lobby state.
+pub fn routes(
+    app_tx: UnboundedSender<AppMessage>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Allow warp route handlers to take in the app sending channel as input.
+    let app_tx = warp::any().map(move || app_tx.clone());
+
+    let liveness = warp::path!("healthz")
+        .and(app_tx.clone())
+        .and_then(liveness);
+    let readiness = warp::path!("readyz").and(app_tx).and_then(readiness);
+    liveness.or(readiness)
+}
+
+/// # Liveness
+///
+/// Succeeds as long as the app message loop answers within
+/// `HEALTH_CHECK_TIMEOUT`.
+pub async fn liveness(app_tx: UnboundedSender<AppMessage>) -> Result<Response, Infallible> {
+    let status = match heartbeat(app_tx).await {
+        Some(_) => StatusCode::OK,
+        None => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    Ok(status.into_response())
+}
+
+/// # Readiness
+///
+/// Succeeds once the app message loop answers and finished starting up, i.e.,
+/// loaded a challenge catalog lobbies can be created with.
+pub async fn readiness(app_tx: UnboundedSender<AppMessage>) -> Result<Response, Infallible> {
+    let status = match heartbeat(app_tx).await {
+        Some(true) => StatusCode::OK,
+        Some(false) | None => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    Ok(status.into_response())
+}
+
+/// Asks the app message loop whether it is ready. Returns `None` if it does
+/// not answer in time.
+async fn heartbeat(app_tx: UnboundedSender<AppMessage>) -> Option<bool> {
+    let (tx, rx) = oneshot::channel();
+    app_tx.send(AppMessage::Heartbeat { tx }).ok()?;
+    timeout(HEALTH_CHECK_TIMEOUT, rx).await.ok()?.ok()
+}
@@ -1,11 +1,20 @@
-use std::convert::Infallible;
+use std::{collections::BTreeSet, convert::Infallible};
 
+use serde::Deserialize;
 use tokio::sync::{mpsc::UnboundedSender, oneshot};
-use warp::Filter;
+use warp::{http::StatusCode, reply::Response, Filter, Reply};
 
-use common::JoinMode;
+use common::{parse_lobby_tags, JoinMode};
 
-use crate::app::message::AppMessage;
+use crate::{app::message::AppMessage, lobby::validate_lobby_description};
+
+/// Query of the lobby route. Creators can describe and tag their lobby
+/// upfront, with tags separated by commas.
+#[derive(Deserialize)]
+struct LobbyQuery {
+    description: Option<String>,
+    tags: Option<String>,
+}
 
 pub fn routes(
     app_tx: UnboundedSender<AppMessage>,
@@ -14,18 +23,107 @@ pub fn routes(
     let app_tx = warp::any().map(move || app_tx.clone());
 
     warp::path!("lobbies" / JoinMode)
+        .and(warp::query::<LobbyQuery>())
         .and(app_tx)
         .and_then(lobby_information)
 }
 
-pub async fn lobby_information(
+async fn lobby_information(
     join_mode: JoinMode,
+    query: LobbyQuery,
     app_tx: UnboundedSender<AppMessage>,
-) -> Result<impl warp::Reply, Infallible> {
+) -> Result<Response, Infallible> {
+    let description = match query.description.as_deref().map(validate_lobby_description) {
+        Some(Ok(description)) => description,
+        Some(Err(e)) => {
+            let reply = warp::reply::with_status(e.to_string(), StatusCode::BAD_REQUEST);
+            return Ok(reply.into_response());
+        }
+        None => None,
+    };
+    let tags = match query.tags.as_deref().map(parse_lobby_tags) {
+        Some(Ok(tags)) => tags,
+        Some(Err(e)) => {
+            let reply = warp::reply::with_status(e.to_string(), StatusCode::BAD_REQUEST);
+            return Ok(reply.into_response());
+        }
+        None => BTreeSet::new(),
+    };
     let (tx, rx) = oneshot::channel();
 
-    let _ = app_tx.send(AppMessage::ProvideLobbyInformation { tx, join_mode });
-    let lobby_information = rx.await.expect("Should receive the lobby name.");
+    let _ = app_tx.send(AppMessage::ProvideLobbyInformation {
+        tx,
+        join_mode,
+        description,
+        tags,
+    });
+
+    // The app drops the sender if there is no lobby for the join mode, e.g.,
+    // when trying to join a lobby that was already removed.
+    let Ok(lobby_information) = rx.await else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    Ok(warp::reply::json(&lobby_information).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{BackendMessage, LobbyFilter, LobbyTag};
+    use tokio::sync::mpsc::unbounded_channel;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        app::{message::handle_app_message, App},
+        config::Config,
+    };
+
+    #[tokio::test]
+    async fn lobby_list_is_filtered_by_tag() {
+        let (app_tx, app_rx) = unbounded_channel();
+        let app = App::new(app_tx.clone(), app_rx, &Config::default()).unwrap();
+        tokio::spawn(handle_app_message(app));
+        let routes = routes(app_tx.clone());
+
+        let create = |path: &'static str| {
+            let routes = routes.clone();
+            async move {
+                let reply = warp::test::request().path(path).reply(&routes).await;
+                assert_eq!(reply.status(), StatusCode::OK);
+                let information: serde_json::Value = serde_json::from_slice(reply.body()).unwrap();
+                information["id"].as_str().unwrap().parse::<Uuid>().unwrap()
+            }
+        };
+        let ranked_id = create("/lobbies/create?tags=ranked,casual").await;
+        create("/lobbies/create?tags=casual").await;
+        create("/lobbies/create").await;
+        let reply = warp::test::request()
+            .path("/lobbies/create?tags=unknown")
+            .reply(&routes)
+            .await;
+        assert_eq!(reply.status(), StatusCode::BAD_REQUEST);
 
-    Ok(warp::reply::json(&lobby_information))
+        let client_id = Uuid::new_v4();
+        let (client_tx, mut client_rx) = unbounded_channel();
+        let _ = app_tx.send(AppMessage::AddClient {
+            client_id,
+            client_tx,
+        });
+        let _ = app_tx.send(AppMessage::CurrentLobbies {
+            client_id,
+            filter: LobbyFilter {
+                tag: Some(LobbyTag::Ranked),
+                ..LobbyFilter::default()
+            },
+        });
+        let lobbies = loop {
+            match client_rx.recv().await {
+                Some(BackendMessage::CurrentLobbies(lobbies)) => break lobbies,
+                Some(_) => continue,
+                None => panic!("Expected the lobby list."),
+            }
+        };
+        assert_eq!(lobbies.keys().collect::<Vec<_>>(), [&ranked_id]);
+    }
 }
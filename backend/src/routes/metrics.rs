@@ -0,0 +1,47 @@
+use std::convert::Infallible;
+
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use warp::{http::StatusCode, reply::Response, Filter, Reply};
+
+use crate::app::message::AppMessage;
+
+pub fn routes(
+    app_tx: UnboundedSender<AppMessage>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Allow warp route handlers to take in the app sending channel as input.
+    let app_tx = warp::any().map(move || app_tx.clone());
+
+    let connections = warp::path!("metrics" / "connections")
+        .and(app_tx.clone())
+        .and_then(connection_history);
+    let lobbies = warp::path!("metrics" / "lobbies")
+        .and(app_tx)
+        .and_then(lobby_metrics);
+    connections.or(lobbies)
+}
+
+pub async fn connection_history(
+    app_tx: UnboundedSender<AppMessage>,
+) -> Result<Response, Infallible> {
+    let (tx, rx) = oneshot::channel();
+
+    let _ = app_tx.send(AppMessage::ProvideConnectionHistory { tx });
+
+    let Ok(samples) = rx.await else {
+        return Ok(StatusCode::SERVICE_UNAVAILABLE.into_response());
+    };
+
+    Ok(warp::reply::json(&samples).into_response())
+}
+
+pub async fn lobby_metrics(app_tx: UnboundedSender<AppMessage>) -> Result<Response, Infallible> {
+    let (tx, rx) = oneshot::channel();
+
+    let _ = app_tx.send(AppMessage::ProvideLobbyMetrics { tx });
+
+    let Ok(metrics) = rx.await else {
+        return Ok(StatusCode::SERVICE_UNAVAILABLE.into_response());
+    };
+
+    Ok(warp::reply::json(&metrics).into_response())
+}
@@ -1,43 +1,110 @@
+use std::{net::SocketAddr, time::Duration};
+
 use anyhow::Result;
+use chrono::Utc;
 use futures_util::{
     future::ready,
     stream::{SplitSink, SplitStream},
     SinkExt, StreamExt,
 };
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
-use tracing::error;
+use serde::Deserialize;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    oneshot,
+};
+use tracing::{debug, error, warn};
 use uuid::Uuid;
 use warp::{
     filters::ws::{Message, WebSocket},
-    Filter,
+    http::StatusCode,
+    Filter, Reply,
+};
+
+use common::{
+    constants::{MAX_CHAT_MESSAGE_LENGTH, MAX_REPLAY_BYTES, MAX_REPLAY_FRAMES},
+    BackendMessage, ClientMessage, CloseReason,
 };
 
-use common::{constants::MAX_CHAT_MESSAGE_LENGTH, BackendMessage, ClientMessage};
+use crate::{player::Player, rate_limit::ConnectionLimiter, routes::to_ws_message, AppMessage};
 
-use crate::{player::Player, AppMessage};
+/// Query of the player route. Players presenting a reconnect token take back
+/// their held seat.
+#[derive(Deserialize)]
+struct JoinQuery {
+    token: Option<Uuid>,
+    /// ID of the lobby browser connection the client joins from. Joins
+    /// without the ID of a connected client are rejected.
+    client: Option<Uuid>,
+}
 
 pub fn routes(
     app_tx: UnboundedSender<AppMessage>,
+    limiter: ConnectionLimiter,
+    max_message_size: usize,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     // Allow warp route handlers to take in the app sending channel as input.
     let app_tx = warp::any().map(move || app_tx.clone());
+    let limiter = warp::any().map(move || limiter.clone());
 
+    // Connections exceeding the rate limit are rejected before the player
+    // joins. Oversized messages are rejected before they are deserialized.
     warp::path!("players" / Uuid)
+        .and(warp::query::<JoinQuery>())
         .and(warp::ws())
+        .and(warp::addr::remote())
+        .and(limiter)
         .and(app_tx)
         .map(
-            |lobby_id: Uuid, ws: warp::ws::Ws, app_tx: UnboundedSender<AppMessage>| {
-                ws.on_upgrade(move |ws| handle_join(ws, app_tx, lobby_id))
+            move |lobby_id: Uuid,
+                  query: JoinQuery,
+                  ws: warp::ws::Ws,
+                  peer: Option<SocketAddr>,
+                  limiter: ConnectionLimiter,
+                  app_tx: UnboundedSender<AppMessage>| {
+                if !limiter.allow(peer) {
+                    return StatusCode::TOO_MANY_REQUESTS.into_response();
+                }
+                ws.max_message_size(max_message_size)
+                    .max_frame_size(max_message_size)
+                    .on_upgrade(move |ws| {
+                        handle_join(ws, app_tx, lobby_id, query.token, query.client)
+                    })
+                    .into_response()
             },
         )
 }
 
-pub async fn handle_join(ws: WebSocket, app_tx: UnboundedSender<AppMessage>, lobby_id: Uuid) {
+pub async fn handle_join(
+    ws: WebSocket,
+    app_tx: UnboundedSender<AppMessage>,
+    lobby_id: Uuid,
+    token: Option<Uuid>,
+    client_id: Option<Uuid>,
+) {
     let (to_ws, from_ws) = ws.split();
+    let (player_tx, player_rx) = unbounded_channel();
+
+    // Try to take back a held seat. Unknown tokens join as a new player.
+    if let Some(token) = token {
+        let (reply, resumed) = oneshot::channel();
+        let _ = app_tx.send(AppMessage::ResumePlayer {
+            lobby_id,
+            token,
+            tx: player_tx.clone(),
+            reply,
+        });
+        if let Ok(Some(player)) = resumed.await {
+            tokio::spawn(receive_and_handle_client_message(
+                from_ws, app_tx, player, lobby_id,
+            ));
+            tokio::spawn(forward_backend_message(to_ws, player_rx));
+            return;
+        }
+    }
 
     // Setup player.
-    let (player_tx, player_rx) = unbounded_channel();
-    let player = Player::new(player_tx);
+    let mut player = Player::new(player_tx);
+    player.client_id = client_id;
 
     // Handle incoming client messages.
     tokio::spawn(receive_and_handle_client_message(
@@ -60,10 +127,25 @@ async fn receive_and_handle_client_message(
     player: Player,
     lobby_id: Uuid,
 ) {
-    while let Some(Ok(msg)) = from_ws.next().await {
+    let mut has_left = false;
+    while let Some(msg) = from_ws.next().await {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!("Closing connection of player {}: {e}", player.name);
+                let _ = player.tx.send(BackendMessage::CloseConnection {
+                    reason: Some(CloseReason::ProtocolError),
+                });
+                break;
+            }
+        };
         if msg.is_close() {
             break;
         }
+        // Pings of the latency display are answered by the WebSocket layer.
+        if msg.is_ping() || msg.is_pong() {
+            continue;
+        }
         let Ok(text) = msg.to_str() else {
             error!("Received non-text message from client.");
             continue;
@@ -91,20 +173,197 @@ async fn receive_and_handle_client_message(
                     lobby_id,
                 }
             }
-            ClientMessage::RequestStart => AppMessage::RequestStart {
+            ClientMessage::Whisper { name, message } => {
+                let message_length = message.len();
+                if message_length > MAX_CHAT_MESSAGE_LENGTH {
+                    error!(
+                        "Player tried to whisper chat message of length: {}",
+                        message_length
+                    );
+                    continue;
+                }
+                AppMessage::Whisper {
+                    player: player.clone(),
+                    lobby_id,
+                    name,
+                    message,
+                }
+            }
+            ClientMessage::ToggleReady => AppMessage::ToggleReady {
+                player: player.clone(),
+                lobby_id,
+            },
+            ClientMessage::EditorReady => AppMessage::EditorReady {
+                player: player.clone(),
+                lobby_id,
+            },
+            ClientMessage::KickPlayer { name, reason } => {
+                let reason_length = reason.len();
+                if reason_length > MAX_CHAT_MESSAGE_LENGTH {
+                    error!("Player tried to kick with reason of length: {reason_length}");
+                    continue;
+                }
+                // An empty reason falls back to the default one.
+                AppMessage::KickPlayer {
+                    player: player.clone(),
+                    lobby_id,
+                    name,
+                    reason: Some(reason).filter(|reason| !reason.trim().is_empty()),
+                }
+            }
+            ClientMessage::SetPlayerMuted { target_id, muted } => AppMessage::SetPlayerMuted {
+                player: player.clone(),
+                lobby_id,
+                target_id,
+                muted,
+            },
+            ClientMessage::VoteKick { target_id } => AppMessage::VoteKick {
+                lobby_id,
+                voter_id: player.id,
+                target_id,
+            },
+            ClientMessage::RenamePlayer { name } => AppMessage::RenamePlayer {
+                player: player.clone(),
+                lobby_id,
+                new_name: name,
+            },
+            ClientMessage::RequestStart => AppMessage::ForceStart {
+                lobby_id,
+                requester_id: player.id,
+            },
+            ClientMessage::PauseRound => AppMessage::PauseRound {
+                lobby_id,
+                requester_id: player.id,
+            },
+            ClientMessage::ResumeRound => AppMessage::ResumeRound {
+                lobby_id,
+                requester_id: player.id,
+            },
+            ClientMessage::SetLobbyGoal { challenge_id } => AppMessage::SetLobbyGoal {
+                player: player.clone(),
+                lobby_id,
+                challenge_id,
+            },
+            ClientMessage::SetWarmup { challenge_id } => AppMessage::SetWarmup {
+                player: player.clone(),
+                lobby_id,
+                challenge_id,
+            },
+            ClientMessage::SetSolutionReveal { enabled } => AppMessage::SetSolutionReveal {
+                player: player.clone(),
+                lobby_id,
+                enabled,
+            },
+            ClientMessage::SetLobbyTags { tags } => AppMessage::SetLobbyTags {
+                player: player.clone(),
+                lobby_id,
+                tags,
+            },
+            ClientMessage::SetComparison { comparison } => AppMessage::SetComparison {
+                player: player.clone(),
+                lobby_id,
+                comparison,
+            },
+            ClientMessage::SetLobbyDescription { description } => AppMessage::SetLobbyDescription {
+                player: player.clone(),
+                lobby_id,
+                description,
+            },
+            ClientMessage::SetGoalPreview { enabled } => AppMessage::SetGoalPreview {
+                player: player.clone(),
+                lobby_id,
+                enabled,
+            },
+            ClientMessage::SetMaxPlayers { max_players } => AppMessage::SetMaxPlayers {
+                player: player.clone(),
+                lobby_id,
+                max_players,
+            },
+            ClientMessage::SetTimeLimit { seconds } => AppMessage::SetTimeLimit {
+                player: player.clone(),
+                lobby_id,
+                time_limit: seconds.map(Duration::from_secs),
+            },
+            ClientMessage::SetMaxSpectators { max_spectators } => AppMessage::SetMaxSpectators {
+                player: player.clone(),
+                lobby_id,
+                max_spectators,
+            },
+            ClientMessage::SetSpectatorsAllowed { allowed } => AppMessage::SetSpectatorsAllowed {
+                player: player.clone(),
+                lobby_id,
+                allowed,
+            },
+            ClientMessage::SetBot { difficulty } => AppMessage::SetBot {
+                player: player.clone(),
+                lobby_id,
+                difficulty,
+            },
+            ClientMessage::SetGameMode { game_mode } => AppMessage::SetGameMode {
                 player: player.clone(),
                 lobby_id,
+                game_mode,
             },
             ClientMessage::Progress { progress } => AppMessage::ComputePlayerProgress {
                 lobby_id,
                 player_id: player.id,
                 progress,
+                received_at: Utc::now(),
             },
+            ClientMessage::SubmitReplay { frames } => {
+                let replay_bytes: usize = frames.iter().map(|frame| frame.bytes.len()).sum();
+                if frames.len() > MAX_REPLAY_FRAMES || replay_bytes > MAX_REPLAY_BYTES {
+                    error!(
+                        "Player tried to submit replay with {} frames and {} bytes.",
+                        frames.len(),
+                        replay_bytes
+                    );
+                    continue;
+                }
+                AppMessage::SubmitReplay {
+                    player: player.clone(),
+                    lobby_id,
+                    frames,
+                }
+            }
+            ClientMessage::RequestReplay { name } => AppMessage::RequestReplay {
+                player: player.clone(),
+                lobby_id,
+                name,
+            },
+            ClientMessage::WhoAmI => AppMessage::SendPlayerSessionInfo {
+                player: player.clone(),
+            },
+            ClientMessage::RequestRoster { lobby_id } => AppMessage::SendRoster {
+                player: player.clone(),
+                lobby_id,
+            },
+            ClientMessage::ListChallenges => AppMessage::SendChallenges {
+                tx: player.tx.clone(),
+            },
+            ClientMessage::ListLobbies(_) => {
+                error!("Player tried to list lobbies from inside a lobby.");
+                continue;
+            }
+            // Leaving frees the seat right away instead of holding it.
+            ClientMessage::LeaveLobby => {
+                has_left = true;
+                break;
+            }
+            ClientMessage::SpectatePlayer { .. } => {
+                error!("Player tried to spectate from inside a lobby.");
+                continue;
+            }
         };
         let _ = app_tx.send(msg);
     }
-    // If the player closes his WS connection remove him from the lobby.
-    let _ = app_tx.send(AppMessage::RemovePlayer { player, lobby_id });
+    // If the player leaves remove him from the lobby. A lost connection
+    // holds the seat for the reconnect grace period first.
+    if has_left {
+        let _ = app_tx.send(AppMessage::RemovePlayer { player, lobby_id });
+    } else {
+        let _ = app_tx.send(AppMessage::DisconnectPlayer { player, lobby_id });
+    }
 }
 
 async fn forward_backend_message(
@@ -113,15 +372,51 @@ async fn forward_backend_message(
 ) {
     // Typecast the websocket sending part to use `BackendMessage directly`.
     let mut to_ws = to_ws.with(|msg: BackendMessage| {
-        let res: Result<Message, warp::Error> = Ok(Message::text(
-            serde_json::to_string(&msg).expect("Converting message to JSON"),
-        ));
+        let res: Result<Message, warp::Error> = Ok(to_ws_message(msg));
         ready(res)
     });
 
     while let Some(msg) = player_rx.recv().await {
+        // The close frame tells the client why, e.g., that the player resumed
+        // their seat through another connection.
+        if let BackendMessage::CloseConnection { reason } = msg {
+            let _ = to_ws.send(msg).await;
+            let _ = to_ws.close().await;
+            debug!("Closed player connection ({:?}).", reason);
+            return;
+        }
         if let Err(e) = to_ws.send(msg).await {
             error!("Error sending message via websocket: {e}");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn leaving_removes_the_player_instead_of_holding_the_seat() {
+        let (app_tx, mut app_rx) = unbounded_channel();
+        let limiter = ConnectionLimiter::new(1, Duration::from_secs(1));
+        let lobby_id = Uuid::new_v4();
+        let mut ws = warp::test::ws()
+            .path(&format!("/players/{lobby_id}"))
+            .handshake(routes(app_tx, limiter, 1024))
+            .await
+            .unwrap();
+        let Some(AppMessage::AddPlayerToLobby { player, .. }) = app_rx.recv().await else {
+            panic!("Expected the player to join the lobby.");
+        };
+
+        ws.send_text(serde_json::to_string(&ClientMessage::LeaveLobby).unwrap())
+            .await;
+        let Some(AppMessage::RemovePlayer {
+            player: removed, ..
+        }) = app_rx.recv().await
+        else {
+            panic!("Expected the player to be removed right away.");
+        };
+        assert_eq!(removed.id, player.id);
+    }
+}
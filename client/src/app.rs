@@ -1,26 +1,28 @@
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use common::{JoinMode, LobbyStatus};
+use anyhow::{anyhow, Result};
+use common::{ClientMessage, JoinMode, LobbyStatus};
 use futures_util::SinkExt;
-use log::debug;
+use log::{debug, error, info};
 use ratatui::{
     backend::Backend,
     crossterm::{
         self,
-        event::{self, Event, KeyEvent},
+        event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     },
     layout::Size,
     Terminal,
 };
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
 
 #[cfg(feature = "audio")]
 use crate::audio::{play_audio, Audio};
 use crate::{
     config::Config,
+    last_lobby,
     schema::{
-        connection::Connection,
+        connection::{Connection, ConnectionEvent, ConnectionState},
         focused_component::{ComponentKind, FocusedComponent},
         lobby::{Lobby, LobbyMessage},
         tab::Tab,
@@ -39,6 +41,8 @@ pub struct App {
     pub rx: UnboundedReceiver<AppMessage>,
 
     pub connection: Connection,
+    /// State of the connection shown in the header.
+    pub connection_state: ConnectionState,
     /// The total number of clients (non-playing users) currently connected.
     pub total_clients: usize,
     /// The total number playing users.
@@ -46,6 +50,9 @@ pub struct App {
     /// The currently focused component has priority over all other elements
     /// when it comes to user inputs.
     pub focused_component: Option<FocusedComponent>,
+    /// Whether a modal is shown above the current content. Editor output is
+    /// not processed meanwhile, so that it does not draw through the modal.
+    pub modal_open: bool,
 
     pub exit: bool,
 }
@@ -57,8 +64,22 @@ pub enum AppMessage {
     ConnectToLobby {
         join_mode: JoinMode,
     },
+    /// Tries to rejoin the lobby the client was connected to before the last
+    /// shutdown.
+    RejoinLastLobby {
+        lobby_id: Uuid,
+    },
     /// Disconnects the client from the current lobby.
     DisconnectLobby,
+    /// The lobby owner kicked the client from the current lobby.
+    Kicked {
+        reason: String,
+    },
+    /// The backend sent the client back to the lobby browser, e.g., because
+    /// an operator closed the lobby.
+    ReturnToBrowser {
+        reason: String,
+    },
     /// Updates the total connection count on the home page.
     ConnectionCounts {
         players: usize,
@@ -68,24 +89,41 @@ pub enum AppMessage {
     ServiceDisconnected,
     /// The backend is back online.
     ServiceBackOnline,
+    /// The backend announced its shutdown.
+    ServerShutdown,
+    /// Quits the application, e.g., after the user pressed Ctrl-C.
+    Quit,
 }
 
 impl App {
     pub async fn new(config: Config, size: Size) -> Result<Self> {
         let (tx, rx) = unbounded_channel();
-        let connection = Connection::new(tx.clone()).await?;
-        let app = App {
+        tokio::spawn(quit_on_interrupt(tx.clone()));
+        info!("Connecting to the backend service.");
+        let mut connection = Connection::new(tx.clone(), &config.server).await?;
+
+        // Try to get back into the last joined lobby if the user opted in.
+        if let Connection::Join(ref mut join) = connection {
+            if config.auto_rejoin {
+                join.rejoin_lobby = last_lobby::load();
+            }
+        }
+
+        let mut app = App {
             config,
             current_tab: Tab::Home,
             size,
             tx,
             rx,
+            connection_state: ConnectionState::default(),
             connection,
             total_clients: 0,
             total_players: 0,
             focused_component: None,
+            modal_open: false,
             exit: false,
         };
+        app.update_connection_state(ConnectionEvent::from(&app.connection));
         Ok(app)
     }
 
@@ -96,6 +134,7 @@ impl App {
     ) -> Result<()> {
         let mut last_tick = Instant::now();
         while !self.exit {
+            self.update_modal_state(terminal)?;
             // Draw the application.
             terminal.draw(|f| ui::draw(f, self))?;
 
@@ -122,6 +161,40 @@ impl App {
             }
         }
 
+        self.shut_down().await
+    }
+
+    /// # Shut down
+    ///
+    /// Leaves the current lobby and kills its editors, so that no editor
+    /// process or start file outlives the application.
+    async fn shut_down(&mut self) -> Result<()> {
+        if let Connection::Lobby(ref mut lobby) = self.connection {
+            info!("Leaving lobby {} on shutdown.", lobby.name);
+            if let Err(e) = lobby.shut_down().await {
+                error!("Error leaving lobby on shutdown: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    /// # Update modal state
+    ///
+    /// Pauses the editor output while a modal is shown. Once it closes, the
+    /// buffered output is processed in order and the whole screen is redrawn,
+    /// so that no remains of the modal stay behind.
+    fn update_modal_state<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let modal_open = self.focused_component_is_kind(ComponentKind::ExitPopup);
+        if modal_open == self.modal_open {
+            return Ok(());
+        }
+        self.modal_open = modal_open;
+        if let Connection::Lobby(ref mut lobby) = self.connection {
+            lobby.set_output_paused(modal_open);
+        }
+        if !modal_open {
+            terminal.clear()?;
+        }
         Ok(())
     }
 
@@ -150,6 +223,16 @@ impl App {
     }
 
     pub async fn on_key(&mut self, key: KeyEvent) -> Result<()> {
+        // In raw mode Ctrl-C arrives as a key press instead of a signal. Quit
+        // on it, unless a focused component like the editor receives it.
+        if self.focused_component.is_none()
+            && key.code == KeyCode::Char('c')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            self.exit = true;
+            return Ok(());
+        }
+
         // Unfocus component or quit the application if no component is focused.
         if key.eq(&self.config.key_bindings.miscellaneous.unfocus) {
             if self.focused_component.is_some() {
@@ -171,6 +254,16 @@ impl App {
                 self.on_left();
             } else if key.eq(&self.config.key_bindings.movement.right) {
                 self.on_right();
+            } else if key.eq(&self.config.key_bindings.miscellaneous.toggle_accessibility) {
+                self.config.accessibility = !self.config.accessibility;
+                info!(
+                    "Turned the accessibility mode {}.",
+                    if self.config.accessibility {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                );
             } else {
                 // Then, handle key bindings per tab.
                 self.handle_key_event_per_tab(key).await?;
@@ -188,13 +281,22 @@ impl App {
                         if key.eq(&self.config.key_bindings.join.focus_lobby_list) {
                             self.focused_component =
                                 Some(FocusedComponent::new(ComponentKind::Lobbies));
+                        } else if key.eq(&self.config.key_bindings.join.focus_lobby_filter) {
+                            self.focused_component =
+                                Some(FocusedComponent::new(ComponentKind::LobbyFilter));
                         }
                     }
                     Connection::Lobby(ref mut lobby) => {
                         // Disconnect from existing lobby.
                         if key.eq(&self.config.key_bindings.lobby.disconnect) {
+                            lobby.ws_tx.send(ClientMessage::LeaveLobby.into()).await?;
                             lobby.ws_tx.close().await?;
-                            self.connection = Connection::new(self.tx.clone()).await?;
+                            // Leaving on purpose means we do not want to
+                            // rejoin this lobby on the next startup.
+                            if self.config.auto_rejoin {
+                                last_lobby::clear()?;
+                            }
+                            self.connect_to_backend().await?;
                         }
                         // Whenever a lobby is about to start, ignore all key
                         // events except the disconnect one.
@@ -218,6 +320,14 @@ impl App {
                         } else if key.eq(&self.config.key_bindings.lobby.toggle_terminal_layout) {
                             lobby.toggle_terminal_layout();
                             lobby.resize(self.size.height, self.size.width)?;
+                        } else if key.eq(&self.config.key_bindings.lobby.toggle_status_bar) {
+                            lobby.toggle_status_bar()?;
+                        }
+                        // Toggle the ready state before the round starts.
+                        else if key.eq(&self.config.key_bindings.lobby.toggle_ready)
+                            && lobby.status == LobbyStatus::WaitingForPlayers
+                        {
+                            lobby.tx.send(LobbyMessage::ToggleReady)?;
                         }
                         // Start the lobby as lobby owner.
                         else if key.eq(&self.config.key_bindings.lobby.start)
@@ -227,6 +337,22 @@ impl App {
                         {
                             lobby.tx.send(LobbyMessage::RequestStart)?;
                         }
+                        // Switch the game mode as lobby owner.
+                        else if key.eq(&self.config.key_bindings.lobby.toggle_game_mode)
+                            && lobby.status == LobbyStatus::WaitingForPlayers
+                            && lobby.owner == lobby.local_player
+                            && lobby.local_player.is_some()
+                        {
+                            lobby.tx.send(LobbyMessage::ToggleGameMode)?;
+                        }
+                        // Add, change or remove the bot opponent as lobby owner.
+                        else if key.eq(&self.config.key_bindings.lobby.toggle_bot)
+                            && lobby.status == LobbyStatus::WaitingForPlayers
+                            && lobby.owner == lobby.local_player
+                            && lobby.local_player.is_some()
+                        {
+                            lobby.tx.send(LobbyMessage::ToggleBot)?;
+                        }
                         // Scroll chat down.
                         else if key.eq(&self.config.key_bindings.movement.down) {
                             lobby.chat.next();
@@ -246,15 +372,40 @@ impl App {
         debug!("Handle message: {:?}.", msg);
 
         match msg {
+            AppMessage::Quit => {
+                self.exit = true;
+            }
             AppMessage::DisconnectLobby => {
                 self.focused_component = None;
                 if let Connection::Lobby(ref mut lobby) = self.connection {
                     lobby.ws_tx.close().await?;
-                    self.connection = Connection::new(self.tx.clone()).await?;
+                    if let Err(e) = lobby.editor.shut_down().await {
+                        error!("Error shutting down the editor: {e}");
+                    }
+                    self.connect_to_backend().await?;
+                }
+            }
+            AppMessage::Kicked { reason } => {
+                if let Connection::Lobby(ref mut lobby) = self.connection {
+                    lobby.ws_tx.close().await?;
+                    if let Err(e) = lobby.editor.shut_down().await {
+                        error!("Error shutting down the editor: {e}");
+                    }
+                    self.return_to_lobby_list(&format!("You were kicked from the lobby: {reason}"))
+                        .await?;
+                }
+            }
+            AppMessage::ReturnToBrowser { reason } => {
+                if let Connection::Lobby(ref mut lobby) = self.connection {
+                    lobby.ws_tx.close().await?;
+                    if let Err(e) = lobby.editor.shut_down().await {
+                        error!("Error shutting down the editor: {e}");
+                    }
+                    self.return_to_lobby_list(&format!("{reason}.")).await?;
                 }
             }
             AppMessage::ServiceBackOnline => {
-                self.connection = Connection::new(self.tx.clone()).await?;
+                self.connect_to_backend().await?;
 
                 #[cfg(feature = "audio")]
                 play_audio(&self.config, Audio::Reconnected)?;
@@ -262,12 +413,28 @@ impl App {
             AppMessage::ServiceDisconnected => {
                 // Make sure to unfocus components on disconnect.
                 self.focused_component = None;
-                self.connection = Connection::new(self.tx.clone()).await?;
+                self.update_connection_state(ConnectionEvent::Lost);
+                self.connect_to_backend().await?;
+            }
+            AppMessage::ServerShutdown => {
+                self.update_connection_state(ConnectionEvent::ServerShutdown);
             }
             AppMessage::ConnectToLobby { join_mode } => {
-                let lobby = Lobby::new(self.tx.clone(), join_mode, self.size).await?;
-                self.connection = Connection::Lobby(lobby);
-                self.focused_component = None;
+                if let Err(e) = self.connect_to_lobby(join_mode).await {
+                    error!("Error connecting to lobby: {e}");
+                    self.return_to_lobby_list("Unable to join the lobby.")
+                        .await?;
+                }
+            }
+            AppMessage::RejoinLastLobby { lobby_id } => {
+                info!("Try to rejoin last lobby {}.", lobby_id);
+                let join_mode = JoinMode::Join { lobby_id };
+                if let Err(e) = self.connect_to_lobby(join_mode).await {
+                    error!("Error rejoining last lobby: {e}");
+                    last_lobby::clear()?;
+                    self.return_to_lobby_list("Your last lobby no longer exists.")
+                        .await?;
+                }
             }
             AppMessage::ConnectionCounts { players, clients } => {
                 self.total_clients = clients;
@@ -280,6 +447,85 @@ impl App {
         Ok(())
     }
 
+    /// # Connect to lobby
+    ///
+    /// Connects to a lobby via the given join mode and remembers it for auto
+    /// rejoin if enabled.
+    async fn connect_to_lobby(&mut self, join_mode: JoinMode) -> Result<()> {
+        self.update_connection_state(ConnectionEvent::Connect);
+        // The backend only lets connected clients join, which keeps each
+        // client in a single lobby.
+        let Connection::Join(ref mut join) = self.connection else {
+            return Err(anyhow!("Lobbies can only be joined from the lobby list."));
+        };
+        let Some(client_id) = join.client_id else {
+            return Err(anyhow!("The backend did not provide a client ID yet."));
+        };
+        let lobby = Lobby::new(
+            self.tx.clone(),
+            join_mode,
+            client_id,
+            self.size,
+            self.config.editor.clone(),
+            &self.config.server,
+        )
+        .await;
+        // The lobby list connection identifies the client during the join, so
+        // it is only closed afterwards.
+        if let Err(e) = join.ws_tx.close().await {
+            error!("Error closing the lobby list connection: {e}");
+        }
+        let lobby = lobby?;
+        if self.config.auto_rejoin {
+            if let Err(e) = last_lobby::store(lobby.id) {
+                error!("Error storing last lobby: {e}");
+            }
+        }
+        self.connection = Connection::Lobby(lobby);
+        self.update_connection_state(ConnectionEvent::Established);
+        self.focused_component = None;
+        Ok(())
+    }
+
+    /// # Connect to backend
+    ///
+    /// Opens a fresh connection to the lobby list, falling back to the offline
+    /// mode if the backend is unreachable.
+    async fn connect_to_backend(&mut self) -> Result<()> {
+        self.update_connection_state(ConnectionEvent::Connect);
+        self.connection = Connection::new(self.tx.clone(), &self.config.server).await?;
+        self.update_connection_state(ConnectionEvent::from(&self.connection));
+        Ok(())
+    }
+
+    /// # Update connection state
+    ///
+    /// Moves the connection state along the given event and logs the
+    /// transition.
+    fn update_connection_state(&mut self, event: ConnectionEvent) {
+        let state = self.connection_state.next(event);
+        if state != self.connection_state {
+            info!(
+                "Connection state changed from {} to {}.",
+                self.connection_state, state
+            );
+            self.connection_state = state;
+        }
+    }
+
+    /// # Return to lobby list
+    ///
+    /// Opens a fresh connection to the lobby list and displays the given
+    /// notice there.
+    async fn return_to_lobby_list(&mut self, notice: &str) -> Result<()> {
+        self.focused_component = None;
+        self.connect_to_backend().await?;
+        if let Connection::Join(ref mut join) = self.connection {
+            join.notice = Some(notice.to_string());
+        }
+        Ok(())
+    }
+
     pub async fn handle_connection_message(&mut self) -> Result<()> {
         match self.connection {
             Connection::Lobby(ref mut lobby) => {
@@ -334,3 +580,16 @@ impl App {
         Ok(())
     }
 }
+
+/// # Quit on interrupt
+///
+/// Waits for an interrupt signal, e.g., Ctrl-C outside of raw mode, and tells
+/// the app to quit cleanly.
+async fn quit_on_interrupt(app_tx: UnboundedSender<AppMessage>) {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Error listening for the interrupt signal: {e}");
+        return;
+    }
+    info!("Received interrupt signal, quitting.");
+    let _ = app_tx.send(AppMessage::Quit);
+}
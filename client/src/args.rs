@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 use anyhow::Result;
 use clap::Parser;
@@ -17,6 +17,10 @@ pub struct Args {
     pub config: Config,
     #[arg(short, long, default_value = "keyglide.logs")]
     pub log: String,
+    /// Writes every sent and received protocol message to this file, with
+    /// tokens redacted.
+    #[arg(long)]
+    pub debug_protocol: Option<PathBuf>,
 }
 
 fn parse_duration(arg: &str) -> Result<std::time::Duration, std::num::ParseIntError> {
@@ -7,22 +7,61 @@ use audio::Audio;
 
 #[cfg(feature = "audio")]
 mod audio;
+mod editor;
 mod key_bindings;
+mod server;
+mod theme;
+
+pub use editor::{EditorConfig, EditorInput};
+pub use server::ServerConfig;
+pub use theme::Theme;
+use theme::COLOR_BLIND_SAFE_THEME;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
+    /// Whether to remember the last joined lobby and try to rejoin it on
+    /// startup.
+    #[serde(default)]
+    pub auto_rejoin: bool,
+    /// Whether to draw with the color-blind-safe palette and mark
+    /// information conveyed by color with symbols and border shapes as well.
+    #[serde(default)]
+    pub accessibility: bool,
     #[cfg(feature = "audio")]
     pub audio: Audio,
+    #[serde(default)]
+    pub editor: EditorConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
     pub key_bindings: KeyBindings,
 }
 
 impl Config {
+    /// # Palette
+    ///
+    /// Returns the colors to draw with. The accessibility mode replaces the
+    /// configured theme with the color-blind-safe one.
+    pub fn palette(&self) -> &Theme {
+        if self.accessibility {
+            &COLOR_BLIND_SAFE_THEME
+        } else {
+            &self.theme
+        }
+    }
+
     /// # Validate configuration
     ///
-    /// Checks whether there are obvious duplicates in leaf categories.
+    /// Checks whether there are obvious duplicates in leaf categories,
+    /// whether the configured editor is allowed, and whether the server
+    /// settings are consistent.
     pub fn validate(&self) -> Result<()> {
         self.key_bindings.validate()?;
+        self.editor.validate()?;
+        self.server.validate()?;
 
         #[cfg(feature = "audio")]
         self.audio.validate()?;
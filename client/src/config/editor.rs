@@ -0,0 +1,260 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use portable_pty::CommandBuilder;
+use serde::Deserialize;
+
+use crate::constants::{
+    DEFAULT_EDITOR_LAUNCH_RETRIES, DEFAULT_EDITOR_LAUNCH_TIMEOUT, EDITOR_PLACEHOLDER, FILE_ENV,
+    FILE_PLACEHOLDER,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct EditorConfig {
+    /// The editor binary used to edit the start file and show the goal file.
+    pub command: String,
+    /// Editors that are allowed to be started.
+    pub allowlist: Vec<String>,
+    /// Skips the allowlist check entirely, e.g., for trusted setups.
+    pub allow_any_editor: bool,
+    /// An optional config file the editor is started with.
+    pub config_file: Option<EditorConfigFile>,
+    /// Starts the editor with `HOME` and the XDG base directories inside the
+    /// session folder, so that none of the player's dotfiles are loaded. Only
+    /// the config file is there.
+    pub isolated_home: bool,
+    /// Command the editor is launched through, e.g., to apply resource limits
+    /// or a curated environment. `{editor}` and `{file}` are replaced with
+    /// the editor binary and the file to open. Empty launches the editor
+    /// directly.
+    pub wrapper: Vec<String>,
+    /// Wrapper binaries that are allowed to launch the editor.
+    pub wrapper_allowlist: Vec<String>,
+    /// Seconds the editor has to render its first output. Editors staying
+    /// silent for longer are killed.
+    pub launch_timeout: u64,
+    /// How often an editor that timed out is restarted before the launch
+    /// counts as failed.
+    pub launch_retries: usize,
+    /// How the editor receives the file content.
+    pub input: EditorInput,
+}
+
+/// How the file content is handed to the editor.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EditorInput {
+    /// The file is written to disk and its path passed as argument.
+    #[default]
+    Path,
+    /// The content is piped into stdin of the editor, e.g., `vim -`. Nothing
+    /// is written to disk upfront. The editor has to save to the path in
+    /// `KEYGLIDE_FILE` for the progress to be tracked.
+    Stdin,
+}
+
+/// A config file written into the session folder of the editor, e.g., a Helix
+/// `config.toml` with the player's keymaps and theme. `XDG_CONFIG_HOME` points
+/// at the session folder.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EditorConfigFile {
+    /// Path relative to the session folder, e.g., `helix/config.toml`.
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        let allowlist = [
+            "helix", "hx", "vim", "nvim", "vi", "nano", "emacs", "kak", "micro",
+        ];
+        Self {
+            command: String::from("helix"),
+            allowlist: allowlist.map(String::from).to_vec(),
+            allow_any_editor: false,
+            config_file: None,
+            isolated_home: false,
+            wrapper: Vec::new(),
+            wrapper_allowlist: ["nice", "ionice", "timeout", "prlimit", "firejail"]
+                .map(String::from)
+                .to_vec(),
+            launch_timeout: DEFAULT_EDITOR_LAUNCH_TIMEOUT.as_secs(),
+            launch_retries: DEFAULT_EDITOR_LAUNCH_RETRIES,
+            input: EditorInput::default(),
+        }
+    }
+}
+
+impl EditorConfig {
+    /// # Validate editor
+    ///
+    /// Checks whether the configured editor is part of the allowlist and the
+    /// wrapper part of the wrapper allowlist. Both are checked by their file
+    /// name if given as path, e.g., `/usr/bin/nvim`.
+    /// Config files have to stay inside the session folder, a wrapper has to
+    /// launch the editor, with the file unless it reads stdin, and the launch
+    /// timeout must not be zero.
+    pub fn validate(&self) -> Result<()> {
+        if self.launch_timeout == 0 {
+            return Err(anyhow!(
+                "Editor launch timeout has to be at least one second."
+            ));
+        }
+        if !self.wrapper.is_empty() {
+            let placeholders: &[&str] = match self.input {
+                EditorInput::Path => &[EDITOR_PLACEHOLDER, FILE_PLACEHOLDER],
+                EditorInput::Stdin => &[EDITOR_PLACEHOLDER],
+            };
+            for placeholder in placeholders {
+                if !self.wrapper.iter().any(|arg| arg.contains(placeholder)) {
+                    return Err(anyhow!(
+                        "Editor wrapper {} is missing the {placeholder} placeholder.",
+                        self.wrapper.join(" ")
+                    ));
+                }
+            }
+        }
+        if let Some(config_file) = &self.config_file {
+            let is_inside_session = config_file
+                .path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+            if !is_inside_session {
+                return Err(anyhow!(
+                    "Editor config file {} has to be a relative path without `..`.",
+                    config_file.path.display()
+                ));
+            }
+        }
+        if self.allow_any_editor {
+            return Ok(());
+        }
+        if !self
+            .allowlist
+            .iter()
+            .any(|allowed| allowed.eq(file_name(&self.command)))
+        {
+            return Err(anyhow!(
+                "Editor {} is not allowed. Allowed editors are: {}.",
+                self.command,
+                self.allowlist.join(", ")
+            ));
+        }
+        // A wrapper starting with the editor itself launches nothing else.
+        if let Some(wrapper) = self
+            .wrapper
+            .first()
+            .filter(|wrapper| wrapper.as_str() != EDITOR_PLACEHOLDER)
+        {
+            let name = file_name(wrapper);
+            if !self
+                .wrapper_allowlist
+                .iter()
+                .any(|allowed| allowed.eq(name))
+            {
+                return Err(anyhow!(
+                    "Editor wrapper {wrapper} is not allowed. Allowed wrappers are: {}.",
+                    self.wrapper_allowlist.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn launch_timeout(&self) -> Duration {
+        Duration::from_secs(self.launch_timeout)
+    }
+
+    /// # Build editor command
+    ///
+    /// Builds the command opening the given file with the configured editor,
+    /// launched through the wrapper if there is one. In stdin mode the file is
+    /// not passed as argument, but via `KEYGLIDE_FILE`.
+    pub fn command(&self, file_path: &Path) -> CommandBuilder {
+        let Some((program, args)) = self.wrapper.split_first() else {
+            let mut cmd = CommandBuilder::new(&self.command);
+            match self.input {
+                EditorInput::Path => cmd.arg(file_path),
+                EditorInput::Stdin => cmd.env(FILE_ENV, file_path),
+            }
+            return cmd;
+        };
+        let file_path = file_path.to_string_lossy();
+        let substitute = |arg: &String| {
+            arg.replace(EDITOR_PLACEHOLDER, &self.command)
+                .replace(FILE_PLACEHOLDER, &file_path)
+        };
+        let mut cmd = CommandBuilder::new(substitute(program));
+        cmd.args(args.iter().map(substitute));
+        if self.input == EditorInput::Stdin {
+            cmd.env(FILE_ENV, file_path.as_ref());
+        }
+        cmd
+    }
+}
+
+fn file_name(command: &str) -> &str {
+    Path::new(command)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(command: &str) -> EditorConfig {
+        EditorConfig {
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rejects_editors_outside_the_allowlist() {
+        assert!(editor("rm").validate().is_err());
+        assert!(editor("/bin/rm").validate().is_err());
+        assert!(editor("nvim").validate().is_ok());
+        assert!(editor("/usr/bin/nvim").validate().is_ok());
+    }
+
+    #[test]
+    fn allows_any_editor_if_configured() {
+        let config = EditorConfig {
+            allow_any_editor: true,
+            ..editor("rm")
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_wrappers_outside_the_wrapper_allowlist() {
+        let wrapped = |wrapper: &[&str]| EditorConfig {
+            wrapper: wrapper.iter().map(|arg| arg.to_string()).collect(),
+            ..editor("nvim")
+        };
+        assert!(wrapped(&["sh", "-c", "{editor} {file}"])
+            .validate()
+            .is_err());
+        assert!(wrapped(&["/bin/sh", "{editor}", "{file}"])
+            .validate()
+            .is_err());
+        assert!(wrapped(&["nice", "-n", "10", "{editor}", "{file}"])
+            .validate()
+            .is_ok());
+        assert!(wrapped(&["/usr/bin/nice", "{editor}", "{file}"])
+            .validate()
+            .is_ok());
+        assert!(wrapped(&["{editor}", "{file}"]).validate().is_ok());
+        let config = EditorConfig {
+            allow_any_editor: true,
+            ..wrapped(&["sh", "-c", "{editor} {file}"])
+        };
+        assert!(config.validate().is_ok());
+    }
+}
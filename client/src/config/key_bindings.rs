@@ -7,8 +7,8 @@ use serde::Deserialize;
 
 use client_derive::{CheckChildrenDuplicates, CheckDuplicates};
 
-#[derive(Clone, Debug, Deserialize, CheckChildrenDuplicates)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Clone, Debug, Default, Deserialize, CheckChildrenDuplicates)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct KeyBindings {
     pub movement: Movement,
     pub lobby: Lobby,
@@ -26,6 +26,40 @@ impl KeyBindings {
 
         Ok(())
     }
+
+    /// # Conflicts
+    ///
+    /// Describes every lobby and join key binding that overlaps with a
+    /// movement or miscellaneous one. Those are handled first, so the
+    /// overlapping binding never triggers.
+    pub fn conflicts(&self) -> Vec<String> {
+        let global = [
+            ("movement", self.movement.key_bindings()),
+            ("miscellaneous", self.miscellaneous.key_bindings()),
+        ];
+        let local = [
+            ("lobby", self.lobby.key_bindings()),
+            ("join", self.join.key_bindings()),
+        ];
+
+        let mut conflicts = Vec::new();
+        for (global_category, global_bindings) in global.iter() {
+            for (global_name, global_binding) in global_bindings.iter() {
+                for (local_category, local_bindings) in local.iter() {
+                    for (local_name, local_binding) in local_bindings.iter() {
+                        if global_binding.overlaps(local_binding) {
+                            conflicts.push(format!(
+                                "Key binding {local_category}.{} {local_binding} is shadowed by {global_category}.{}.",
+                                local_name.replace('_', "-"),
+                                global_name.replace('_', "-")
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        conflicts
+    }
 }
 
 impl Display for KeyBinding {
@@ -57,7 +91,7 @@ impl Display for KeyBinding {
 }
 
 #[derive(Clone, Debug, Deserialize, CheckDuplicates)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Movement {
     pub left: KeyBinding,
     pub down: KeyBinding,
@@ -65,15 +99,37 @@ pub struct Movement {
     pub up: KeyBinding,
 }
 
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            left: KeyBinding::new(KeyCode::Char('h'), None),
+            down: KeyBinding::new(KeyCode::Char('j'), None),
+            right: KeyBinding::new(KeyCode::Char('l'), None),
+            up: KeyBinding::new(KeyCode::Char('k'), None),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, CheckDuplicates)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Miscellaneous {
     pub unfocus: KeyBinding,
     pub toggle_full_screen: KeyBinding,
+    pub toggle_accessibility: KeyBinding,
+}
+
+impl Default for Miscellaneous {
+    fn default() -> Self {
+        Self {
+            unfocus: KeyBinding::new(KeyCode::Char('q'), Some(KeyModifiers::CONTROL)),
+            toggle_full_screen: KeyBinding::new(KeyCode::Char('f'), Some(KeyModifiers::CONTROL)),
+            toggle_accessibility: KeyBinding::new(KeyCode::Char('a'), Some(KeyModifiers::CONTROL)),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, CheckDuplicates)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Lobby {
     pub disconnect: KeyBinding,
     pub focus_chat: KeyBinding,
@@ -81,24 +137,71 @@ pub struct Lobby {
     pub focus_goal: KeyBinding,
     pub toggle_terminal_layout: KeyBinding,
     pub start: KeyBinding,
+    pub toggle_game_mode: KeyBinding,
+    pub toggle_bot: KeyBinding,
+    pub toggle_ready: KeyBinding,
+    pub toggle_status_bar: KeyBinding,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Self {
+            disconnect: KeyBinding::new(KeyCode::Char('d'), None),
+            focus_chat: KeyBinding::new(KeyCode::Char('s'), None),
+            focus_editor: KeyBinding::new(KeyCode::Char('i'), None),
+            focus_goal: KeyBinding::new(KeyCode::Char('g'), None),
+            toggle_terminal_layout: KeyBinding::new(KeyCode::Char('v'), None),
+            start: KeyBinding::new(KeyCode::Char('x'), None),
+            toggle_game_mode: KeyBinding::new(KeyCode::Char('m'), None),
+            toggle_bot: KeyBinding::new(KeyCode::Char('b'), None),
+            toggle_ready: KeyBinding::new(KeyCode::Char('r'), None),
+            toggle_status_bar: KeyBinding::new(KeyCode::Char('t'), None),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, CheckDuplicates)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Join {
     pub focus_lobby_list: KeyBinding,
+    pub focus_lobby_filter: KeyBinding,
     pub join_selected: KeyBinding,
     pub quickplay: KeyBinding,
     pub create: KeyBinding,
+    pub toggle_joinable: KeyBinding,
+    pub toggle_challenges: KeyBinding,
+}
+
+impl Default for Join {
+    fn default() -> Self {
+        Self {
+            focus_lobby_list: KeyBinding::new(KeyCode::Char('i'), None),
+            focus_lobby_filter: KeyBinding::new(KeyCode::Char('/'), None),
+            join_selected: KeyBinding::new(KeyCode::Enter, None),
+            quickplay: KeyBinding::new(KeyCode::Char('q'), None),
+            create: KeyBinding::new(KeyCode::Char('n'), None),
+            toggle_joinable: KeyBinding::new(KeyCode::Char('a'), None),
+            toggle_challenges: KeyBinding::new(KeyCode::Char('c'), None),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, CheckDuplicates)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Popup {
     pub confirm: KeyBinding,
     pub abort: KeyBinding,
 }
 
+impl Default for Popup {
+    fn default() -> Self {
+        Self {
+            confirm: KeyBinding::new(KeyCode::Char('y'), None),
+            abort: KeyBinding::new(KeyCode::Char('n'), None),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Hash, PartialEq, Eq)]
 pub struct KeyBinding {
     #[serde(deserialize_with = "deserialize_user_key")]
@@ -106,6 +209,23 @@ pub struct KeyBinding {
     pub modifiers: Option<KeyModifiers>,
 }
 
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: Option<KeyModifiers>) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// # Overlaps
+    ///
+    /// Whether a key event can match both key bindings. Key bindings without
+    /// modifiers match any modifiers.
+    pub fn overlaps(&self, other: &KeyBinding) -> bool {
+        self.code == other.code
+            && (self.modifiers.is_none()
+                || other.modifiers.is_none()
+                || self.modifiers == other.modifiers)
+    }
+}
+
 // Implement our own deserialization for user provided key codes. This
 // allows the user to provide simple string values instead of something like
 // this for a character, e.g., unfocus.code = { Char = 'q' }.
@@ -166,3 +286,35 @@ impl PartialEq<KeyBinding> for KeyEvent {
         self.code == other.code
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(key_bindings: &KeyBindings) -> Vec<(&'static str, &KeyBinding)> {
+        [
+            key_bindings.movement.key_bindings(),
+            key_bindings.lobby.key_bindings(),
+            key_bindings.join.key_bindings(),
+            key_bindings.popup.key_bindings(),
+            key_bindings.miscellaneous.key_bindings(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn defaults_match_the_shipped_config() {
+        let config: crate::config::Config =
+            toml::from_str(include_str!("../../config.toml")).unwrap();
+        assert_eq!(all(&config.key_bindings), all(&KeyBindings::default()));
+    }
+
+    #[test]
+    fn missing_key_bindings_fall_back_to_their_defaults() {
+        let key_bindings: KeyBindings =
+            toml::from_str("[lobby]\nstart = { code = \"s\" }\n").unwrap();
+        assert_eq!(key_bindings.lobby.start.code, KeyCode::Char('s'));
+        assert_eq!(key_bindings.lobby.toggle_ready.code, KeyCode::Char('r'));
+        assert_eq!(key_bindings.join.toggle_challenges.code, KeyCode::Char('c'));
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct ServerConfig {
+    /// Host and port of the backend service.
+    pub address: String,
+    /// Whether to connect via HTTPS and WSS.
+    pub tls: bool,
+    /// SHA-256 fingerprint of the DER encoded backend certificate as hex, e.g.,
+    /// `AB:CD:EF:...`. Colons are optional.
+    pub certificate_fingerprint: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: String::from("127.0.0.1:3030"),
+            tls: false,
+            certificate_fingerprint: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// # HTTP URL
+    ///
+    /// Returns the HTTP(S) URL of the given backend path, e.g., `health`.
+    pub fn http_url(&self, path: &str) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{scheme}://{}/{path}", self.address)
+    }
+
+    /// # WebSocket URL
+    ///
+    /// Returns the WS(S) URL of the given backend path, e.g., `clients`.
+    pub fn ws_url(&self, path: &str) -> String {
+        let scheme = if self.tls { "wss" } else { "ws" };
+        format!("{scheme}://{}/{path}", self.address)
+    }
+
+    /// # Pinned fingerprint
+    ///
+    /// Parses the configured certificate fingerprint into its 32 bytes.
+    /// Returns `None` if no fingerprint is pinned.
+    pub fn pinned_fingerprint(&self) -> Result<Option<Vec<u8>>> {
+        let Some(fingerprint) = &self.certificate_fingerprint else {
+            return Ok(None);
+        };
+        let hex = fingerprint.replace(':', "");
+        let invalid =
+            || anyhow!("Certificate fingerprint {fingerprint} is not a hex encoded SHA-256 hash.");
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(bytes))
+    }
+
+    /// # Validate server
+    ///
+    /// Checks whether the pinned fingerprint is well-formed and only used
+    /// together with TLS.
+    pub fn validate(&self) -> Result<()> {
+        if self.pinned_fingerprint()?.is_some() && !self.tls {
+            return Err(anyhow!(
+                "A certificate fingerprint can only be pinned if `tls` is enabled."
+            ));
+        }
+        Ok(())
+    }
+}
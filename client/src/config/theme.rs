@@ -0,0 +1,146 @@
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// Built-in palettes a theme starts from.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    ColorBlindSafe,
+}
+
+/// Palette of the accessibility mode. Built from the Okabe-Ito colors, which
+/// stay distinguishable with the common forms of color blindness.
+pub static COLOR_BLIND_SAFE_THEME: Theme = Theme {
+    focus: Color::Rgb(86, 180, 233),
+    selection: Color::Gray,
+    muted: Color::DarkGray,
+    highlight: Color::Rgb(240, 228, 66),
+    notice: Color::Rgb(230, 159, 0),
+    error: Color::Rgb(213, 94, 0),
+    success: Color::Rgb(0, 158, 115),
+    popup_text: Color::Black,
+    latency_good: Color::Rgb(0, 114, 178),
+    latency_ok: Color::Rgb(230, 159, 0),
+    latency_bad: Color::Rgb(213, 94, 0),
+};
+
+/// Colors of the semantic roles the UI is drawn with.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "ThemeConfig")]
+pub struct Theme {
+    /// Border of the focused component.
+    pub focus: Color,
+    /// Selected entries of the lobby list and the chat.
+    pub selection: Color,
+    /// Unavailable lobbies, placeholders and debug logs.
+    pub muted: Color,
+    /// The selected tab.
+    pub highlight: Color,
+    /// Notices, warnings and the borders of informational popups.
+    pub notice: Color,
+    /// Error logs and the background of the exit popup.
+    pub error: Color,
+    /// Info logs.
+    pub success: Color,
+    /// Text of the exit popup.
+    pub popup_text: Color,
+    /// Latency display depending on the round-trip time.
+    pub latency_good: Color,
+    pub latency_ok: Color,
+    pub latency_bad: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from(ThemeName::Dark)
+    }
+}
+
+impl From<ThemeName> for Theme {
+    fn from(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self {
+                focus: Color::Green,
+                selection: Color::DarkGray,
+                muted: Color::DarkGray,
+                highlight: Color::Yellow,
+                notice: Color::LightYellow,
+                error: Color::LightRed,
+                success: Color::LightGreen,
+                popup_text: Color::Black,
+                latency_good: Color::Green,
+                latency_ok: Color::Yellow,
+                latency_bad: Color::Red,
+            },
+            ThemeName::Light => Self {
+                focus: Color::Blue,
+                selection: Color::Gray,
+                muted: Color::Gray,
+                highlight: Color::Magenta,
+                notice: Color::Magenta,
+                error: Color::Red,
+                success: Color::Green,
+                popup_text: Color::White,
+                latency_good: Color::Green,
+                latency_ok: Color::Magenta,
+                latency_bad: Color::Red,
+            },
+            ThemeName::HighContrast => Self {
+                focus: Color::LightCyan,
+                selection: Color::White,
+                muted: Color::Gray,
+                highlight: Color::LightYellow,
+                notice: Color::LightYellow,
+                error: Color::LightRed,
+                success: Color::LightGreen,
+                popup_text: Color::Black,
+                latency_good: Color::LightGreen,
+                latency_ok: Color::LightYellow,
+                latency_bad: Color::LightRed,
+            },
+            ThemeName::ColorBlindSafe => COLOR_BLIND_SAFE_THEME.clone(),
+        }
+    }
+}
+
+/// Describes the theme inside the `theme` section of the config file: a
+/// built-in theme and optional overrides of its individual colors.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+struct ThemeConfig {
+    name: ThemeName,
+    focus: Option<Color>,
+    selection: Option<Color>,
+    muted: Option<Color>,
+    highlight: Option<Color>,
+    notice: Option<Color>,
+    error: Option<Color>,
+    success: Option<Color>,
+    popup_text: Option<Color>,
+    latency_good: Option<Color>,
+    latency_ok: Option<Color>,
+    latency_bad: Option<Color>,
+}
+
+impl From<ThemeConfig> for Theme {
+    fn from(config: ThemeConfig) -> Self {
+        let theme = Theme::from(config.name);
+        Self {
+            focus: config.focus.unwrap_or(theme.focus),
+            selection: config.selection.unwrap_or(theme.selection),
+            muted: config.muted.unwrap_or(theme.muted),
+            highlight: config.highlight.unwrap_or(theme.highlight),
+            notice: config.notice.unwrap_or(theme.notice),
+            error: config.error.unwrap_or(theme.error),
+            success: config.success.unwrap_or(theme.success),
+            popup_text: config.popup_text.unwrap_or(theme.popup_text),
+            latency_good: config.latency_good.unwrap_or(theme.latency_good),
+            latency_ok: config.latency_ok.unwrap_or(theme.latency_ok),
+            latency_bad: config.latency_bad.unwrap_or(theme.latency_bad),
+        }
+    }
+}
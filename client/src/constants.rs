@@ -1,17 +1,71 @@
 use std::time::Duration;
 
 pub static APP_TITLE: &str = "KEYGLIDE";
+/// Editors that did not come up are restarted once by default.
+pub static DEFAULT_EDITOR_LAUNCH_RETRIES: usize = 1;
+/// Editors have five seconds to render something by default.
+pub static DEFAULT_EDITOR_LAUNCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Unchanged lines shown around each difference of the post-round diff.
+pub static DIFF_CONTEXT_LINES: usize = 1;
 /// The height of the editor instance displaying the actual editor (the user is
 /// editing in) in percent of the whole application size.
 pub static EDITOR_HEIGHT: f64 = 0.5;
+/// Placeholder of the editor wrapper replaced with the editor binary.
+pub static EDITOR_PLACEHOLDER: &str = "{editor}";
+/// Shutting down waits this long for the editor process to exit.
+pub static EDITOR_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+/// Editors exiting on their own within this time after spawn count as failed
+/// to start.
+pub static EDITOR_STARTUP_WINDOW: Duration = Duration::from_secs(2);
+/// Ends the input of editors reading the file from stdin, i.e., Ctrl-D.
+pub static END_OF_TRANSMISSION: u8 = 0x04;
+/// Environment variable telling editors in stdin mode where to save.
+pub static FILE_ENV: &str = "KEYGLIDE_FILE";
+/// Placeholder of the editor wrapper replaced with the file to open.
+pub static FILE_PLACEHOLDER: &str = "{file}";
 /// The height of the editor instance displaying the goal in percent of the
 /// whole application size.
 pub static GOAL_HEIGHT: f64 = 0.5;
+/// XDG base directories of editors in an isolated home, relative to the
+/// session folder.
+pub static ISOLATED_XDG_DIRECTORIES: [(&str, &str); 3] = [
+    ("XDG_DATA_HOME", ".local/share"),
+    ("XDG_STATE_HOME", ".local/state"),
+    ("XDG_CACHE_HOME", ".cache"),
+];
+/// Round-trip times below this are rated good.
+pub static LATENCY_GOOD: Duration = Duration::from_millis(80);
+/// Round-trip times below this are rated ok, above poor.
+pub static LATENCY_OK: Duration = Duration::from_millis(200);
+/// The backend connection is pinged every two seconds to measure the latency.
+pub static LATENCY_PING_INTERVAL: Duration = Duration::from_secs(2);
+/// Weight of a new round-trip time sample in the displayed latency.
+pub static LATENCY_SMOOTHING: f64 = 0.2;
+/// File the ID of the last joined lobby is persisted to if auto rejoin is
+/// enabled.
+pub static LAST_LOBBY_FILE: &str = "keyglide.last_lobby";
 /// Width of the sidebar in the play tab in percent of the whole application
 /// size.
 pub static PLAY_SIDE_WIDTH: f64 = 0.2;
+/// Up to 32 output chunks of a PTY are buffered before reading pauses.
+pub static PTY_OUTPUT_BACKLOG: usize = 32;
+/// At most 128 KiB of PTY output are processed per tick, so that output
+/// bursts do not stall the UI.
+pub static PTY_OUTPUT_PER_TICK: usize = 128 * 1024;
 
+/// Delay before the first reconnect attempt, doubled after every failure.
 pub static RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+/// Reconnect delays are randomly spread by up to ±30%, so that clients do not
+/// reconnect in sync once the backend restarts.
+pub static RECONNECT_JITTER: f64 = 0.3;
+/// Upper bound of the reconnect delay before the jitter is applied.
+pub static RECONNECT_MAX_INTERVAL: Duration = Duration::from_secs(60);
 pub static SYMBOLS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
 /// Width of the terminals in percent of the whole application size.
 pub static TERMINAL_WIDTH: f64 = 0.8;
+/// The typing speed in the status bar is refreshed twice a second.
+pub static TYPING_SPEED_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+/// The typing speed is averaged over the keystrokes of the last ten seconds.
+pub static TYPING_SPEED_WINDOW: Duration = Duration::from_secs(10);
+/// Marks lobbies players can not join right now in the accessibility mode.
+pub static UNAVAILABLE_SYMBOL: char = '✗';
@@ -0,0 +1,41 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::Result;
+use log::{debug, warn};
+use uuid::Uuid;
+
+use crate::constants::LAST_LOBBY_FILE;
+
+/// # Load last lobby
+///
+/// Reads the ID of the last joined lobby from disk. Returns `None` if no lobby
+/// was stored yet or the stored ID is malformed.
+pub fn load() -> Option<Uuid> {
+    let content = fs::read_to_string(LAST_LOBBY_FILE).ok()?;
+    match Uuid::from_str(content.trim()) {
+        Ok(lobby_id) => Some(lobby_id),
+        Err(e) => {
+            warn!("Ignoring malformed last lobby file {LAST_LOBBY_FILE}: {e}");
+            None
+        }
+    }
+}
+
+/// # Store last lobby
+///
+/// Persists the ID of the lobby the player just joined.
+pub fn store(lobby_id: Uuid) -> Result<()> {
+    debug!("Store last lobby {}.", lobby_id);
+    fs::write(LAST_LOBBY_FILE, lobby_id.to_string())?;
+    Ok(())
+}
+
+/// # Clear last lobby
+///
+/// Forgets the last joined lobby, e.g., after it no longer exists.
+pub fn clear() -> Result<()> {
+    if Path::new(LAST_LOBBY_FILE).exists() {
+        fs::remove_file(LAST_LOBBY_FILE)?;
+    }
+    Ok(())
+}
@@ -7,6 +7,7 @@ use anyhow::Result;
 use args::Args;
 use clap::Parser;
 use env_logger::Env;
+use log::{info, warn};
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
@@ -26,7 +27,9 @@ mod args;
 mod audio;
 mod config;
 mod constants;
+mod last_lobby;
 mod schema;
+mod server;
 mod ui;
 
 #[tokio::main]
@@ -47,6 +50,17 @@ async fn main() -> Result<()> {
         })
         .init();
 
+    if let Some(ref path) = args.debug_protocol {
+        common::trace::enable(path)?;
+        info!("Tracing protocol messages to {}.", path.display());
+    }
+
+    // Configuration is parsed before the logger exists, so report conflicting
+    // key bindings now.
+    for conflict in args.config.key_bindings.conflicts() {
+        warn!("{conflict}");
+    }
+
     // Setup the terminal.
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -54,9 +68,12 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create the app and run it.
-    let mut app = App::new(args.config, terminal.size()?).await?;
-    let res = app.run(&mut terminal, args.tick_rate).await;
+    // Create the app and run it. Errors are printed after the terminal is
+    // restored.
+    let res = match App::new(args.config, terminal.size()?).await {
+        Ok(mut app) => app.run(&mut terminal, args.tick_rate).await,
+        Err(e) => Err(e),
+    };
 
     // Restore the terminal after app termination.
     disable_raw_mode()?;
@@ -1,11 +1,16 @@
 pub(crate) mod chat;
+pub(crate) mod command;
 pub(crate) mod connection;
+pub(crate) mod diff;
 pub(crate) mod editor;
 pub(crate) mod encryption;
 pub(crate) mod focused_component;
 pub(crate) mod goal;
 pub(crate) mod join;
+pub(crate) mod latency;
 pub(crate) mod lobby;
 pub(crate) mod offline;
+pub(crate) mod replay;
 pub(crate) mod tab;
 pub(crate) mod terminal;
+pub(crate) mod typing_speed;
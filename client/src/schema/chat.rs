@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use common::constants::MAX_CHAT_MESSAGE_LENGTH;
 use log::debug;
 use ratatui::{
@@ -7,10 +8,21 @@ use ratatui::{
 };
 use tokio::sync::mpsc::UnboundedSender;
 
-use super::lobby::LobbyMessage;
+use super::{
+    command::{parse_command, Command, HELP},
+    lobby::LobbyMessage,
+};
+
+/// A line inside the chat.
+pub struct ChatLine {
+    /// The time the backend sent the line. Notices of the client itself have
+    /// no timestamp.
+    pub sent_at: Option<DateTime<Utc>>,
+    pub text: String,
+}
 
 pub struct Chat {
-    pub messages: Vec<String>,
+    pub messages: Vec<ChatLine>,
     pub input: String,
     pub message_tx: UnboundedSender<LobbyMessage>,
     pub state: TableState,
@@ -27,8 +39,29 @@ impl Chat {
     }
 
     pub fn add_message(&mut self, message: String) {
+        self.add_timestamped_message(message, None);
+    }
+
+    /// # Add timestamped message
+    ///
+    /// Adds a message sent by the backend at the given time. The message is
+    /// placed in front of messages the backend sent later, so that lines
+    /// arriving out of order are still displayed in order.
+    pub fn add_timestamped_message(&mut self, message: String, sent_at: Option<DateTime<Utc>>) {
         debug!("Add message '{message}' to chat.");
-        self.messages.push(message);
+        let line = ChatLine {
+            sent_at,
+            text: message,
+        };
+        let index = match sent_at {
+            Some(sent_at) => self
+                .messages
+                .iter()
+                .rposition(|line| line.sent_at.is_none_or(|other| other <= sent_at))
+                .map_or(0, |i| i + 1),
+            None => self.messages.len(),
+        };
+        self.messages.insert(index, line);
         self.state.scroll_down_by(1);
     }
 
@@ -73,13 +106,65 @@ impl Chat {
                 self.input.pop();
             }
             KeyCode::Enter => {
-                self.message_tx.send(LobbyMessage::SendMessage {
-                    message: self.input.clone(),
-                })?;
-                self.input = String::new();
+                let input = std::mem::take(&mut self.input);
+                match parse_command(&input) {
+                    // Regular chat messages are sent as is.
+                    None => self
+                        .message_tx
+                        .send(LobbyMessage::SendMessage { message: input })?,
+                    Some(Ok(command)) => self.handle_command(command)?,
+                    Some(Err(e)) => self.add_message(e.to_string()),
+                }
             }
             _ => {}
         };
         Ok(())
     }
+
+    /// # Handle command
+    ///
+    /// Translates a chat command into the matching lobby message. The help
+    /// command is answered locally.
+    fn handle_command(&mut self, command: Command) -> Result<()> {
+        debug!("Handle command {:?}.", command);
+
+        let message = match command {
+            Command::Ready => LobbyMessage::ToggleReady,
+            Command::Whisper { name, message } => LobbyMessage::Whisper { name, message },
+            Command::Kick { name, reason } => LobbyMessage::KickPlayer { name, reason },
+            Command::VoteKick { name } => LobbyMessage::VoteKick { name },
+            Command::Mute { name, muted } => LobbyMessage::SetPlayerMuted { name, muted },
+            Command::Goal { challenge_id } => LobbyMessage::SetLobbyGoal { challenge_id },
+            Command::Warmup { challenge_id } => LobbyMessage::SetWarmup { challenge_id },
+            Command::Challenges => LobbyMessage::ListChallenges,
+            Command::GoalPreview => LobbyMessage::ToggleGoalPreview,
+            Command::SolutionReveal => LobbyMessage::ToggleSolutionReveal,
+            Command::Pause => LobbyMessage::PauseRound,
+            Command::Resume => LobbyMessage::ResumeRound,
+            Command::Tags { tags } => LobbyMessage::SetLobbyTags { tags },
+            Command::Comparison { comparison } => LobbyMessage::SetComparison { comparison },
+            Command::Description { description } => {
+                LobbyMessage::SetLobbyDescription { description }
+            }
+            Command::Seats { max_players } => LobbyMessage::SetMaxPlayers { max_players },
+            Command::TimeLimit { seconds } => LobbyMessage::SetTimeLimit { seconds },
+            Command::Spectators { max_spectators } => {
+                LobbyMessage::SetMaxSpectators { max_spectators }
+            }
+            Command::AllowSpectators { allowed } => LobbyMessage::SetSpectatorsAllowed { allowed },
+            Command::Replay { name } => LobbyMessage::RequestReplay { name },
+            Command::Rename { name } => LobbyMessage::RenamePlayer { name },
+            Command::Export { path } => LobbyMessage::ExportSolution { path },
+            Command::WhoAmI => LobbyMessage::WhoAmI,
+            Command::Leave => LobbyMessage::Leave,
+            Command::Help => {
+                for line in HELP {
+                    self.add_message(line.to_string());
+                }
+                return Ok(());
+            }
+        };
+        self.message_tx.send(message)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,249 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use common::{parse_lobby_tags, Comparison, LobbyTag};
+
+/// Lines shown in the chat when the player enters `/help`.
+pub static HELP: [&str; 27] = [
+    "Available commands:",
+    "/ready - Toggle your ready state",
+    "/w <name> <message> - Whisper to a player",
+    "/kick <name> [reason] - Kick a player (owner only)",
+    "/votekick <name> - Vote to kick a player, a majority of the others kicks",
+    "/mute <name> - Mute a player in the chat (owner only)",
+    "/unmute <name> - Unmute a player (owner only)",
+    "/goal <challenge> - Change the challenge (owner only)",
+    "/warmup [challenge] - Set or remove the warmup (owner only)",
+    "/challenges - List the available challenges",
+    "/preview - Show or hide the goal (owner only)",
+    "/reveal - Show or hide the winning solution (owner only)",
+    "/pause - Pause the round (owner only)",
+    "/resume - Resume the paused round (owner only)",
+    "/description [text] - Describe the lobby or clear it (owner only)",
+    "/tags [tag ...] - Tag the lobby as ranked, casual, teaching or practice (owner only)",
+    "/comparison [exact|ignore-whitespace|tokens] - Change how solutions are compared or use the challenge's (owner only)",
+    "/seats <count> - Change the seats of the lobby between rounds (owner only)",
+    "/timelimit [seconds] - Limit races or use the challenge's limit (owner only)",
+    "/spectators <count> - Limit the spectators (owner only)",
+    "/spectators <on|off> - Allow or forbid spectators (owner only)",
+    "/replay <name> - Fetch the last round inputs of a player",
+    "/name <name> - Change your name",
+    "/export <path> - Save your editor buffer to a file",
+    "/whoami - Show your session as seen by the server",
+    "/leave - Leave the lobby",
+    "/help - Show this help",
+];
+
+/// A command the player entered into the chat input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Ready,
+    Whisper { name: String, message: String },
+    Kick { name: String, reason: String },
+    VoteKick { name: String },
+    Mute { name: String, muted: bool },
+    Goal { challenge_id: String },
+    Warmup { challenge_id: Option<String> },
+    Challenges,
+    GoalPreview,
+    SolutionReveal,
+    Pause,
+    Resume,
+    Description { description: Option<String> },
+    Tags { tags: BTreeSet<LobbyTag> },
+    Comparison { comparison: Option<Comparison> },
+    Seats { max_players: usize },
+    TimeLimit { seconds: Option<u64> },
+    Spectators { max_spectators: usize },
+    AllowSpectators { allowed: bool },
+    Replay { name: String },
+    Rename { name: String },
+    Export { path: String },
+    WhoAmI,
+    Leave,
+    Help,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CommandError {
+    Unknown(String),
+    MissingArgument { usage: &'static str },
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Unknown(command) => {
+                write!(f, "Unknown command /{command}. Type /help for a list.")
+            }
+            CommandError::MissingArgument { usage } => write!(f, "Usage: {usage}"),
+        }
+    }
+}
+
+/// # Parse command
+///
+/// Maps a chat input to a command. Returns `None` if the input is not a
+/// command, i.e., does not start with a slash. Player names containing spaces
+/// can be wrapped in double quotes, e.g., `/kick "Jane Doe"`.
+pub fn parse_command(input: &str) -> Option<Result<Command, CommandError>> {
+    let input = input.trim().strip_prefix('/')?;
+    let (command, arguments) = input.split_once(' ').unwrap_or((input, ""));
+
+    let command = match command {
+        "ready" => Ok(Command::Ready),
+        "w" => match split_first_argument(arguments) {
+            Some((name, message)) if !message.is_empty() => Ok(Command::Whisper {
+                name,
+                message: message.to_string(),
+            }),
+            _ => Err(CommandError::MissingArgument {
+                usage: "/w <name> <message>",
+            }),
+        },
+        "kick" => match split_first_argument(arguments) {
+            Some((name, reason)) => Ok(Command::Kick {
+                name,
+                reason: reason.to_string(),
+            }),
+            None => Err(CommandError::MissingArgument {
+                usage: "/kick <name> [reason]",
+            }),
+        },
+        "votekick" => match split_first_argument(arguments) {
+            Some((name, _)) => Ok(Command::VoteKick { name }),
+            None => Err(CommandError::MissingArgument {
+                usage: "/votekick <name>",
+            }),
+        },
+        "mute" | "unmute" => match split_first_argument(arguments) {
+            Some((name, _)) => Ok(Command::Mute {
+                name,
+                muted: command == "mute",
+            }),
+            None => Err(CommandError::MissingArgument {
+                usage: if command == "mute" {
+                    "/mute <name>"
+                } else {
+                    "/unmute <name>"
+                },
+            }),
+        },
+        "goal" => match split_first_argument(arguments) {
+            Some((challenge_id, _)) => Ok(Command::Goal { challenge_id }),
+            None => Err(CommandError::MissingArgument {
+                usage: "/goal <challenge>",
+            }),
+        },
+        "warmup" => Ok(Command::Warmup {
+            challenge_id: split_first_argument(arguments).map(|(challenge_id, _)| challenge_id),
+        }),
+        "challenges" => Ok(Command::Challenges),
+        "preview" => Ok(Command::GoalPreview),
+        "reveal" => Ok(Command::SolutionReveal),
+        "pause" => Ok(Command::Pause),
+        "resume" => Ok(Command::Resume),
+        // The whole input is taken as description. Leaving it out removes
+        // the description.
+        "description" => Ok(Command::Description {
+            description: Some(arguments.trim())
+                .filter(|description| !description.is_empty())
+                .map(str::to_string),
+        }),
+        "tags" => match parse_lobby_tags(arguments) {
+            Ok(tags) => Ok(Command::Tags { tags }),
+            Err(_) => Err(CommandError::MissingArgument {
+                usage: "/tags [ranked|casual|teaching|practice ...], up to three",
+            }),
+        },
+        // Leaving out the comparison falls back to the one of the challenge.
+        "comparison" => match split_first_argument(arguments) {
+            None => Ok(Command::Comparison { comparison: None }),
+            Some((comparison, _)) => match comparison.parse() {
+                Ok(comparison) => Ok(Command::Comparison {
+                    comparison: Some(comparison),
+                }),
+                Err(_) => Err(CommandError::MissingArgument {
+                    usage: "/comparison [exact|ignore-whitespace|tokens]",
+                }),
+            },
+        },
+        "seats" => match split_first_argument(arguments)
+            .and_then(|(max_players, _)| max_players.parse().ok())
+        {
+            Some(max_players) => Ok(Command::Seats { max_players }),
+            None => Err(CommandError::MissingArgument {
+                usage: "/seats <count>",
+            }),
+        },
+        // Leaving out the seconds falls back to the limit of the challenge.
+        "timelimit" => match split_first_argument(arguments) {
+            None => Ok(Command::TimeLimit { seconds: None }),
+            Some((seconds, _)) => match seconds.parse() {
+                Ok(seconds) => Ok(Command::TimeLimit {
+                    seconds: Some(seconds),
+                }),
+                Err(_) => Err(CommandError::MissingArgument {
+                    usage: "/timelimit [seconds]",
+                }),
+            },
+        },
+        "spectators" => {
+            let argument = split_first_argument(arguments).map(|(argument, _)| argument);
+            match argument.as_deref() {
+                Some("on") => Ok(Command::AllowSpectators { allowed: true }),
+                Some("off") => Ok(Command::AllowSpectators { allowed: false }),
+                _ => match argument.and_then(|max_spectators| max_spectators.parse().ok()) {
+                    Some(max_spectators) => Ok(Command::Spectators { max_spectators }),
+                    None => Err(CommandError::MissingArgument {
+                        usage: "/spectators <count|on|off>",
+                    }),
+                },
+            }
+        }
+        "replay" => match split_first_argument(arguments) {
+            Some((name, _)) => Ok(Command::Replay { name }),
+            None => Err(CommandError::MissingArgument {
+                usage: "/replay <name>",
+            }),
+        },
+        // The whole input is taken as name, so that it may contain spaces.
+        "name" => match arguments.trim() {
+            "" => Err(CommandError::MissingArgument {
+                usage: "/name <name>",
+            }),
+            name => Ok(Command::Rename {
+                name: name.to_string(),
+            }),
+        },
+        "export" => match arguments.trim() {
+            "" => Err(CommandError::MissingArgument {
+                usage: "/export <path>",
+            }),
+            path => Ok(Command::Export {
+                path: path.to_string(),
+            }),
+        },
+        "whoami" => Ok(Command::WhoAmI),
+        "leave" => Ok(Command::Leave),
+        "help" => Ok(Command::Help),
+        command => Err(CommandError::Unknown(command.to_string())),
+    };
+    Some(command)
+}
+
+/// # Split first argument
+///
+/// Splits the first argument off the remaining input. The argument is either
+/// a single word or a double quoted string.
+fn split_first_argument(arguments: &str) -> Option<(String, &str)> {
+    let arguments = arguments.trim_start();
+    let (argument, rest) = if let Some(quoted) = arguments.strip_prefix('"') {
+        quoted.split_once('"')?
+    } else {
+        arguments.split_once(' ').unwrap_or((arguments, ""))
+    };
+    if argument.is_empty() {
+        return None;
+    }
+    Some((argument.to_string(), rest.trim()))
+}
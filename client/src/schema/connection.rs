@@ -1,9 +1,10 @@
 use anyhow::Result;
 use log::error;
+use strum::Display;
 use tokio::sync::mpsc::UnboundedSender;
 
 use super::{join::Join, lobby::Lobby, offline::Offline};
-use crate::app::AppMessage;
+use crate::{app::AppMessage, config::ServerConfig};
 
 pub enum Connection {
     Join(Join),
@@ -18,16 +19,82 @@ impl Connection {
     /// `Connection::Offline` variant and spawns a task that tries to reconnect
     /// continously.
     /// Notifies the application on a successful reconnect.
-    pub async fn new(app_tx: UnboundedSender<AppMessage>) -> Result<Self> {
-        let connection = match Join::new(app_tx.clone()).await {
+    pub async fn new(
+        app_tx: UnboundedSender<AppMessage>,
+        server_config: &ServerConfig,
+    ) -> Result<Self> {
+        let connection = match Join::new(app_tx.clone(), server_config).await {
             Ok(join) => Connection::Join(join),
             Err(e) => {
                 error!("Error connecting to backend service: {e}.");
 
-                let offline = Offline::new(app_tx);
+                let offline = Offline::new(app_tx, server_config.clone())?;
                 Connection::Offline(offline)
             }
         };
         Ok(connection)
     }
 }
+
+/// State of the connection to the backend as shown in the header.
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ConnectionState {
+    /// The connection was lost and no new one is attempted yet.
+    Disconnected,
+    #[default]
+    Connecting,
+    Connected,
+    /// Connecting again after the connection was lost, including the
+    /// continuous retries while offline.
+    Reconnecting,
+    /// The backend announced its shutdown and is about to close the
+    /// connection.
+    ShuttingDown,
+}
+
+/// Events moving the connection state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// A new connection to the lobby list or a lobby is attempted.
+    Connect,
+    /// The connection to the lobby list or a lobby is established.
+    Established,
+    /// The backend is unreachable and the client retries in the background.
+    Offline,
+    /// The connection closed unexpectedly.
+    Lost,
+    /// The backend sent `ServerShutdown`.
+    ServerShutdown,
+}
+
+impl ConnectionState {
+    /// # Next state
+    ///
+    /// Returns the state after the given event. Connection attempts following
+    /// a lost connection or a shutdown count as reconnecting.
+    pub fn next(self, event: ConnectionEvent) -> Self {
+        match (self, event) {
+            (_, ConnectionEvent::ServerShutdown) => ConnectionState::ShuttingDown,
+            (_, ConnectionEvent::Established) => ConnectionState::Connected,
+            (_, ConnectionEvent::Lost) => ConnectionState::Disconnected,
+            (_, ConnectionEvent::Offline) => ConnectionState::Reconnecting,
+            (
+                ConnectionState::Disconnected
+                | ConnectionState::Reconnecting
+                | ConnectionState::ShuttingDown,
+                ConnectionEvent::Connect,
+            ) => ConnectionState::Reconnecting,
+            (_, ConnectionEvent::Connect) => ConnectionState::Connecting,
+        }
+    }
+}
+
+impl From<&Connection> for ConnectionEvent {
+    fn from(connection: &Connection) -> Self {
+        match connection {
+            Connection::Join(_) | Connection::Lobby(_) => ConnectionEvent::Established,
+            Connection::Offline(_) => ConnectionEvent::Offline,
+        }
+    }
+}
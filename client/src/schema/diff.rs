@@ -0,0 +1,161 @@
+/// A line of the diff between the goal and the solution of a player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    Unchanged(String),
+    /// Line of the solution missing in the goal.
+    Added(String),
+    /// Line of the goal missing in the solution.
+    Removed(String),
+    /// Line of the goal replaced by a different line of the solution.
+    Changed {
+        goal: String,
+        solution: String,
+    },
+}
+
+/// Line-by-line diff of a solution against the goal of a race.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolutionDiff {
+    pub lines: Vec<DiffLine>,
+}
+
+impl SolutionDiff {
+    /// # Create solution diff
+    ///
+    /// Compares both files line by line with the Myers diff algorithm. A run
+    /// of removed lines directly followed by added lines is paired up into
+    /// changed lines.
+    pub fn new(goal: &[u8], solution: &[u8]) -> Self {
+        let goal = String::from_utf8_lossy(goal);
+        let solution = String::from_utf8_lossy(solution);
+        let goal = goal.lines().collect::<Vec<_>>();
+        let solution = solution.lines().collect::<Vec<_>>();
+
+        let mut lines = Vec::new();
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for edit in myers(&goal, &solution) {
+            match edit {
+                Edit::Equal(line) => {
+                    pair_changes(&mut lines, &mut removed, &mut added);
+                    lines.push(DiffLine::Unchanged(line.to_string()));
+                }
+                Edit::Delete(line) => removed.push(line),
+                Edit::Insert(line) => added.push(line),
+            }
+        }
+        pair_changes(&mut lines, &mut removed, &mut added);
+        Self { lines }
+    }
+
+    /// Whether the solution matches the goal line by line.
+    pub fn is_perfect(&self) -> bool {
+        self.lines
+            .iter()
+            .all(|line| matches!(line, DiffLine::Unchanged(_)))
+    }
+
+    /// # Count changes
+    ///
+    /// Returns the number of added, removed and changed lines.
+    pub fn count_changes(&self) -> (usize, usize, usize) {
+        self.lines
+            .iter()
+            .fold((0, 0, 0), |(added, removed, changed), line| match line {
+                DiffLine::Unchanged(_) => (added, removed, changed),
+                DiffLine::Added(_) => (added + 1, removed, changed),
+                DiffLine::Removed(_) => (added, removed + 1, changed),
+                DiffLine::Changed { .. } => (added, removed, changed + 1),
+            })
+    }
+}
+
+enum Edit<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// # Pair changes
+///
+/// Turns the pending removed and added lines into diff lines. Pairs of both
+/// become changed lines, the rest stays removed or added.
+fn pair_changes(lines: &mut Vec<DiffLine>, removed: &mut Vec<&str>, added: &mut Vec<&str>) {
+    let paired = removed.len().min(added.len());
+    for (goal, solution) in removed.iter().zip(added.iter()) {
+        lines.push(DiffLine::Changed {
+            goal: goal.to_string(),
+            solution: solution.to_string(),
+        });
+    }
+    for line in removed.drain(..).skip(paired) {
+        lines.push(DiffLine::Removed(line.to_string()));
+    }
+    for line in added.drain(..).skip(paired) {
+        lines.push(DiffLine::Added(line.to_string()));
+    }
+}
+
+/// # Myers diff
+///
+/// Finds the shortest edit script turning `old` into `new`. Walks the edit
+/// graph diagonal by diagonal and keeps the furthest reaching path of every
+/// diagonal per step, then backtracks through the recorded steps.
+fn myers<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Edit<'a>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    // Diagonals range from `-max` to `max`, shifted to be usable as index.
+    let index = |k: isize| (k + max + 1) as usize;
+    let mut v = vec![0; 2 * max as usize + 3];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = v[index(previous_k)];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Equal(old[x as usize - 1]));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == previous_x {
+                edits.push(Edit::Insert(new[y as usize - 1]));
+            } else {
+                edits.push(Edit::Delete(old[x as usize - 1]));
+            }
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    edits.reverse();
+    edits
+}
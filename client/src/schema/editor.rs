@@ -1,42 +1,78 @@
 use std::{
     env,
     fs::{self, File},
-    io::Write,
+    io::{ErrorKind, Write},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
-use log::{error, warn};
+use common::normalize::normalize_line_endings;
+use log::{debug, error, info, warn};
 use notify::{
     event::ModifyKind, Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
 };
-use portable_pty::{Child, CommandBuilder};
+use portable_pty::{Child, ChildKiller};
 use ratatui::layout::{Direction, Size};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::{
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    task::JoinHandle,
+    time::timeout,
+};
 use uuid::Uuid;
 
 use super::terminal::Terminal;
 use crate::{
-    constants::{EDITOR_HEIGHT, TERMINAL_WIDTH},
+    config::{EditorConfig, EditorInput},
+    constants::{
+        EDITOR_HEIGHT, EDITOR_SHUTDOWN_TIMEOUT, EDITOR_STARTUP_WINDOW, ISOLATED_XDG_DIRECTORIES,
+        TERMINAL_WIDTH,
+    },
     schema::lobby::LobbyMessage,
 };
 
 pub struct Editor {
     pub terminal: Terminal,
     pub is_full_screen: bool,
+    /// Whether a status bar with the match state is shown below the editor.
+    /// It takes up one row of the terminal outside of full screen.
+    pub status_bar: bool,
+    /// Whether the editor was killed on purpose, e.g., to restart it with new
+    /// challenge files.
+    killed: Arc<AtomicBool>,
+    /// The process ID of the editor. Not every platform provides one.
+    pid: Option<u32>,
+    /// The start file and optional config folder of this editor instance.
+    file_path: PathBuf,
+    config_dir: Option<PathBuf>,
+    /// The task waiting for the editor process to exit. Taken on shutdown.
+    termination: Option<JoinHandle<Result<()>>>,
 }
 
 impl Editor {
     /// # Create a new editor
     ///
     /// Starts a new editor inside a PTY instance that opens up the start file
-    /// of the current lobby.
+    /// of the current lobby. Fails if the configured editor is not allowed.
     pub fn new(
+        editor_config: &EditorConfig,
         app_size: Size,
         lobby_tx: UnboundedSender<LobbyMessage>,
         start_file: Vec<u8>,
         is_full_screen: bool,
+        status_bar: bool,
     ) -> Result<Self> {
+        // Refuse to start editors that are not allowed before touching any
+        // files.
+        editor_config.validate()?;
+
         // Get the temporary directory.
         let mut temp_dir = env::temp_dir();
         temp_dir.push("keyglide_challenge");
@@ -46,51 +82,246 @@ impl Editor {
             return Err(anyhow!("Failed to create folder: {e}"));
         }
         // Write the start file bytes to file.
+        let session_id = Uuid::new_v4();
         let mut file_path = temp_dir.clone();
-        file_path.push(Uuid::new_v4().to_string());
+        file_path.push(session_id.to_string());
 
-        let mut file = match File::create(&file_path) {
-            Ok(file) => file,
-            Err(e) => return Err(anyhow!("Error creating file: {e}")),
-        };
-        if let Err(e) = file.write_all(&start_file) {
-            return Err(anyhow!("Error writing to file: {e}"));
+        // Normalize line endings so that the file content does not depend on
+        // how the editor treats CRLF files on save.
+        let start_file = normalize_line_endings(&start_file);
+        // Editors reading stdin create the file on their first save.
+        if editor_config.input == EditorInput::Path {
+            let mut file = match File::create(&file_path) {
+                Ok(file) => file,
+                Err(e) => return Err(anyhow!("Error creating file: {e}")),
+            };
+            if let Err(e) = file.write_all(&start_file) {
+                return Err(anyhow!("Error writing to file: {e}"));
+            }
         }
 
         tokio::spawn(watch_progress(
-            temp_dir,
+            temp_dir.clone(),
             file_path.clone(),
             lobby_tx.clone(),
         ));
 
         // Build the command that opens the new start file.
-        let mut cmd = CommandBuilder::new("helix");
-        cmd.arg(&file_path);
+        let mut cmd = editor_config.command(&file_path);
+
+        // Start the editor with the player's config file, if there is one,
+        // and inside an isolated home if requested.
+        let config_dir = if editor_config.config_file.is_some() || editor_config.isolated_home {
+            let config_dir = temp_dir.join(format!("{session_id}-config"));
+            fs::create_dir_all(&config_dir)
+                .map_err(|e| anyhow!("Failed to create config folder: {e}"))?;
+            if let Some(config_file) = &editor_config.config_file {
+                let config_path = config_dir.join(&config_file.path);
+                if let Some(parent) = config_path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| anyhow!("Failed to create config folder: {e}"))?;
+                }
+                fs::write(&config_path, &config_file.content)
+                    .map_err(|e| anyhow!("Error writing editor config file: {e}"))?;
+            }
+            cmd.env("XDG_CONFIG_HOME", &config_dir);
+            if editor_config.isolated_home {
+                cmd.env("HOME", &config_dir);
+                for (variable, folder) in ISOLATED_XDG_DIRECTORIES {
+                    cmd.env(variable, config_dir.join(folder));
+                }
+            }
+            Some(config_dir)
+        } else {
+            None
+        };
 
         // Build the terminal and resize it directly.
-        let (terminal, child) = Terminal::new(app_size, cmd)?;
+        let (first_output_tx, first_output_rx) = oneshot::channel();
+        let (terminal, child) = Terminal::new(app_size, cmd, Some(first_output_tx))?;
+        if editor_config.input == EditorInput::Stdin {
+            terminal.feed_stdin(&start_file)?;
+        }
+        let pid = child.process_id();
+        match pid {
+            Some(pid) => info!("Started editor process with PID {pid}."),
+            None => info!("Started editor process without a known PID."),
+        }
+
+        // Tell the lobby once the editor is up and accepts input, or kill it
+        // if it does not come up in time.
+        let timed_out = Arc::new(AtomicBool::new(false));
+        tokio::spawn(Editor::supervise_launch(
+            first_output_rx,
+            lobby_tx.clone(),
+            editor_config.launch_timeout(),
+            terminal.child_killer.clone_killer(),
+            Arc::clone(&timed_out),
+        ));
 
         // Spawn a task that messages the application after our editor instance
         // terminates and kills the terminal process on app close.
-        tokio::spawn(Editor::handle_termination(child, lobby_tx));
+        let killed = Arc::new(AtomicBool::new(false));
+        let termination = tokio::spawn(Editor::handle_termination(
+            child,
+            lobby_tx,
+            file_path.clone(),
+            config_dir.clone(),
+            Arc::clone(&killed),
+            timed_out,
+        ));
 
         Ok(Self {
             terminal,
             is_full_screen,
+            status_bar,
+            killed,
+            pid,
+            file_path,
+            config_dir,
+            termination: Some(termination),
         })
     }
 
+    /// # Process ID
+    ///
+    /// Returns the process ID of the editor, e.g., to supervise or kill a stuck
+    /// editor from outside. Returns `None` if the platform does not provide
+    /// one.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// # Kill editor
+    ///
+    /// Kills the editor process. The termination is not reported as a failed
+    /// start, regardless of how long the editor ran. Editors that already
+    /// exited are left alone.
+    pub fn kill(&mut self) -> Result<()> {
+        if self
+            .termination
+            .as_ref()
+            .is_none_or(|termination| termination.is_finished())
+        {
+            return Ok(());
+        }
+        self.killed.store(true, Ordering::SeqCst);
+        self.terminal.child_killer.kill()?;
+        Ok(())
+    }
+
+    /// # Shut down editor
+    ///
+    /// Kills the editor process, waits for the termination handler to finish
+    /// and removes the session files. Returns once everything is torn down or
+    /// `EDITOR_SHUTDOWN_TIMEOUT` passed. Calling it again, or after the editor
+    /// exited on its own, only removes leftover files.
+    pub async fn shut_down(&mut self) -> Result<()> {
+        let killed = self.kill();
+        if let Some(termination) = self.termination.take() {
+            match timeout(EDITOR_SHUTDOWN_TIMEOUT, termination).await {
+                Ok(Ok(Ok(()))) => debug!("The editor shut down."),
+                Ok(Ok(Err(e))) => warn!("The editor terminated with an error: {e}"),
+                Ok(Err(e)) => warn!("The editor termination task failed: {e}"),
+                Err(_) => warn!(
+                    "The editor did not exit within {}s.",
+                    EDITOR_SHUTDOWN_TIMEOUT.as_secs()
+                ),
+            }
+        }
+        self.remove_files();
+        killed
+    }
+
+    /// # Read buffer
+    ///
+    /// Reads the start file of this editor instance, i.e., the buffer as the
+    /// player saved it last. Fails once the session file was removed, and in
+    /// stdin mode before the first save.
+    pub fn read_buffer(&self) -> Result<Vec<u8>> {
+        fs::read(&self.file_path).map_err(|e| anyhow!("Error reading editor buffer: {e}"))
+    }
+
+    /// # Remove files
+    ///
+    /// Removes the start file and config folder of this editor instance right
+    /// away. Used on shutdown, as the termination handler might not get to run
+    /// before the application exits.
+    pub fn remove_files(&self) {
+        remove_session_files(&self.file_path, self.config_dir.as_deref());
+    }
+
+    /// # Supervise launch
+    ///
+    /// Waits for the first output of the editor and tells the lobby that the
+    /// editor is ready. Editors that stay silent for the launch timeout are
+    /// killed, which the termination handler reports as timed out launch.
+    async fn supervise_launch(
+        first_output_rx: oneshot::Receiver<()>,
+        lobby_tx: UnboundedSender<LobbyMessage>,
+        launch_timeout: Duration,
+        mut child_killer: Box<dyn ChildKiller + Send + Sync>,
+        timed_out: Arc<AtomicBool>,
+    ) {
+        if timeout(launch_timeout, first_output_rx).await.is_ok() {
+            let _ = lobby_tx.send(LobbyMessage::EditorReady);
+            return;
+        }
+        warn!(
+            "The editor produced no output within {}s, killing it.",
+            launch_timeout.as_secs()
+        );
+        timed_out.store(true, Ordering::SeqCst);
+        if let Err(e) = child_killer.kill() {
+            error!("Error killing the editor that did not come up: {e}");
+        }
+    }
+
     /// # Handle termination
     ///
-    /// Waits for the child process to finish. After finish, message the lobby
-    /// and trigger a restart.
+    /// Waits for the child process to finish. After finish, remove the start
+    /// file and config folder of this editor instance, message the lobby and
+    /// trigger a restart. Editors that fail within `EDITOR_STARTUP_WINDOW`
+    /// without being killed are reported as failed to start instead, editors
+    /// killed for not coming up in time as timed out launch.
     pub async fn handle_termination(
         mut child: Box<dyn Child + Send + Sync>,
         lobby_tx: UnboundedSender<LobbyMessage>,
+        file_path: PathBuf,
+        config_dir: Option<PathBuf>,
+        killed: Arc<AtomicBool>,
+        timed_out: Arc<AtomicBool>,
     ) -> Result<()> {
-        child.wait()?;
+        let spawned_at = Instant::now();
+        let status = child.wait();
         warn!("The editor process terminated.");
-        lobby_tx.send(LobbyMessage::EditorTerminated)?;
+
+        // Clean up before anything else, so the files are removed even if
+        // nobody is listening anymore.
+        remove_session_files(&file_path, config_dir.as_deref());
+        let status = status?;
+
+        let message = if timed_out.load(Ordering::SeqCst) && !killed.load(Ordering::SeqCst) {
+            LobbyMessage::EditorLaunchTimedOut
+        } else if !status.success()
+            && !killed.load(Ordering::SeqCst)
+            && spawned_at.elapsed() < EDITOR_STARTUP_WINDOW
+        {
+            error!("The editor failed to start: {status}");
+            LobbyMessage::EditorFailedToStart {
+                reason: status.to_string(),
+            }
+        } else {
+            LobbyMessage::EditorTerminated {
+                killed: killed.load(Ordering::SeqCst),
+            }
+        };
+
+        // A closed channel just means the lobby is gone, e.g., because the
+        // player left. There is nothing to restart in that case.
+        if lobby_tx.send(message).is_err() {
+            debug!("Lobby channel closed, not restarting the editor.");
+        }
         Ok(())
     }
 
@@ -110,11 +341,35 @@ impl Editor {
                 ((cols - 2) as f64 * TERMINAL_WIDTH) as u16,
             ),
         };
+        // Leave a row for the status bar.
+        let rows = if self.status_bar { rows - 1 } else { rows };
         self.terminal.resize(rows, cols)?;
         Ok(())
     }
 }
 
+/// # Remove session files
+///
+/// Removes the start file and optional config folder of an editor instance.
+/// Files that are already gone are ignored.
+fn remove_session_files(file_path: &Path, config_dir: Option<&Path>) {
+    if let Err(e) = fs::remove_file(file_path) {
+        if e.kind() != ErrorKind::NotFound {
+            warn!("Error removing start file {}: {e}", file_path.display());
+        }
+    }
+    if let Some(config_dir) = config_dir {
+        if let Err(e) = fs::remove_dir_all(config_dir) {
+            if e.kind() != ErrorKind::NotFound {
+                warn!(
+                    "Error removing editor config folder {}: {e}",
+                    config_dir.display()
+                );
+            }
+        }
+    }
+}
+
 fn async_watcher() -> notify::Result<(RecommendedWatcher, UnboundedReceiver<notify::Result<Event>>)>
 {
     let (tx, rx) = unbounded_channel();
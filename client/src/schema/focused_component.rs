@@ -19,6 +19,7 @@ pub enum ComponentKind {
     ExitPopup,
     Goal,
     Lobbies,
+    LobbyFilter,
 }
 
 impl FocusedComponent {
@@ -65,6 +66,7 @@ impl FocusedComponent {
                 }
             }
             ComponentKind::Lobbies => {}
+            ComponentKind::LobbyFilter => {}
             ComponentKind::ExitPopup => {}
         };
         Ok(())
@@ -95,7 +97,13 @@ impl FocusedComponent {
                 if let Connection::Lobby(ref mut lobby) = app.connection {
                     // Only allow to edit the file if the lobby is in progress.
                     if let LobbyStatus::InProgress(_) = lobby.status {
-                        lobby.editor.terminal.handle_key_event(key)?;
+                        let bytes = lobby.editor.terminal.handle_key_event(key)?;
+                        lobby.replay.record(&bytes);
+                        // Keys without editor input, e.g., unmapped ones,
+                        // are no keystrokes.
+                        if !bytes.is_empty() {
+                            lobby.typing_speed.record();
+                        }
                     }
                 }
             }
@@ -105,6 +113,11 @@ impl FocusedComponent {
                     join.handle_key_event(&app.config, key).await?;
                 }
             }
+            ComponentKind::LobbyFilter => {
+                if let Connection::Join(ref mut join) = app.connection {
+                    join.handle_filter_key_event(key).await?;
+                }
+            }
             ComponentKind::ExitPopup => {
                 if key.eq(&app.config.key_bindings.popup.confirm) {
                     app.exit = true;
@@ -153,6 +166,7 @@ impl FocusedComponent {
                 }
             }
             ComponentKind::Lobbies => {}
+            ComponentKind::LobbyFilter => {}
             ComponentKind::ExitPopup => {}
         };
         Ok(())
@@ -1,12 +1,16 @@
 use std::{env::temp_dir, fs::File, io::Write};
 
 use anyhow::{anyhow, Result};
-use log::warn;
-use portable_pty::{Child, CommandBuilder};
+use common::normalize::normalize_line_endings;
+use log::{debug, warn};
+use portable_pty::Child;
 use ratatui::layout::{Direction, Size};
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::constants::{GOAL_HEIGHT, TERMINAL_WIDTH};
+use crate::{
+    config::{EditorConfig, EditorInput},
+    constants::{GOAL_HEIGHT, TERMINAL_WIDTH},
+};
 
 use super::{lobby::LobbyMessage, terminal::Terminal};
 
@@ -19,32 +23,41 @@ impl Goal {
     /// # Create a new goal editor
     ///
     /// Starts a new editor inside a PTY instance that opens up the goal file of
-    /// the current lobby.
+    /// the current lobby. Fails if the configured editor is not allowed.
     pub fn new(
+        editor_config: &EditorConfig,
         app_size: Size,
         lobby_tx: UnboundedSender<LobbyMessage>,
         goal_file: Vec<u8>,
         is_full_screen: bool,
     ) -> Result<Self> {
-        // Write the start file bytes to a temporary file.
+        editor_config.validate()?;
+
+        // Write the start file bytes to a temporary file, unless the editor
+        // reads them from stdin.
         let mut path = temp_dir();
         path.push("goal.txt");
+        let goal_file = normalize_line_endings(&goal_file);
 
-        let mut file = match File::create(&path) {
-            Ok(file) => file,
-            Err(e) => return Err(anyhow!("Error creating file: {e}")),
-        };
-        if let Err(e) = file.write_all(&goal_file) {
-            return Err(anyhow!("Error writing to file: {e}"));
+        if editor_config.input == EditorInput::Path {
+            let mut file = match File::create(&path) {
+                Ok(file) => file,
+                Err(e) => return Err(anyhow!("Error creating file: {e}")),
+            };
+            if let Err(e) = file.write_all(&goal_file) {
+                return Err(anyhow!("Error writing to file: {e}"));
+            }
         }
 
         // Build the command that opens the goal file fetched from the backend
         // service.
-        let mut cmd = CommandBuilder::new("helix");
-        cmd.arg(&path);
+        let cmd = editor_config.command(&path);
 
         // Build the terminal and resize it directly.
-        let (terminal, child) = Terminal::new(app_size, cmd)?;
+        let (terminal, child) = Terminal::new(app_size, cmd, None)?;
+        if editor_config.input == EditorInput::Stdin {
+            terminal.feed_stdin(&goal_file)?;
+        }
 
         tokio::spawn(Goal::handle_termination(child, lobby_tx));
 
@@ -64,7 +77,12 @@ impl Goal {
     ) -> Result<()> {
         child.wait()?;
         warn!("The goal process has completed.");
-        lobby_tx.send(LobbyMessage::GoalTerminated)?;
+
+        // A closed channel just means the lobby is gone, e.g., because the
+        // player left. There is nothing to restart in that case.
+        if lobby_tx.send(LobbyMessage::GoalTerminated).is_err() {
+            debug!("Lobby channel closed, not restarting the goal editor.");
+        }
         Ok(())
     }
 
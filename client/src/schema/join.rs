@@ -7,24 +7,28 @@ use futures_util::{
 };
 use log::{debug, error, info};
 use ratatui::{
-    crossterm::event::KeyEvent,
+    crossterm::event::{KeyCode, KeyEvent},
     widgets::{ScrollbarState, TableState},
 };
 use tokio::{
     net::TcpStream,
     sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
 };
-use tokio_tungstenite::{
-    connect_async,
-    tungstenite::{Error, Message},
-    MaybeTlsStream, WebSocketStream,
-};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use uuid::Uuid;
 
-use common::{constants::MAX_LOBBY_SIZE, BackendMessage, JoinMode, LobbyListItem, LobbyStatus};
+use common::{
+    BackendMessage, ChallengeInformation, ClientMessage, CloseReason, Difficulty, JoinMode,
+    LobbyAvailability, LobbyFilter, LobbyListItem, LobbyPlayerCount, LobbyStatus, LobbyTag,
+    SpectatorCount,
+};
 
 use super::encryption::{Encryption, EncryptionAction};
-use crate::{app::AppMessage, config::Config};
+use crate::{
+    app::AppMessage,
+    config::{Config, ServerConfig},
+    server,
+};
 
 pub struct Join {
     pub lobby_list: BTreeMap<Uuid, LobbyListItem>,
@@ -34,6 +38,22 @@ pub struct Join {
     pub app_tx: UnboundedSender<AppMessage>,
     pub state: TableState,
     pub scroll_state: ScrollbarState,
+    /// An optional notice displayed below the lobby list, e.g., after a failed
+    /// join attempt.
+    pub notice: Option<String>,
+    /// Raw input of the lobby filter, e.g., `foo lang:rust diff:easy`.
+    pub filter_input: String,
+    /// The filter the current lobby list was requested with.
+    pub filter: LobbyFilter,
+    /// The challenge catalog shown instead of the lobby list. `None` while
+    /// the lobbies are shown.
+    pub challenges: Option<Vec<ChallengeInformation>>,
+    pub challenge_state: TableState,
+    /// The ID the backend gave this connection. Passed on when joining a
+    /// lobby.
+    pub client_id: Option<Uuid>,
+    /// The lobby to rejoin as soon as the backend provided the client ID.
+    pub rejoin_lobby: Option<Uuid>,
 
     pub encrypted_names: BTreeMap<Uuid, Encryption>,
     pub encrypted_player_counts: BTreeMap<Uuid, Encryption>,
@@ -44,21 +64,30 @@ pub struct Join {
 pub enum JoinMessage {
     /// Updates the table showing current lobbies.
     CurrentLobbies(BTreeMap<Uuid, LobbyListItem>),
-    /// Closes the websocket connection to the backend service.
-    CloseConnection,
     /// Adds a lobby to the lobby list table.
     AddLobby(Uuid, LobbyListItem),
     /// Updates the player count for a lobby in the lobby list table.
-    UpdateLobbyPlayerCount { id: Uuid, player_count: usize },
+    UpdateLobbyPlayerCount(LobbyPlayerCount),
     /// Updates the status for a lobby in the lobby list table.
     UpdateLobbyStatus { id: Uuid, status: LobbyStatus },
+    /// Marks a lobby in the lobby list table as full or available.
+    LobbyAvailabilityChanged(LobbyAvailability),
+    /// Updates whether a lobby in the lobby list table can be watched.
+    SpectatorCountChanged(SpectatorCount),
     /// Removes a lobby from the lobby list table.
     RemoveLobby(Uuid),
+    /// Fills the challenge list with the catalog of the backend.
+    Challenges(Vec<ChallengeInformation>),
+    /// Remembers the ID the backend gave this connection.
+    ProvideClientId { id: Uuid },
 }
 
 impl Join {
-    pub async fn new(app_tx: UnboundedSender<AppMessage>) -> Result<Self, Error> {
-        let (ws_stream, _) = connect_async("ws://127.0.0.1:3030/clients").await?;
+    pub async fn new(
+        app_tx: UnboundedSender<AppMessage>,
+        server_config: &ServerConfig,
+    ) -> Result<Self> {
+        let ws_stream = server::connect_ws(server_config, "clients").await?;
         let (ws_tx, ws_rx) = ws_stream.split();
 
         let (tx, rx) = unbounded_channel();
@@ -77,6 +106,13 @@ impl Join {
             app_tx,
             state: TableState::default(),
             scroll_state: ScrollbarState::default(),
+            notice: None,
+            filter_input: String::new(),
+            filter: LobbyFilter::joinable(),
+            challenges: None,
+            challenge_state: TableState::default(),
+            client_id: None,
+            rejoin_lobby: None,
 
             encrypted_names: BTreeMap::new(),
             encrypted_player_counts: BTreeMap::new(),
@@ -90,24 +126,70 @@ impl Join {
         // Join a selected lobby.
         if key.eq(&config.key_bindings.join.join_selected) {
             if let Some(lobby_id) = self.selected_lobby {
-                self.ws_tx.close().await?;
                 let join_mode = JoinMode::Join { lobby_id };
                 self.app_tx.send(AppMessage::ConnectToLobby { join_mode })?;
             }
         } else if key.eq(&config.key_bindings.movement.down) {
-            self.next_lobby_entry();
+            if self.challenges.is_some() {
+                self.challenge_state.select_next();
+            } else {
+                self.next_lobby_entry();
+            }
         } else if key.eq(&config.key_bindings.movement.up) {
-            self.previous_lobby_entry();
+            if self.challenges.is_some() {
+                self.challenge_state.select_previous();
+            } else {
+                self.previous_lobby_entry();
+            }
         } else if key.eq(&config.key_bindings.join.quickplay) {
-            self.ws_tx.close().await?;
             let join_mode = JoinMode::Quickplay;
             self.app_tx.send(AppMessage::ConnectToLobby { join_mode })?;
         } else if key.eq(&config.key_bindings.join.create) {
-            debug!("Close client connection.");
-
-            self.ws_tx.close().await?;
             let join_mode = JoinMode::Create;
             self.app_tx.send(AppMessage::ConnectToLobby { join_mode })?;
+        } else if key.eq(&config.key_bindings.join.toggle_joinable) {
+            // Switch between joinable and all lobbies.
+            self.filter.joinable_only = !self.filter.joinable_only;
+            self.ws_tx
+                .send(ClientMessage::ListLobbies(self.filter.clone()).into())
+                .await?;
+        } else if key.eq(&config.key_bindings.join.toggle_challenges) {
+            // Switch between the lobby list and the challenge catalog, which
+            // is fetched anew every time it is shown.
+            if self.challenges.take().is_none() {
+                self.challenges = Some(Vec::new());
+                self.ws_tx
+                    .send(ClientMessage::ListChallenges.into())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// # Handle filter key event
+    ///
+    /// Edits the lobby filter input. On enter the filter is applied and the
+    /// matching lobbies are requested from the backend.
+    pub async fn handle_filter_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        debug!("Handle filter key event {:?}.", key);
+
+        match key.code {
+            KeyCode::Char(input) => self.filter_input.push(input),
+            KeyCode::Backspace => {
+                self.filter_input.pop();
+            }
+            KeyCode::Enter => match parse_filter(&self.filter_input) {
+                Ok(mut filter) => {
+                    filter.joinable_only = self.filter.joinable_only;
+                    self.notice = None;
+                    self.filter = filter.clone();
+                    self.ws_tx
+                        .send(ClientMessage::ListLobbies(filter).into())
+                        .await?;
+                }
+                Err(e) => self.notice = Some(e),
+            },
+            _ => {}
         }
         Ok(())
     }
@@ -116,13 +198,35 @@ impl Join {
         debug!("Handle message {:?}.", msg);
 
         match msg {
+            JoinMessage::ProvideClientId { id } => {
+                self.client_id = Some(id);
+                // Joining needs the client ID, so the last lobby is rejoined
+                // only now.
+                if let Some(lobby_id) = self.rejoin_lobby.take() {
+                    self.app_tx.send(AppMessage::RejoinLastLobby { lobby_id })?;
+                }
+            }
             JoinMessage::CurrentLobbies(lobby_list) => {
+                // Hide lobbies that are not part of the new list anymore, e.g.,
+                // because they do not match the filter.
+                let hidden_lobbies = self
+                    .lobby_list
+                    .keys()
+                    .filter(|id| !lobby_list.contains_key(id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for lobby_id in hidden_lobbies {
+                    self.hide_lobby(lobby_id);
+                }
+                self.selected_lobby = None;
+                self.state.select(None);
+
                 for (id, lobby) in lobby_list.iter() {
                     self.encrypted_names
                         .insert(*id, Encryption::new(lobby.name.clone()));
                     self.encrypted_player_counts.insert(
                         *id,
-                        Encryption::new(format!("{} / {}", lobby.player_count, MAX_LOBBY_SIZE)),
+                        Encryption::new(format!("{} / {}", lobby.player_count, lobby.max_players)),
                     );
                     self.encrypted_status
                         .insert(*id, Encryption::new(lobby.status.to_string()));
@@ -130,11 +234,16 @@ impl Join {
                 self.lobby_list = lobby_list;
                 self.scroll_state = self.scroll_state.content_length(self.lobby_list.len());
             }
-            JoinMessage::CloseConnection => {
-                info!("Close non-player connection.");
-                self.ws_tx.close().await?;
-            }
             JoinMessage::AddLobby(lobby_id, lobby) => {
+                // The backend announces new lobbies to every client, so apply
+                // the filter here as well.
+                if !self.filter.matches(&lobby) {
+                    debug!("Ignore lobby {} not matching the filter.", lobby.name);
+                    // Listed lobbies may stop matching, e.g., after their
+                    // challenge changed.
+                    self.hide_lobby(lobby_id);
+                    return Ok(());
+                }
                 info!(
                     "Update lobby list with lobby {} and {} players.",
                     lobby.name, lobby.player_count
@@ -143,7 +252,7 @@ impl Join {
                     .insert(lobby_id, Encryption::new(lobby.name.clone()));
                 self.encrypted_player_counts.insert(
                     lobby_id,
-                    Encryption::new(format!("{} / {}", lobby.player_count, MAX_LOBBY_SIZE)),
+                    Encryption::new(format!("{} / {}", lobby.player_count, lobby.max_players)),
                 );
                 self.encrypted_status
                     .insert(lobby_id, Encryption::new(lobby.status.to_string()));
@@ -157,32 +266,60 @@ impl Join {
                         self.selected_lobby = None;
                     }
                 }
-                if let Some(lobby) = self.lobby_list.remove(&lobby_id) {
-                    self.scroll_state = self.scroll_state.content_length(self.lobby_list.len());
-                    if let Some(encryption) = self.encrypted_names.get_mut(&lobby_id) {
-                        encryption.action = EncryptionAction::Left;
-                        encryption.index = encryption.value.len() - 1;
-                    }
-                    if let Some(encryption) = self.encrypted_player_counts.get_mut(&lobby_id) {
-                        encryption.action = EncryptionAction::Left;
-                        encryption.index = encryption.value.len() - 1;
-                    }
-                    if let Some(encryption) = self.encrypted_status.get_mut(&lobby_id) {
-                        encryption.action = EncryptionAction::Left;
-                        encryption.index = encryption.value.len() - 1;
-                    }
+                if let Some(lobby) = self.hide_lobby(lobby_id) {
                     info!("Remove lobby {} from lobby list.", lobby.name);
                 } else {
-                    error!("Tried to remove a non-existent lobby with ID {}.", lobby_id);
+                    debug!("Tried to remove a non-listed lobby with ID {}.", lobby_id);
                 }
             }
-            JoinMessage::UpdateLobbyPlayerCount { id, player_count } => {
-                if let Some(lobby) = self.lobby_list.get_mut(&id) {
+            JoinMessage::UpdateLobbyPlayerCount(count) => {
+                if let Some(lobby) = self.lobby_list.get_mut(&count.lobby_id) {
+                    self.encrypted_player_counts.insert(
+                        count.lobby_id,
+                        Encryption::new(format!("{} / {}", count.player_count, count.max_players)),
+                    );
+                    lobby.player_count = count.player_count;
+                    lobby.max_players = count.max_players;
+                }
+                self.refresh_lobby(count.lobby_id, false).await?;
+            }
+            JoinMessage::LobbyAvailabilityChanged(availability) => {
+                if let Some(lobby) = self.lobby_list.get_mut(&availability.lobby_id) {
+                    debug!(
+                        "Lobby {} is {} with {} / {} players.",
+                        lobby.name,
+                        if availability.is_full {
+                            "full"
+                        } else {
+                            "available"
+                        },
+                        availability.player_count,
+                        availability.max_players
+                    );
                     self.encrypted_player_counts.insert(
-                        id,
-                        Encryption::new(format!("{} / {}", player_count, MAX_LOBBY_SIZE)),
+                        availability.lobby_id,
+                        Encryption::new(format!(
+                            "{} / {}",
+                            availability.player_count, availability.max_players
+                        )),
                     );
-                    lobby.player_count = player_count;
+                    lobby.player_count = availability.player_count;
+                }
+                self.refresh_lobby(availability.lobby_id, !availability.is_full)
+                    .await?;
+            }
+            JoinMessage::Challenges(challenges) => {
+                // The list might have been closed in the meantime.
+                if self.challenges.is_some() {
+                    info!("Received {} challenges.", challenges.len());
+                    self.challenge_state
+                        .select((!challenges.is_empty()).then_some(0));
+                    self.challenges = Some(challenges);
+                }
+            }
+            JoinMessage::SpectatorCountChanged(count) => {
+                if let Some(lobby) = self.lobby_list.get_mut(&count.lobby_id) {
+                    lobby.spectators_allowed = count.spectators_allowed;
                 }
             }
             JoinMessage::UpdateLobbyStatus { id, status } => {
@@ -193,8 +330,10 @@ impl Join {
                     );
                     self.encrypted_status
                         .insert(id, Encryption::new(status.to_string()));
-                    lobby.status = status;
+                    lobby.status = status.clone();
                 }
+                self.refresh_lobby(id, status == LobbyStatus::WaitingForPlayers)
+                    .await?;
             }
         }
         Ok(())
@@ -208,13 +347,15 @@ impl Join {
         while let Some(Ok(msg)) = ws_rx.next().await {
             debug!("Handle backend message {:?}.", msg);
 
-            if msg.is_close() {
-                return Ok(());
-            }
             let backend_message: BackendMessage = msg.into();
             match backend_message {
-                BackendMessage::CloseConnection => {
-                    message_tx.send(JoinMessage::CloseConnection)?;
+                // The backend closed the connection. Only reconnect if it is
+                // expected back, e.g., after a restart.
+                BackendMessage::CloseConnection { reason } => {
+                    info!("Backend closed the connection ({:?}).", reason);
+                    if reason.is_some_and(CloseReason::should_reconnect) {
+                        app_tx.send(AppMessage::ServiceDisconnected)?;
+                    }
                     return Ok(());
                 }
                 BackendMessage::CurrentLobbies(lobbies) => {
@@ -229,12 +370,27 @@ impl Join {
                 BackendMessage::ConnectionCounts { clients, players } => {
                     app_tx.send(AppMessage::ConnectionCounts { clients, players })?;
                 }
-                BackendMessage::UpdateLobbyPlayerCount { id, player_count } => {
-                    message_tx.send(JoinMessage::UpdateLobbyPlayerCount { id, player_count })?;
+                BackendMessage::ServerShutdown => {
+                    app_tx.send(AppMessage::ServerShutdown)?;
+                }
+                BackendMessage::UpdateLobbyPlayerCount(count) => {
+                    message_tx.send(JoinMessage::UpdateLobbyPlayerCount(count))?;
                 }
                 BackendMessage::UpdateLobbyStatus { id, status } => {
                     message_tx.send(JoinMessage::UpdateLobbyStatus { id, status })?;
                 }
+                BackendMessage::LobbyAvailabilityChanged(availability) => {
+                    message_tx.send(JoinMessage::LobbyAvailabilityChanged(availability))?;
+                }
+                BackendMessage::SpectatorCountChanged(count) => {
+                    message_tx.send(JoinMessage::SpectatorCountChanged(count))?;
+                }
+                BackendMessage::Challenges(challenges) => {
+                    message_tx.send(JoinMessage::Challenges(challenges))?;
+                }
+                BackendMessage::ProvideClientId { id } => {
+                    message_tx.send(JoinMessage::ProvideClientId { id })?;
+                }
                 _ => {}
             }
         }
@@ -246,6 +402,54 @@ impl Join {
         Ok(())
     }
 
+    /// # Refresh lobby
+    ///
+    /// Hides a listed lobby that stopped matching the filter, e.g., because it
+    /// became full while only joinable lobbies are shown. Requests the lobby
+    /// list again if an unlisted lobby may have become joinable.
+    async fn refresh_lobby(&mut self, lobby_id: Uuid, may_be_joinable: bool) -> Result<()> {
+        match self.lobby_list.get(&lobby_id) {
+            Some(lobby) if !self.filter.matches(lobby) => {
+                if self
+                    .selected_lobby
+                    .is_some_and(|selected| selected == lobby_id)
+                {
+                    self.selected_lobby = None;
+                    self.state.select(None);
+                }
+                self.hide_lobby(lobby_id);
+            }
+            None if may_be_joinable && self.filter.joinable_only => {
+                self.ws_tx
+                    .send(ClientMessage::ListLobbies(self.filter.clone()).into())
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// # Hide lobby
+    ///
+    /// Removes a lobby from the lobby list and lets its table row animate out.
+    fn hide_lobby(&mut self, lobby_id: Uuid) -> Option<LobbyListItem> {
+        let lobby = self.lobby_list.remove(&lobby_id)?;
+        self.scroll_state = self.scroll_state.content_length(self.lobby_list.len());
+        if let Some(encryption) = self.encrypted_names.get_mut(&lobby_id) {
+            encryption.action = EncryptionAction::Left;
+            encryption.index = encryption.value.len() - 1;
+        }
+        if let Some(encryption) = self.encrypted_player_counts.get_mut(&lobby_id) {
+            encryption.action = EncryptionAction::Left;
+            encryption.index = encryption.value.len() - 1;
+        }
+        if let Some(encryption) = self.encrypted_status.get_mut(&lobby_id) {
+            encryption.action = EncryptionAction::Left;
+            encryption.index = encryption.value.len() - 1;
+        }
+        Some(lobby)
+    }
+
     /// # Next lobby entry
     ///
     /// Selects the next lobby entry given an already selected lobby. Otherwise
@@ -337,3 +541,34 @@ impl Join {
         }
     }
 }
+
+/// # Parse filter
+///
+/// Builds a lobby filter from the filter input. Words prefixed with `lang:`,
+/// `diff:` or `tag:` select the language, difficulty and tag, all other words
+/// make up the lobby name.
+fn parse_filter(input: &str) -> Result<LobbyFilter, String> {
+    let mut filter = LobbyFilter::default();
+    let mut name = Vec::new();
+    for word in input.split_whitespace() {
+        if let Some(language) = word.strip_prefix("lang:") {
+            filter.language = Some(language.to_string());
+        } else if let Some(difficulty) = word.strip_prefix("diff:") {
+            let Ok(difficulty) = difficulty.parse::<Difficulty>() else {
+                return Err(format!("Unknown difficulty {difficulty}."));
+            };
+            filter.difficulty = Some(difficulty);
+        } else if let Some(tag) = word.strip_prefix("tag:") {
+            let Ok(tag) = tag.parse::<LobbyTag>() else {
+                return Err(format!("Unknown tag {tag}."));
+            };
+            filter.tag = Some(tag);
+        } else {
+            name.push(word);
+        }
+    }
+    if !name.is_empty() {
+        filter.name_contains = Some(name.join(" "));
+    }
+    Ok(filter)
+}
@@ -0,0 +1,70 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::constants::LATENCY_SMOOTHING;
+
+/// Smooths round-trip time samples of the backend connection with an
+/// exponential moving average.
+#[derive(Debug, Default)]
+pub struct LatencyMeter {
+    average: Option<Duration>,
+}
+
+impl LatencyMeter {
+    /// # Record sample
+    ///
+    /// Adds a round-trip time sample. The first sample is taken as is, later
+    /// ones are weighted by `LATENCY_SMOOTHING`.
+    pub fn record(&mut self, sample: Duration) {
+        self.average = Some(match self.average {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_SMOOTHING) + sample.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => sample,
+        });
+    }
+
+    /// The smoothed round-trip time, if there is any sample yet.
+    pub fn average(&self) -> Option<Duration> {
+        self.average
+    }
+}
+
+/// # Clock offset
+///
+/// Estimates how far the clock of the backend is ahead of the local one from
+/// a server time received at `received_at`. The server time is assumed to
+/// be half a round trip old.
+pub fn clock_offset(
+    unix_millis: i64,
+    received_at: DateTime<Utc>,
+    round_trip_time: Option<Duration>,
+) -> Option<TimeDelta> {
+    let server_time = DateTime::from_timestamp_millis(unix_millis)?;
+    let transit = TimeDelta::from_std(round_trip_time.unwrap_or_default() / 2).ok()?;
+    Some(server_time + transit - received_at)
+}
+
+/// # Ping payload
+///
+/// Encodes the current time as payload of a WebSocket ping. The backend echoes
+/// it inside the pong.
+pub fn ping_payload() -> Vec<u8> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_micros().to_be_bytes().to_vec()
+}
+
+/// # Round-trip time
+///
+/// Decodes the payload of a pong and returns the time passed since the ping
+/// was sent. Returns `None` for payloads that were not created by
+/// `ping_payload`.
+pub fn round_trip_time(payload: &[u8]) -> Option<Duration> {
+    let sent_at = u128::from_be_bytes(payload.try_into().ok()?);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    let sent_at = Duration::from_micros(u64::try_from(sent_at).ok()?);
+    now.checked_sub(sent_at)
+}
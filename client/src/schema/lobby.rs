@@ -1,54 +1,246 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeDelta, Utc};
 use common::{
-    BackendMessage, ChallengeFiles, ClientMessage, JoinMode, LobbyInformation, LobbyStatus, Player,
+    BackendMessage, ChallengeFiles, ChallengeInformation, ChatMessage, ClientMessage, CloseReason,
+    Comparison, Difficulty, GameMode, JoinMode, LobbyInformation, LobbyStatus, LobbyTag, Player,
+    PlayerInfo, PlayerRoundState, QuickplayOutcome, Record, ReplayFrame, RoundState, RoundSummary,
+    SessionInfo,
 };
 use futures_util::{
     stream::{SplitSink, SplitStream},
     SinkExt, StreamExt,
 };
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use ratatui::layout::{Direction, Size};
 use tokio::{
     net::TcpStream,
     sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
 };
-use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use uuid::Uuid;
 
 use super::{
     chat::Chat,
+    diff::SolutionDiff,
     editor::Editor,
     encryption::{Encryption, EncryptionAction},
 };
 use crate::{
     app::AppMessage,
+    config::{EditorConfig, ServerConfig},
+    constants::LATENCY_PING_INTERVAL,
     schema::{
         focused_component::{ComponentKind, FocusedComponent},
         goal::Goal,
+        latency::{clock_offset, ping_payload, round_trip_time, LatencyMeter},
+        replay::ReplayRecorder,
+        typing_speed::TypingSpeed,
     },
+    server,
 };
 
 #[derive(Debug)]
 pub enum LobbyMessage {
-    CloseConnection,
-    EditorTerminated,
+    /// The editor process finished. `killed` tells whether it was killed on
+    /// purpose instead of quit by the player.
+    EditorTerminated {
+        killed: bool,
+    },
+    EditorFailedToStart {
+        reason: String,
+    },
+    EditorLaunchTimedOut,
+    EditorReady,
     GoalTerminated,
-    AssignOwner { id: Uuid },
+    SendPing,
+    Latency(Duration),
+    ServerTime {
+        unix_millis: i64,
+        received_at: DateTime<Utc>,
+    },
+    AssignOwner {
+        id: Uuid,
+    },
     PlayerJoined(Player),
     PlayerLeft(Uuid),
-    ReceiveMessage(String),
+    ReceiveMessage(ChatMessage),
     RequestStart,
-    StatusUpdate { status: LobbyStatus },
-    SendMessage { message: String },
-    SendProgress { progress: Vec<u8> },
-    SetLocalPlayerId { id: Uuid },
-    UpdatePlayerProgress { player_id: Uuid, progress: f64 },
+    StatusUpdate {
+        status: LobbyStatus,
+    },
+    SendMessage {
+        message: String,
+    },
+    SendProgress {
+        progress: Vec<u8>,
+    },
+    SetLocalPlayerId {
+        id: Uuid,
+    },
+    UpdatePlayerProgress {
+        player_id: Uuid,
+        progress: f64,
+    },
+    ToggleGameMode,
+    ToggleBot,
+    GameModeUpdate {
+        game_mode: GameMode,
+    },
+    ChallengeFiles(ChallengeFiles),
+    UpdatePlayerScore {
+        player_id: Uuid,
+        score: usize,
+    },
+    FinalScores(BTreeMap<Uuid, usize>),
+    ToggleReady,
+    PlayerReady {
+        player_id: Uuid,
+        ready: bool,
+    },
+    Whisper {
+        name: String,
+        message: String,
+    },
+    KickPlayer {
+        name: String,
+        reason: String,
+    },
+    PlayerKicked {
+        player_id: Uuid,
+        reason: String,
+    },
+    VoteKick {
+        name: String,
+    },
+    VoteKickProgress {
+        target_id: Uuid,
+        votes: usize,
+        needed: usize,
+    },
+    SetPlayerMuted {
+        name: String,
+        muted: bool,
+    },
+    PlayerMuted {
+        player_id: Uuid,
+        muted: bool,
+    },
+    RenamePlayer {
+        name: String,
+    },
+    PlayerRenamed {
+        player_id: Uuid,
+        new_name: String,
+    },
+    YouAreMuted,
+    MessageFiltered,
+    ReadyTimeout,
+    RoundRoster(Vec<PlayerRoundState>),
+    SetLobbyGoal {
+        challenge_id: String,
+    },
+    ChallengeChanged(ChallengeInformation),
+    SetWarmup {
+        challenge_id: Option<String>,
+    },
+    SetMaxPlayers {
+        max_players: usize,
+    },
+    SetTimeLimit {
+        seconds: Option<u64>,
+    },
+    SetMaxSpectators {
+        max_spectators: usize,
+    },
+    SetSpectatorsAllowed {
+        allowed: bool,
+    },
+    ToggleGoalPreview,
+    GoalPreviewChanged {
+        enabled: bool,
+    },
+    GoalPreview {
+        content: Option<Vec<u8>>,
+    },
+    RevealGoal {
+        content: Vec<u8>,
+    },
+    SetLobbyDescription {
+        description: Option<String>,
+    },
+    LobbyDescriptionChanged {
+        description: Option<String>,
+    },
+    SetLobbyTags {
+        tags: BTreeSet<LobbyTag>,
+    },
+    LobbyTagsChanged {
+        tags: BTreeSet<LobbyTag>,
+    },
+    SetComparison {
+        comparison: Option<Comparison>,
+    },
+    ComparisonChanged {
+        comparison: Option<Comparison>,
+    },
+    ToggleSolutionReveal,
+    SolutionRevealChanged {
+        enabled: bool,
+    },
+    WinningSolution {
+        player_id: Uuid,
+        content: Vec<u8>,
+    },
+    RoundSummary(RoundSummary),
+    WarmupChanged(Option<ChallengeInformation>),
+    RoundStarted {
+        warmup: bool,
+        round_number: usize,
+        comparison: Comparison,
+    },
+    RoundEndingSoon {
+        seconds: u64,
+    },
+    PauseRound,
+    ResumeRound,
+    RoundPaused,
+    RoundResumed,
+    Leave,
+    ChallengeRecord(Record),
+    NewRecord(Record),
+    SubmitReplay {
+        frames: Vec<ReplayFrame>,
+    },
+    RequestReplay {
+        name: String,
+    },
+    Replay {
+        frames: Vec<ReplayFrame>,
+    },
+    ExportSolution {
+        path: String,
+    },
+    WhoAmI,
+    SessionInfo(SessionInfo),
+    RequestRoster,
+    Roster(Vec<PlayerInfo>),
+    ListChallenges,
+    Challenges(Vec<ChallengeInformation>),
 }
 
 pub struct Lobby {
+    pub id: Uuid,
     pub name: String,
+    /// What the host describes the lobby with, if anything.
+    pub description: Option<String>,
+    /// Categories the host tagged the lobby with.
+    pub tags: BTreeSet<LobbyTag>,
     pub owner: Option<Uuid>,
     pub players: BTreeMap<Uuid, Player>,
     pub local_player: Option<Uuid>,
@@ -58,16 +250,72 @@ pub struct Lobby {
     pub ws_tx: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
     pub tx: UnboundedSender<LobbyMessage>,
     pub rx: UnboundedReceiver<LobbyMessage>,
+    pub app_tx: UnboundedSender<AppMessage>,
+    /// The configured editor used for both editor instances.
+    pub editor_config: EditorConfig,
+    /// How often the editor was restarted for not coming up in time since it
+    /// was last ready.
+    pub editor_launch_retries: usize,
     /// An instance of the users default editor with full interactivity.
     pub editor: Editor,
     /// An instance of the users default editor only capable of resizing.
     pub goal: Goal,
     pub app_size: Size,
+    pub challenge: ChallengeInformation,
     pub challenge_files: ChallengeFiles,
+    /// Whether the goal file is shown. Players race blind otherwise.
+    pub goal_preview: bool,
+    /// Whether everyone sees the winning solution after a race.
+    pub solution_reveal: bool,
+    /// The warmup challenge raced once before the scored rounds, if any.
+    pub warmup: Option<ChallengeInformation>,
+    /// Whether the current round is the warmup round.
+    pub is_warmup_round: bool,
+    /// How files are compared against the goal file in the current or last
+    /// round. Unknown before the first round started.
+    pub comparison: Option<Comparison>,
+    /// The number of the current or last scored round of the series. Zero
+    /// before the first round.
+    pub round_number: usize,
+    /// What every active player is doing in the current round.
+    pub round_roster: BTreeMap<Uuid, RoundState>,
+    /// Whether the full roster was requested and not received yet. Keeps a
+    /// burst of messages about unknown players from requesting it repeatedly.
+    pub roster_requested: bool,
+    /// When the current or last round started.
+    pub round_started_at: Option<Instant>,
+    /// When the lobby owner paused the current round, if it is paused.
+    pub round_paused_at: Option<Instant>,
     pub status: LobbyStatus,
+    pub game_mode: GameMode,
+    /// The fastest recorded time of the lobby challenge, if any.
+    pub record: Option<Record>,
+    /// Records the editor inputs of the local player during a round.
+    pub replay: ReplayRecorder,
+    /// Keystrokes per minute of the local player in the current round.
+    pub typing_speed: TypingSpeed,
+    /// The last replay received from the backend.
+    pub last_replay: Option<Vec<ReplayFrame>>,
+    /// The editor buffer the local player saved last. Exported in case the
+    /// session file of the editor is already gone.
+    pub last_buffer: Vec<u8>,
+    /// Diff of the final buffer against the goal of the last round. Shown in
+    /// place of the goal until the lobby resets.
+    pub solution_diff: Option<SolutionDiff>,
+    /// The race winner and the solution, shown next to the diff until the
+    /// lobby resets.
+    pub winning_solution: Option<(Uuid, Vec<u8>)>,
+    /// Results of the last round, shown as scoreboard until the lobby resets.
+    pub round_summary: Option<RoundSummary>,
     /// Whether to display the two editors horizontally or vertically next to
     /// each other.
     pub terminal_layout_direction: Direction,
+    /// The smoothed round-trip time to the backend.
+    pub latency: LatencyMeter,
+    /// How far the backend clock is ahead of the local one, once the backend
+    /// sent its time.
+    pub clock_offset: Option<TimeDelta>,
+    pub last_ping: Instant,
 }
 
 impl Lobby {
@@ -77,15 +325,23 @@ impl Lobby {
     pub async fn new(
         app_tx: UnboundedSender<AppMessage>,
         join_mode: JoinMode,
+        client_id: Uuid,
         app_size: Size,
+        editor_config: EditorConfig,
+        server_config: &ServerConfig,
     ) -> Result<Self> {
         // First, fetch lobby information of the lobby we want to join.
-        let url = format!("http://127.0.0.1:3030/lobbies/{}", join_mode);
-        let lobby_information = reqwest::get(url).await?.json::<LobbyInformation>().await?;
+        let client = server::http_client(server_config)?;
+        let path = format!("lobbies/{}", join_mode);
+        let lobby_information = server::get(&client, server_config, &path)
+            .await?
+            .error_for_status()?
+            .json::<LobbyInformation>()
+            .await?;
 
         // Connect to lobby with given join mode.
-        let url = format!("ws://127.0.0.1:3030/players/{}", lobby_information.id);
-        let (ws_stream, _) = connect_async(url).await?;
+        let path = format!("players/{}?client={client_id}", lobby_information.id);
+        let ws_stream = server::connect_ws(server_config, &path).await?;
 
         // Setup messaging channels.
         let (ws_tx, ws_rx) = ws_stream.split();
@@ -122,15 +378,29 @@ impl Lobby {
             }
         }
 
+        let mut chat = Chat::new(tx.clone());
+        match lobby_information.quickplay {
+            Some(QuickplayOutcome::Joined) => {
+                chat.add_message(format!("Joined lobby {}.", lobby_information.name))
+            }
+            Some(QuickplayOutcome::Created) => {
+                chat.add_message("No lobby had a free seat, created a new lobby.".to_string())
+            }
+            None => {}
+        }
+
         let mut editor = Editor::new(
+            &editor_config,
             app_size,
             tx.clone(),
             lobby_information.challenge_files.start_file.clone(),
             false,
+            true,
         )?;
         let terminal_layout_direction = Direction::Vertical;
         editor.resize(app_size.height, app_size.width, terminal_layout_direction)?;
         let mut goal = Goal::new(
+            &editor_config,
             app_size,
             tx.clone(),
             lobby_information.challenge_files.goal_file.clone(),
@@ -139,22 +409,51 @@ impl Lobby {
         goal.resize(app_size.height, app_size.width, terminal_layout_direction)?;
 
         Ok(Self {
+            id: lobby_information.id,
             name: lobby_information.name,
+            description: lobby_information.description,
+            tags: lobby_information.tags,
             owner: lobby_information.owner,
             players: lobby_information.players,
             local_player: None,
             encryptions,
             waiting_encryptions,
-            chat: Chat::new(tx.clone()),
+            chat,
             ws_tx,
             tx,
             rx,
+            app_tx,
+            editor_config,
+            editor_launch_retries: 0,
             editor,
             goal,
             app_size,
+            challenge: lobby_information.challenge,
+            goal_preview: lobby_information.goal_preview,
+            solution_reveal: lobby_information.solution_reveal,
+            warmup: lobby_information.warmup,
+            is_warmup_round: false,
+            comparison: None,
+            round_number: lobby_information.round_number,
+            round_roster: BTreeMap::new(),
+            roster_requested: false,
+            round_started_at: None,
+            round_paused_at: None,
             challenge_files: lobby_information.challenge_files,
             status: lobby_information.status,
+            game_mode: lobby_information.game_mode,
+            record: None,
+            replay: ReplayRecorder::default(),
+            typing_speed: TypingSpeed::default(),
+            last_replay: None,
+            last_buffer: Vec::new(),
+            solution_diff: None,
+            winning_solution: None,
+            round_summary: None,
             terminal_layout_direction,
+            latency: LatencyMeter::default(),
+            clock_offset: None,
+            last_ping: Instant::now(),
         })
     }
 
@@ -176,14 +475,11 @@ impl Lobby {
                     error!("New lobby owner with ID {} was not found!", id);
                 }
             }
-            LobbyMessage::CloseConnection => {
-                info!("Close connection to lobby.");
-                self.ws_tx.close().await?;
-            }
             LobbyMessage::PlayerJoined(player) => {
                 info!("Player {} joined the lobby.", player.name);
 
-                self.chat.add_message(format!("{} joined!", player.name));
+                self.chat
+                    .add_timestamped_message(format!("{} joined!", player.name), player.joined_at);
                 let encryption = Encryption {
                     action: EncryptionAction::Joined,
                     index: 0,
@@ -202,6 +498,7 @@ impl Lobby {
                     self.chat.add_message(format!("{} left!", player.name));
                 } else {
                     error!("Tried to remove a non-existent player with ID {}.", id);
+                    self.request_roster()?;
                 }
 
                 if let Some(encryption) = self
@@ -214,7 +511,7 @@ impl Lobby {
                 }
             }
             LobbyMessage::ReceiveMessage(msg) => {
-                self.chat.add_message(msg);
+                self.chat.add_timestamped_message(msg.text, msg.sent_at);
             }
             LobbyMessage::SendMessage { message } => {
                 self.ws_tx
@@ -233,23 +530,55 @@ impl Lobby {
                     local_player.value.push_str(" (you)");
                 }
             }
-            LobbyMessage::EditorTerminated => {
-                // Restart the editor if it terminates.
-                self.editor = Editor::new(
-                    self.app_size,
-                    self.tx.clone(),
-                    self.challenge_files.start_file.clone(),
-                    self.editor.is_full_screen,
-                )?;
-                self.editor.resize(
-                    self.app_size.height,
-                    self.app_size.width,
-                    self.terminal_layout_direction,
-                )?;
+            LobbyMessage::EditorTerminated { killed } => {
+                // Restart the editor if it terminates. A player quitting it by
+                // accident mid-round gets the start file of the round back
+                // right away, while the round clock keeps running.
+                if !killed && matches!(self.status, LobbyStatus::InProgress(_)) {
+                    info!("The editor quit during the round, relaunching it.");
+                    self.chat.add_message(
+                        "Your editor quit. It was relaunched with the start file, the round goes on."
+                            .to_string(),
+                    );
+                }
+                self.restart_editor()?;
+            }
+            LobbyMessage::EditorFailedToStart { reason } => {
+                // Do not restart the editor, it would most likely fail again.
+                self.chat.add_message(format!(
+                    "Couldn't launch editor {} ({reason}). Check the editor section of your config.",
+                    self.editor_config.command
+                ));
+            }
+            LobbyMessage::EditorLaunchTimedOut => {
+                let launch_timeout = self.editor_config.launch_timeout;
+                if self.editor_launch_retries < self.editor_config.launch_retries {
+                    self.editor_launch_retries += 1;
+                    info!(
+                        "Restart the editor that did not come up, retry {} of {}.",
+                        self.editor_launch_retries, self.editor_config.launch_retries
+                    );
+                    self.chat.add_message(format!(
+                        "The editor did not come up within {launch_timeout}s, restarting it."
+                    ));
+                    self.restart_editor()?;
+                } else {
+                    error!("The editor did not come up, giving up.");
+                    self.chat.add_message(format!(
+                        "Couldn't launch editor {} (no output within {launch_timeout}s). Check the editor section of your config.",
+                        self.editor_config.command
+                    ));
+                }
+            }
+            LobbyMessage::EditorReady => {
+                debug!("The editor is ready.");
+                self.editor_launch_retries = 0;
+                self.ws_tx.send(ClientMessage::EditorReady.into()).await?;
             }
             LobbyMessage::GoalTerminated => {
                 // Restart the goal editor if it terminates.
                 self.goal = Goal::new(
+                    &self.editor_config,
                     self.app_size,
                     self.tx.clone(),
                     self.challenge_files.goal_file.clone(),
@@ -261,13 +590,154 @@ impl Lobby {
                     self.terminal_layout_direction,
                 )?;
             }
+            LobbyMessage::SendPing => {
+                self.ws_tx.send(Message::Ping(ping_payload())).await?;
+            }
+            LobbyMessage::Latency(round_trip_time) => {
+                self.latency.record(round_trip_time);
+            }
+            LobbyMessage::ServerTime {
+                unix_millis,
+                received_at,
+            } => {
+                self.clock_offset = clock_offset(unix_millis, received_at, self.latency.average());
+            }
             LobbyMessage::RequestStart => {
                 self.ws_tx.send(ClientMessage::RequestStart.into()).await?;
             }
             LobbyMessage::StatusUpdate { status } => {
+                match status {
+                    LobbyStatus::InProgress(_) => {
+                        if !matches!(self.status, LobbyStatus::InProgress(_)) {
+                            self.replay.start();
+                            self.typing_speed.start();
+                        }
+                    }
+                    // Submit the inputs of the round once it is over.
+                    _ => {
+                        self.is_warmup_round = false;
+                        self.typing_speed.stop();
+                        if let Some(frames) = self.replay.stop() {
+                            self.tx.send(LobbyMessage::SubmitReplay { frames })?;
+                        }
+                    }
+                }
                 self.status = status;
             }
+            LobbyMessage::SubmitReplay { frames } => {
+                debug!("Submit replay with {} frames.", frames.len());
+                self.ws_tx
+                    .send(ClientMessage::SubmitReplay { frames }.into())
+                    .await?;
+            }
+            LobbyMessage::RequestReplay { name } => {
+                self.ws_tx
+                    .send(ClientMessage::RequestReplay { name }.into())
+                    .await?;
+            }
+            LobbyMessage::Replay { frames } => {
+                let duration = frames.last().map(|frame| frame.elapsed).unwrap_or_default();
+                self.chat.add_message(format!(
+                    "Received replay with {} inputs over {:.1}s.",
+                    frames.len(),
+                    duration.as_secs_f64()
+                ));
+                self.last_replay = Some(frames);
+            }
+            LobbyMessage::ExportSolution { path } => {
+                match fs::write(&path, self.solution_buffer()) {
+                    Ok(()) => {
+                        info!("Exported solution to {path}.");
+                        self.chat
+                            .add_message(format!("Exported your solution to {path}."));
+                    }
+                    Err(e) => {
+                        error!("Error exporting solution to {path}: {e}");
+                        self.chat
+                            .add_message(format!("Couldn't export your solution: {e}"));
+                    }
+                }
+            }
+            LobbyMessage::WhoAmI => {
+                self.ws_tx.send(ClientMessage::WhoAmI.into()).await?;
+            }
+            LobbyMessage::ListChallenges => {
+                self.ws_tx
+                    .send(ClientMessage::ListChallenges.into())
+                    .await?;
+            }
+            LobbyMessage::Challenges(challenges) => {
+                self.chat.add_message("Available challenges:".to_string());
+                for challenge in challenges {
+                    let description = challenge
+                        .description
+                        .map_or(String::new(), |description| format!(" - {description}"));
+                    self.chat.add_message(format!(
+                        "{}: {} ({}, {}){description}",
+                        challenge.id, challenge.name, challenge.language, challenge.difficulty
+                    ));
+                }
+            }
+            LobbyMessage::RequestRoster => {
+                debug!("Request the roster of lobby {}.", self.id);
+                self.ws_tx
+                    .send(ClientMessage::RequestRoster { lobby_id: self.id }.into())
+                    .await?;
+            }
+            LobbyMessage::Roster(roster) => {
+                info!("Resynchronized the roster with {} players.", roster.len());
+                self.roster_requested = false;
+                self.players = roster
+                    .iter()
+                    .map(|info| (info.player.id, info.player.clone()))
+                    .collect();
+                self.round_roster = roster
+                    .into_iter()
+                    .filter_map(|info| info.round_state.map(|state| (info.player.id, state)))
+                    .collect();
+                // Drop the animations of players we missed leaving.
+                self.encryptions
+                    .retain(|id, _| self.players.contains_key(id));
+                self.waiting_encryptions
+                    .retain(|id, _| self.players.contains_key(id));
+            }
+            LobbyMessage::SessionInfo(session_info) => {
+                debug!("Received session info {:?}.", session_info);
+                // Reconcile our view with the one of the backend.
+                if session_info.player_id.is_some() && session_info.player_id != self.local_player {
+                    warn!(
+                        "Local player ID {:?} differs from the backend, using {:?}.",
+                        self.local_player, session_info.player_id
+                    );
+                    self.local_player = session_info.player_id;
+                    self.request_roster()?;
+                }
+                if session_info.is_host && self.owner != self.local_player {
+                    warn!("The backend names us lobby owner, updating the owner.");
+                    self.owner = self.local_player;
+                }
+                let player_id = session_info
+                    .player_id
+                    .map_or("none".to_string(), |id| id.to_string());
+                let lobby_id = session_info
+                    .lobby_id
+                    .map_or("none".to_string(), |id| id.to_string());
+                self.chat.add_message(format!("Player ID: {player_id}"));
+                self.chat.add_message(format!(
+                    "Lobby ID: {lobby_id}{}",
+                    if session_info.lobby_id == Some(self.id) {
+                        ""
+                    } else {
+                        " (differs from the joined lobby)"
+                    }
+                ));
+                self.chat.add_message(format!(
+                    "Lobby owner: {}",
+                    if session_info.is_host { "yes" } else { "no" }
+                ));
+            }
             LobbyMessage::SendProgress { progress } => {
+                self.last_buffer = progress.clone();
                 self.ws_tx
                     .send(ClientMessage::Progress { progress }.into())
                     .await?;
@@ -283,6 +753,414 @@ impl Lobby {
                         "Tried to update progress of non-existent player with ID {}.",
                         player_id
                     );
+                    self.request_roster()?;
+                }
+            }
+            LobbyMessage::ToggleGameMode => {
+                let game_mode = self.game_mode.next();
+                self.ws_tx
+                    .send(ClientMessage::SetGameMode { game_mode }.into())
+                    .await?;
+            }
+            LobbyMessage::ToggleBot => {
+                let difficulty = self.next_bot_difficulty();
+                self.ws_tx
+                    .send(ClientMessage::SetBot { difficulty }.into())
+                    .await?;
+            }
+            LobbyMessage::GameModeUpdate { game_mode } => {
+                info!("Lobby game mode changed to {}.", game_mode);
+                self.game_mode = game_mode;
+                self.round_number = 0;
+                self.chat
+                    .add_message(format!("Game mode changed to {}.", game_mode));
+            }
+            LobbyMessage::ChallengeFiles(challenge_files) => {
+                // Kill both editors. Their termination handlers restart them
+                // with the new challenge files.
+                self.challenge_files = challenge_files;
+                if let Err(e) = self.clean_up() {
+                    error!("Error restarting editors with new challenge files: {e}");
+                }
+            }
+            LobbyMessage::UpdatePlayerScore { player_id, score } => {
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    player.score = score;
+                } else {
+                    error!(
+                        "Tried to update score of non-existent player with ID {}.",
+                        player_id
+                    );
+                    self.request_roster()?;
+                }
+            }
+            LobbyMessage::ToggleReady => {
+                self.ws_tx.send(ClientMessage::ToggleReady.into()).await?;
+            }
+            LobbyMessage::PlayerReady { player_id, ready } => {
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    player.ready = ready;
+                } else {
+                    error!(
+                        "Tried to update ready state of non-existent player with ID {}.",
+                        player_id
+                    );
+                    self.request_roster()?;
+                }
+            }
+            LobbyMessage::Whisper { name, message } => {
+                self.ws_tx
+                    .send(ClientMessage::Whisper { name, message }.into())
+                    .await?;
+            }
+            LobbyMessage::KickPlayer { name, reason } => {
+                self.ws_tx
+                    .send(ClientMessage::KickPlayer { name, reason }.into())
+                    .await?;
+            }
+            LobbyMessage::PlayerKicked { player_id, reason } => {
+                if let Some(player) = self.players.get(&player_id) {
+                    info!("Player {} was kicked: {reason}", player.name);
+                    self.chat
+                        .add_message(format!("{} was kicked: {reason}", player.name));
+                }
+            }
+            LobbyMessage::VoteKick { name } => {
+                let Some(target_id) = self
+                    .players
+                    .values()
+                    .find(|player| player.name.eq_ignore_ascii_case(&name))
+                    .map(|player| player.id)
+                else {
+                    self.chat
+                        .add_message(format!("Player {name} was not found."));
+                    return Ok(());
+                };
+                self.ws_tx
+                    .send(ClientMessage::VoteKick { target_id }.into())
+                    .await?;
+            }
+            LobbyMessage::VoteKickProgress {
+                target_id,
+                votes,
+                needed,
+            } => {
+                if let Some(player) = self.players.get(&target_id) {
+                    self.chat.add_message(format!(
+                        "Votes to kick {}: {votes}/{needed}. Type /votekick {} to agree.",
+                        player.name, player.name
+                    ));
+                }
+            }
+            LobbyMessage::SetPlayerMuted { name, muted } => {
+                let Some(target_id) = self
+                    .players
+                    .values()
+                    .find(|player| player.name.eq_ignore_ascii_case(&name))
+                    .map(|player| player.id)
+                else {
+                    self.chat
+                        .add_message(format!("Player {name} was not found."));
+                    return Ok(());
+                };
+                self.ws_tx
+                    .send(ClientMessage::SetPlayerMuted { target_id, muted }.into())
+                    .await?;
+            }
+            LobbyMessage::PlayerMuted { player_id, muted } => {
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    player.muted = muted;
+                } else {
+                    error!(
+                        "Tried to update muted state of non-existent player with ID {}.",
+                        player_id
+                    );
+                    self.request_roster()?;
+                }
+            }
+            LobbyMessage::RenamePlayer { name } => {
+                self.ws_tx
+                    .send(ClientMessage::RenamePlayer { name }.into())
+                    .await?;
+            }
+            LobbyMessage::PlayerRenamed {
+                player_id,
+                new_name,
+            } => {
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    player.name = new_name;
+                } else {
+                    error!("Tried to rename non-existent player with ID {}.", player_id);
+                    self.request_roster()?;
+                }
+            }
+            LobbyMessage::YouAreMuted => {
+                self.chat
+                    .add_message("You are muted by the lobby owner.".to_string());
+            }
+            LobbyMessage::RoundRoster(round_roster) => {
+                self.round_roster = round_roster
+                    .into_iter()
+                    .map(|PlayerRoundState { player_id, state }| (player_id, state))
+                    .collect();
+            }
+            LobbyMessage::ReadyTimeout => {
+                self.chat.add_message(
+                    "Not everyone got ready in time. Ready states were reset.".to_string(),
+                );
+            }
+            LobbyMessage::MessageFiltered => {
+                self.chat
+                    .add_message("Your message was caught by the chat filter.".to_string());
+            }
+            LobbyMessage::SetLobbyGoal { challenge_id } => {
+                self.ws_tx
+                    .send(ClientMessage::SetLobbyGoal { challenge_id }.into())
+                    .await?;
+            }
+            LobbyMessage::ChallengeChanged(challenge) => {
+                info!("Lobby challenge changed to {}.", challenge.name);
+                self.round_number = 0;
+                self.chat.add_message(format!(
+                    "Challenge changed to {} ({}, {}).",
+                    challenge.name, challenge.language, challenge.difficulty
+                ));
+                // The backend sends the record of the new challenge, if any.
+                self.record = None;
+                self.challenge = challenge;
+            }
+            LobbyMessage::SetWarmup { challenge_id } => {
+                self.ws_tx
+                    .send(ClientMessage::SetWarmup { challenge_id }.into())
+                    .await?;
+            }
+            LobbyMessage::SetMaxPlayers { max_players } => {
+                self.ws_tx
+                    .send(ClientMessage::SetMaxPlayers { max_players }.into())
+                    .await?;
+            }
+            LobbyMessage::SetTimeLimit { seconds } => {
+                self.ws_tx
+                    .send(ClientMessage::SetTimeLimit { seconds }.into())
+                    .await?;
+            }
+            LobbyMessage::SetMaxSpectators { max_spectators } => {
+                self.ws_tx
+                    .send(ClientMessage::SetMaxSpectators { max_spectators }.into())
+                    .await?;
+            }
+            LobbyMessage::SetSpectatorsAllowed { allowed } => {
+                self.ws_tx
+                    .send(ClientMessage::SetSpectatorsAllowed { allowed }.into())
+                    .await?;
+            }
+            LobbyMessage::ToggleGoalPreview => {
+                let enabled = !self.goal_preview;
+                self.ws_tx
+                    .send(ClientMessage::SetGoalPreview { enabled }.into())
+                    .await?;
+            }
+            LobbyMessage::GoalPreviewChanged { enabled } => {
+                info!(
+                    "Lobby goal preview turned {}.",
+                    if enabled { "on" } else { "off" }
+                );
+                self.goal_preview = enabled;
+                self.chat.add_message(if enabled {
+                    "The goal is shown again.".to_string()
+                } else {
+                    "The goal is hidden. Follow your progress to find it!".to_string()
+                });
+            }
+            LobbyMessage::GoalPreview { content } => match content {
+                // Only restart the goal editor if the goal actually changed.
+                Some(content) if content != self.challenge_files.goal_file => {
+                    self.challenge_files.goal_file = content;
+                    self.goal.terminal.child_killer.kill()?;
+                }
+                Some(_) => {}
+                None => self
+                    .chat
+                    .add_message("The goal is hidden this round.".to_string()),
+            },
+            LobbyMessage::RevealGoal { content } => {
+                let diff = SolutionDiff::new(&content, &self.solution_buffer());
+                if diff.is_perfect() {
+                    self.chat.add_message("Perfect solution!".to_string());
+                }
+                self.solution_diff = Some(diff);
+            }
+            LobbyMessage::SetLobbyDescription { description } => {
+                self.ws_tx
+                    .send(ClientMessage::SetLobbyDescription { description }.into())
+                    .await?;
+            }
+            LobbyMessage::LobbyDescriptionChanged { description } => {
+                info!("Lobby description changed to {:?}.", description);
+                self.chat.add_message(match description {
+                    Some(ref description) => {
+                        format!("The lobby is now described as: {description}")
+                    }
+                    None => "The lobby description was removed.".to_string(),
+                });
+                self.description = description;
+            }
+            LobbyMessage::SetLobbyTags { tags } => {
+                self.ws_tx
+                    .send(ClientMessage::SetLobbyTags { tags }.into())
+                    .await?;
+            }
+            LobbyMessage::LobbyTagsChanged { tags } => {
+                info!("Lobby tags changed to {:?}.", tags);
+                self.chat.add_message(if tags.is_empty() {
+                    "The lobby tags were removed.".to_string()
+                } else {
+                    format!("The lobby is now tagged as: {}", format_tags(&tags))
+                });
+                self.tags = tags;
+            }
+            LobbyMessage::SetComparison { comparison } => {
+                self.ws_tx
+                    .send(ClientMessage::SetComparison { comparison }.into())
+                    .await?;
+            }
+            LobbyMessage::ComparisonChanged { comparison } => {
+                info!("Lobby comparison changed to {:?}.", comparison);
+                self.chat.add_message(match comparison {
+                    Some(comparison) => {
+                        format!("Comparison changed to {comparison}, starting next round.")
+                    }
+                    None => "Comparison reset to the one of the challenge, starting next round."
+                        .to_string(),
+                });
+            }
+            LobbyMessage::ToggleSolutionReveal => {
+                let enabled = !self.solution_reveal;
+                self.ws_tx
+                    .send(ClientMessage::SetSolutionReveal { enabled }.into())
+                    .await?;
+            }
+            LobbyMessage::SolutionRevealChanged { enabled } => {
+                info!(
+                    "Lobby solution reveal turned {}.",
+                    if enabled { "on" } else { "off" }
+                );
+                self.solution_reveal = enabled;
+                self.chat.add_message(if enabled {
+                    "The winning solution is shown after each race.".to_string()
+                } else {
+                    "The winning solution stays hidden.".to_string()
+                });
+            }
+            LobbyMessage::WinningSolution { player_id, content } => {
+                self.winning_solution = Some((player_id, content));
+            }
+            LobbyMessage::RoundSummary(summary) => {
+                debug!(
+                    "Received summary of round {} with {} results.",
+                    summary.round_number,
+                    summary.results.len()
+                );
+                self.round_summary = Some(summary);
+            }
+            LobbyMessage::WarmupChanged(warmup) => {
+                let message = match warmup {
+                    Some(ref warmup) => {
+                        info!("Lobby warmup changed to {}.", warmup.name);
+                        format!(
+                            "Warmup changed to {}. It is raced once before the next round.",
+                            warmup.name
+                        )
+                    }
+                    None => {
+                        info!("Lobby warmup removed.");
+                        "Warmup removed.".to_string()
+                    }
+                };
+                self.chat.add_message(message);
+                self.warmup = warmup;
+            }
+            LobbyMessage::RoundStarted {
+                warmup,
+                round_number,
+                comparison,
+            } => {
+                self.is_warmup_round = warmup;
+                self.round_number = round_number;
+                self.comparison = Some(comparison);
+                self.round_started_at = Some(Instant::now());
+                self.round_paused_at = None;
+                self.solution_diff = None;
+                self.winning_solution = None;
+                self.round_summary = None;
+                if warmup {
+                    self.chat
+                        .add_message("Warmup round! Results are not recorded.".to_string());
+                } else {
+                    self.chat
+                        .add_message(format!("Round {round_number} started."));
+                }
+                self.chat
+                    .add_message(format!("Solutions are judged by {comparison} comparison."));
+            }
+            LobbyMessage::RoundEndingSoon { seconds } => {
+                self.chat
+                    .add_message(format!("Hurry up! The round ends in {seconds}s."));
+            }
+            LobbyMessage::PauseRound => {
+                self.ws_tx.send(ClientMessage::PauseRound.into()).await?;
+            }
+            LobbyMessage::ResumeRound => {
+                self.ws_tx.send(ClientMessage::ResumeRound.into()).await?;
+            }
+            LobbyMessage::RoundPaused => {
+                self.round_paused_at = Some(Instant::now());
+                self.chat
+                    .add_message("The lobby owner paused the round.".to_string());
+            }
+            LobbyMessage::RoundResumed => {
+                // Leave the pause out of the elapsed round time. The new round
+                // end follows as status update.
+                if let Some(paused_at) = self.round_paused_at.take() {
+                    self.round_started_at = self
+                        .round_started_at
+                        .map(|started_at| started_at + paused_at.elapsed());
+                }
+                self.chat.add_message("The round goes on!".to_string());
+            }
+            LobbyMessage::Leave => {
+                // Tell the backend explicitly, so that the seat is freed right
+                // away.
+                self.ws_tx.send(ClientMessage::LeaveLobby.into()).await?;
+                self.app_tx.send(AppMessage::DisconnectLobby)?;
+            }
+            LobbyMessage::ChallengeRecord(record) => {
+                debug!("Received record of challenge {}.", record.challenge_id);
+                self.record = Some(record);
+            }
+            LobbyMessage::NewRecord(record) => {
+                self.chat.add_message(format!(
+                    "New record! {} finished in {:.1}s.",
+                    record.holder_name,
+                    record.best_time.as_secs_f64()
+                ));
+                self.record = Some(record);
+            }
+            LobbyMessage::FinalScores(scores) => {
+                let mut scores = scores
+                    .into_iter()
+                    .filter_map(|(id, score)| {
+                        self.players.get_mut(&id).map(|player| {
+                            player.score = score;
+                            (player.name.clone(), score)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                scores.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+                self.chat.add_message("Final scores:".to_string());
+                for (i, (name, score)) in scores.into_iter().enumerate() {
+                    self.chat.add_message(format!("{}. {name}: {score}", i + 1));
                 }
             }
         }
@@ -297,8 +1175,12 @@ impl Lobby {
         while let Some(Ok(msg)) = ws_rx.next().await {
             debug!("Handle backend message {:?}.", msg);
 
-            if msg.is_close() {
-                return Ok(());
+            // Pongs answer the latency pings.
+            if let Message::Pong(ref payload) = msg {
+                if let Some(round_trip_time) = round_trip_time(payload) {
+                    message_tx.send(LobbyMessage::Latency(round_trip_time))?;
+                }
+                continue;
             }
             let backend_message: BackendMessage = msg.into();
             match backend_message {
@@ -308,8 +1190,21 @@ impl Lobby {
                 BackendMessage::AssignOwner { id } => {
                     message_tx.send(LobbyMessage::AssignOwner { id })?;
                 }
-                BackendMessage::CloseConnection => {
-                    message_tx.send(LobbyMessage::CloseConnection)?;
+                // Kicks, closed lobbies and shutdowns were announced before
+                // the connection closed. Other reasons send the player back.
+                BackendMessage::CloseConnection { reason } => {
+                    info!("Backend closed the lobby connection ({:?}).", reason);
+                    if let Some(
+                        reason @ (CloseReason::ProtocolError
+                        | CloseReason::IdleTimeout
+                        | CloseReason::Superseded),
+                    ) = reason
+                    {
+                        app_tx.send(AppMessage::ReturnToBrowser {
+                            reason: reason.description().to_string(),
+                        })?;
+                    }
+                    return Ok(());
                 }
                 BackendMessage::SendMessage(msg) => {
                     message_tx.send(LobbyMessage::ReceiveMessage(msg))?;
@@ -326,9 +1221,31 @@ impl Lobby {
                 BackendMessage::LobbyNotWaitingForPlayers => {
                     app_tx.send(AppMessage::DisconnectLobby)?;
                 }
+                BackendMessage::RoundStarting => {
+                    warn!("The backend rejected the join as the round is about to start.");
+                    app_tx.send(AppMessage::DisconnectLobby)?;
+                }
+                BackendMessage::RoundInProgress { spectating } => {
+                    warn!(
+                        "The backend rejected the join as a round is in progress (spectating: {spectating})."
+                    );
+                    app_tx.send(AppMessage::DisconnectLobby)?;
+                }
+                BackendMessage::AlreadyInLobby => {
+                    warn!("The backend rejected the join as the player is already in a lobby.");
+                    app_tx.send(AppMessage::DisconnectLobby)?;
+                }
+                BackendMessage::UnknownClient => {
+                    warn!("The backend rejected the join as the client is not connected.");
+                    app_tx.send(AppMessage::DisconnectLobby)?;
+                }
                 BackendMessage::ConnectionCounts { clients, players } => {
                     app_tx.send(AppMessage::ConnectionCounts { clients, players })?;
                 }
+                BackendMessage::ServerShutdown => {
+                    warn!("The backend service is shutting down.");
+                    app_tx.send(AppMessage::ServerShutdown)?;
+                }
                 BackendMessage::StatusUpdate { status } => {
                     let component_to_focus = match status {
                         LobbyStatus::WaitingForPlayers
@@ -350,6 +1267,142 @@ impl Lobby {
                         progress,
                     })?;
                 }
+                BackendMessage::GameModeUpdate { game_mode } => {
+                    message_tx.send(LobbyMessage::GameModeUpdate { game_mode })?;
+                }
+                BackendMessage::ProvideChallengeFiles(challenge_files) => {
+                    message_tx.send(LobbyMessage::ChallengeFiles(challenge_files))?;
+                }
+                BackendMessage::ChallengeChanged(challenge) => {
+                    message_tx.send(LobbyMessage::ChallengeChanged(challenge))?;
+                }
+                BackendMessage::GoalPreviewChanged { enabled } => {
+                    message_tx.send(LobbyMessage::GoalPreviewChanged { enabled })?;
+                }
+                BackendMessage::GoalPreview { content } => {
+                    message_tx.send(LobbyMessage::GoalPreview { content })?;
+                }
+                BackendMessage::RevealGoal { content } => {
+                    message_tx.send(LobbyMessage::RevealGoal { content })?;
+                }
+                BackendMessage::LobbyDescriptionChanged { description } => {
+                    message_tx.send(LobbyMessage::LobbyDescriptionChanged { description })?;
+                }
+                BackendMessage::LobbyTagsChanged { tags } => {
+                    message_tx.send(LobbyMessage::LobbyTagsChanged { tags })?;
+                }
+                BackendMessage::SolutionRevealChanged { enabled } => {
+                    message_tx.send(LobbyMessage::SolutionRevealChanged { enabled })?;
+                }
+                BackendMessage::WinningSolution { player_id, content } => {
+                    message_tx.send(LobbyMessage::WinningSolution { player_id, content })?;
+                }
+                BackendMessage::RoundSummary(summary) => {
+                    message_tx.send(LobbyMessage::RoundSummary(summary))?;
+                }
+                BackendMessage::WarmupChanged(warmup) => {
+                    message_tx.send(LobbyMessage::WarmupChanged(warmup))?;
+                }
+                BackendMessage::RoundStarted {
+                    warmup,
+                    round_number,
+                    comparison,
+                } => {
+                    message_tx.send(LobbyMessage::RoundStarted {
+                        warmup,
+                        round_number,
+                        comparison,
+                    })?;
+                }
+                BackendMessage::ComparisonChanged { comparison } => {
+                    message_tx.send(LobbyMessage::ComparisonChanged { comparison })?;
+                }
+                BackendMessage::RoundEndingSoon { seconds } => {
+                    message_tx.send(LobbyMessage::RoundEndingSoon { seconds })?;
+                }
+                BackendMessage::RoundPaused => {
+                    message_tx.send(LobbyMessage::RoundPaused)?;
+                }
+                BackendMessage::RoundResumed => {
+                    message_tx.send(LobbyMessage::RoundResumed)?;
+                }
+                BackendMessage::UpdatePlayerScore { player_id, score } => {
+                    message_tx.send(LobbyMessage::UpdatePlayerScore { player_id, score })?;
+                }
+                BackendMessage::FinalScores(scores) => {
+                    message_tx.send(LobbyMessage::FinalScores(scores))?;
+                }
+                BackendMessage::PlayerReady { player_id, ready } => {
+                    message_tx.send(LobbyMessage::PlayerReady { player_id, ready })?;
+                }
+                BackendMessage::PlayerMuted { player_id, muted } => {
+                    message_tx.send(LobbyMessage::PlayerMuted { player_id, muted })?;
+                }
+                BackendMessage::PlayerRenamed {
+                    player_id,
+                    new_name,
+                } => {
+                    message_tx.send(LobbyMessage::PlayerRenamed {
+                        player_id,
+                        new_name,
+                    })?;
+                }
+                BackendMessage::MessageFiltered => {
+                    message_tx.send(LobbyMessage::MessageFiltered)?;
+                }
+                BackendMessage::ReadyTimeout => {
+                    message_tx.send(LobbyMessage::ReadyTimeout)?;
+                }
+                BackendMessage::RoundRoster(round_roster) => {
+                    message_tx.send(LobbyMessage::RoundRoster(round_roster))?;
+                }
+                BackendMessage::YouAreMuted => {
+                    message_tx.send(LobbyMessage::YouAreMuted)?;
+                }
+                BackendMessage::Kicked { reason } => {
+                    app_tx.send(AppMessage::Kicked { reason })?;
+                }
+                BackendMessage::ServerTime { unix_millis } => {
+                    message_tx.send(LobbyMessage::ServerTime {
+                        unix_millis,
+                        received_at: Utc::now(),
+                    })?;
+                }
+                BackendMessage::ReturnToBrowser { reason } => {
+                    app_tx.send(AppMessage::ReturnToBrowser { reason })?;
+                }
+                BackendMessage::PlayerKicked { player_id, reason } => {
+                    message_tx.send(LobbyMessage::PlayerKicked { player_id, reason })?;
+                }
+                BackendMessage::VoteKickProgress {
+                    target_id,
+                    votes,
+                    needed,
+                } => {
+                    message_tx.send(LobbyMessage::VoteKickProgress {
+                        target_id,
+                        votes,
+                        needed,
+                    })?;
+                }
+                BackendMessage::ChallengeRecord(record) => {
+                    message_tx.send(LobbyMessage::ChallengeRecord(record))?;
+                }
+                BackendMessage::NewRecord(record) => {
+                    message_tx.send(LobbyMessage::NewRecord(record))?;
+                }
+                BackendMessage::Replay { frames } => {
+                    message_tx.send(LobbyMessage::Replay { frames })?;
+                }
+                BackendMessage::SessionInfo(session_info) => {
+                    message_tx.send(LobbyMessage::SessionInfo(session_info))?;
+                }
+                BackendMessage::Roster(roster) => {
+                    message_tx.send(LobbyMessage::Roster(roster))?;
+                }
+                BackendMessage::Challenges(challenges) => {
+                    message_tx.send(LobbyMessage::Challenges(challenges))?;
+                }
                 _ => {}
             }
         }
@@ -360,6 +1413,52 @@ impl Lobby {
         Ok(())
     }
 
+    /// # Restart editor
+    ///
+    /// Replaces the editor with a fresh instance opening the start file.
+    fn restart_editor(&mut self) -> Result<()> {
+        self.editor = Editor::new(
+            &self.editor_config,
+            self.app_size,
+            self.tx.clone(),
+            self.challenge_files.start_file.clone(),
+            self.editor.is_full_screen,
+            self.editor.status_bar,
+        )?;
+        self.editor.resize(
+            self.app_size.height,
+            self.app_size.width,
+            self.terminal_layout_direction,
+        )?;
+        Ok(())
+    }
+
+    /// # Request roster
+    ///
+    /// Asks the backend for the full roster after our view of the players
+    /// turned out to be inconsistent, unless a request is already pending.
+    fn request_roster(&mut self) -> Result<()> {
+        if !self.roster_requested {
+            self.roster_requested = true;
+            self.tx.send(LobbyMessage::RequestRoster)?;
+        }
+        Ok(())
+    }
+
+    /// # Next bot difficulty
+    ///
+    /// Cycles through the bot difficulties, starting without a bot and ending
+    /// with its removal.
+    pub fn next_bot_difficulty(&self) -> Option<Difficulty> {
+        let bot = self.players.values().find_map(|player| player.bot);
+        match bot {
+            None => Some(Difficulty::Easy),
+            Some(Difficulty::Easy) => Some(Difficulty::Medium),
+            Some(Difficulty::Medium) => Some(Difficulty::Hard),
+            Some(Difficulty::Hard) => None,
+        }
+    }
+
     pub fn resize(&mut self, rows: u16, cols: u16) -> Result<()> {
         self.app_size = Size::new(cols, rows);
         self.goal
@@ -369,7 +1468,36 @@ impl Lobby {
         Ok(())
     }
 
+    /// # Server now
+    ///
+    /// The current time of the backend as estimated from its clock offset.
+    /// Falls back to the local time until the backend sent its time.
+    pub fn server_now(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_offset.unwrap_or_default()
+    }
+
+    /// # Set output paused
+    ///
+    /// Pauses or resumes the output processing of both editors.
+    pub fn set_output_paused(&mut self, paused: bool) {
+        for terminal in [&mut self.editor.terminal, &mut self.goal.terminal] {
+            if paused {
+                terminal.pause();
+            } else {
+                terminal.resume();
+            }
+        }
+    }
+
     pub fn on_tick(&mut self) {
+        self.editor.terminal.process_output();
+        self.goal.terminal.process_output();
+
+        if self.last_ping.elapsed() >= LATENCY_PING_INTERVAL {
+            self.last_ping = Instant::now();
+            let _ = self.tx.send(LobbyMessage::SendPing);
+        }
+
         let mut encryptions_to_delete = vec![];
         for (id, encryption) in self
             .encryptions
@@ -396,6 +1524,30 @@ impl Lobby {
         }
     }
 
+    /// # Solution buffer
+    ///
+    /// Reads the editor buffer of the local player. Falls back to the last
+    /// saved buffer if the editor already removed its session file.
+    pub fn solution_buffer(&self) -> Vec<u8> {
+        self.editor.read_buffer().unwrap_or_else(|e| {
+            debug!("Using last saved buffer: {e}");
+            self.last_buffer.clone()
+        })
+    }
+
+    /// # Toggle status bar
+    ///
+    /// Shows or hides the status bar below the editor and resizes the editor
+    /// to the free space.
+    pub fn toggle_status_bar(&mut self) -> Result<()> {
+        self.editor.status_bar = !self.editor.status_bar;
+        self.editor.resize(
+            self.app_size.height,
+            self.app_size.width,
+            self.terminal_layout_direction,
+        )
+    }
+
     pub fn toggle_terminal_layout(&mut self) {
         if self.terminal_layout_direction == Direction::Vertical {
             self.terminal_layout_direction = Direction::Horizontal;
@@ -404,14 +1556,40 @@ impl Lobby {
         }
     }
 
+    /// # Shut down
+    ///
+    /// Kills both editors, removes the session files and tells the backend
+    /// that the player leaves. Used when the application quits. The local
+    /// clean up comes first, so that a dead connection cannot leak files.
+    pub async fn shut_down(&mut self) -> Result<()> {
+        if let Err(e) = self.goal.terminal.child_killer.kill() {
+            error!("Error killing goal viewer: {e}");
+        }
+        let editor = self.editor.shut_down().await;
+        if let Err(e) = self.ws_tx.send(ClientMessage::LeaveLobby.into()).await {
+            error!("Error leaving lobby: {e}");
+        }
+        if let Err(e) = self.ws_tx.close().await {
+            error!("Error closing lobby connection: {e}");
+        }
+        editor
+    }
+
     pub fn clean_up(&mut self) -> Result<()> {
-        self.goal.terminal.child_killer.kill()?;
-        self.editor.terminal.child_killer.kill()?;
-        Ok(())
+        let goal = self.goal.terminal.child_killer.kill();
+        // Name the process, so that a stuck editor can be killed manually.
+        let editor = self.editor.kill().map_err(|e| match self.editor.pid() {
+            Some(pid) => anyhow!("Error killing editor process {pid}: {e}"),
+            None => e,
+        });
+        self.editor.remove_files();
+        goal?;
+        editor
     }
 }
 
-// Make sure the terminal instances are killed whenever we drop a lobby.
+// Make sure the terminal instances are killed and their files removed whenever
+// we drop a lobby.
 impl Drop for Lobby {
     fn drop(&mut self) {
         if let Err(e) = self.clean_up() {
@@ -419,3 +1597,26 @@ impl Drop for Lobby {
         }
     }
 }
+
+/// # Format tags
+///
+/// Lists lobby tags separated by commas.
+pub fn format_tags(tags: &BTreeSet<LobbyTag>) -> String {
+    tags.iter()
+        .map(LobbyTag::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// # Lobby details
+///
+/// Combines the description and the tags of a lobby into a single line.
+/// Returns `None` if the lobby has neither.
+pub fn lobby_details(description: Option<&str>, tags: &BTreeSet<LobbyTag>) -> Option<String> {
+    let tags = (!tags.is_empty()).then(|| format!("[{}]", format_tags(tags)));
+    match (description, tags) {
+        (Some(description), Some(tags)) => Some(format!("{description} {tags}")),
+        (Some(description), None) => Some(description.to_string()),
+        (None, tags) => tags,
+    }
+}
@@ -2,52 +2,75 @@ use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use log::{debug, error, info};
+use rand::{thread_rng, Rng};
 use reqwest::{Client, StatusCode};
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::{app::AppMessage, constants::RECONNECT_INTERVAL};
+use crate::{
+    app::AppMessage,
+    config::ServerConfig,
+    constants::{RECONNECT_INTERVAL, RECONNECT_JITTER, RECONNECT_MAX_INTERVAL},
+    server,
+};
 
 pub struct Offline {
     /// HTTP client to check the service connection.
     pub client: Client,
+    pub server_config: ServerConfig,
     pub last_reconnect: Instant,
+    /// Delay between the last and the next reconnect attempt.
+    pub reconnect_delay: Duration,
+    /// Failed reconnect attempts in a row.
+    pub failed_reconnects: u32,
     pub dot_count: usize,
     pub last_dot: Instant,
     pub app_tx: UnboundedSender<AppMessage>,
 }
 
 impl Offline {
-    pub fn new(app_tx: UnboundedSender<AppMessage>) -> Self {
-        let client = reqwest::Client::new();
-        Self {
+    pub fn new(app_tx: UnboundedSender<AppMessage>, server_config: ServerConfig) -> Result<Self> {
+        let client = server::http_client(&server_config)?;
+        Ok(Self {
             client,
+            server_config,
             last_reconnect: Instant::now(),
+            reconnect_delay: reconnect_delay(0),
+            failed_reconnects: 0,
             dot_count: 0,
             last_dot: Instant::now(),
             app_tx,
-        }
+        })
     }
 
-    pub async fn try_reconnect(&self) -> Result<()> {
+    /// # Try reconnect
+    ///
+    /// Checks the health of the backend service. Returns whether it is back
+    /// online.
+    pub async fn try_reconnect(&self) -> Result<bool> {
         debug!("Try reconnect to backend service.");
 
-        let Ok(response) = self.client.get("http://127.0.0.1:3030/health").send().await else {
+        let Ok(response) = server::get(&self.client, &self.server_config, "health").await else {
             error!("Backend service unreachable.");
-            // TODO: Return an error here.
-            return Ok(());
+            return Ok(false);
         };
 
         if response.status() == StatusCode::OK {
             info!("Backend service appears to be back online!");
             self.app_tx.send(AppMessage::ServiceBackOnline)?;
+            return Ok(true);
         }
-        Ok(())
+        Ok(false)
     }
 
     pub async fn on_tick(&mut self) -> Result<()> {
-        // Try to reconnect every `RECONNECT_INTERVAL`.
-        if self.last_reconnect.elapsed() > RECONNECT_INTERVAL {
-            self.try_reconnect().await?;
+        // Try to reconnect with an exponential and jittered backoff.
+        if self.last_reconnect.elapsed() > self.reconnect_delay {
+            if self.try_reconnect().await? {
+                self.failed_reconnects = 0;
+            } else {
+                self.failed_reconnects = self.failed_reconnects.saturating_add(1);
+            }
+            self.reconnect_delay = reconnect_delay(self.failed_reconnects);
             self.last_reconnect = Instant::now();
         }
 
@@ -59,3 +82,17 @@ impl Offline {
         Ok(())
     }
 }
+
+/// # Reconnect delay
+///
+/// Doubles `RECONNECT_INTERVAL` for every failed attempt up to
+/// `RECONNECT_MAX_INTERVAL` and spreads the result randomly by
+/// `RECONNECT_JITTER`.
+fn reconnect_delay(failed_reconnects: u32) -> Duration {
+    let delay = RECONNECT_INTERVAL
+        .checked_mul(2u32.saturating_pow(failed_reconnects))
+        .unwrap_or(RECONNECT_MAX_INTERVAL)
+        .min(RECONNECT_MAX_INTERVAL);
+    let jitter = thread_rng().gen_range(-RECONNECT_JITTER..=RECONNECT_JITTER);
+    delay.mul_f64(1.0 + jitter)
+}
@@ -0,0 +1,63 @@
+use std::time::Instant;
+
+use common::{
+    constants::{MAX_REPLAY_BYTES, MAX_REPLAY_FRAMES},
+    ReplayFrame,
+};
+use log::{debug, warn};
+
+/// Records the inputs sent to the editor during a round, so that the round
+/// can be replayed afterwards.
+#[derive(Debug, Default)]
+pub struct ReplayRecorder {
+    /// The start of the current round. Inputs are only recorded while a round
+    /// is in progress.
+    started_at: Option<Instant>,
+    frames: Vec<ReplayFrame>,
+    bytes: usize,
+}
+
+impl ReplayRecorder {
+    /// # Start recording
+    ///
+    /// Discards any previous recording and starts a new one.
+    pub fn start(&mut self) {
+        debug!("Start recording editor inputs.");
+        self.started_at = Some(Instant::now());
+        self.frames.clear();
+        self.bytes = 0;
+    }
+
+    /// # Record input
+    ///
+    /// Stores the bytes sent to the editor together with the time passed since
+    /// the start of the round. Inputs exceeding the replay limits are dropped.
+    pub fn record(&mut self, bytes: &[u8]) {
+        let Some(started_at) = self.started_at else {
+            return;
+        };
+        if bytes.is_empty() {
+            return;
+        }
+        if self.frames.len() >= MAX_REPLAY_FRAMES || self.bytes + bytes.len() > MAX_REPLAY_BYTES {
+            warn!("Replay limit reached, dropping editor input.");
+            return;
+        }
+        self.bytes += bytes.len();
+        self.frames.push(ReplayFrame {
+            elapsed: started_at.elapsed(),
+            bytes: bytes.to_vec(),
+        });
+    }
+
+    /// # Stop recording
+    ///
+    /// Stops the current recording and returns its frames. Returns `None` if
+    /// nothing was recorded.
+    pub fn stop(&mut self) -> Option<Vec<ReplayFrame>> {
+        self.started_at.take()?;
+        self.bytes = 0;
+        let frames = std::mem::take(&mut self.frames);
+        (!frames.is_empty()).then_some(frames)
+    }
+}
@@ -1,63 +1,90 @@
 use std::{
     io::{BufWriter, Write},
     sync::{Arc, Mutex},
+    thread,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bytes::Bytes;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use log::debug;
+use log::{debug, error};
 use portable_pty::{
     Child, ChildKiller, CommandBuilder, MasterPty, NativePtySystem, PtySize, PtySystem,
 };
 use ratatui::layout::Size;
-use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::{
+    mpsc::{channel, unbounded_channel, Receiver, UnboundedSender},
+    oneshot,
+};
 use tui_term::vt100::Parser;
 
+use crate::constants::{END_OF_TRANSMISSION, PTY_OUTPUT_BACKLOG, PTY_OUTPUT_PER_TICK};
+
+/// Maximum size of a single output chunk read from the PTY.
+const OUTPUT_CHUNK_SIZE: usize = 8192;
+
 pub struct Terminal {
     pub sender: UnboundedSender<Bytes>,
     pub master_pty: Box<dyn MasterPty + Send>,
     pub parser: Arc<Mutex<Parser>>,
     pub child_killer: Box<dyn ChildKiller + Send>,
+    /// Output chunks of the PTY that were not processed by the parser yet.
+    output_rx: Receiver<Vec<u8>>,
+    /// Whether output processing is paused, e.g., while a modal is shown.
+    paused: bool,
 }
 
 impl Terminal {
+    /// # Create a new terminal
+    ///
+    /// Spawns the command inside a new PTY. If provided, `first_output_tx` is
+    /// notified as soon as the command produces its first output. Fails with
+    /// a hint on how to fix the environment if no PTY can be allocated.
     pub fn new(
         app_size: Size,
         cmd: CommandBuilder,
+        mut first_output_tx: Option<oneshot::Sender<()>>,
     ) -> Result<(Self, Box<dyn Child + Send + Sync>)> {
         let parser = Arc::new(Mutex::new(Parser::new(app_size.height, app_size.width, 0)));
         let pty_system = NativePtySystem::default();
 
         let size = PtySize::default();
-        let pair = pty_system.openpty(size)?;
+        // Containers without `/dev/pts` can't hand out PTYs. Tell the player
+        // about the environment instead of surfacing the raw error.
+        let pair = pty_system.openpty(size).map_err(|e| {
+            anyhow!(
+                "No PTY available ({e}). Make sure the environment provides pseudo \
+                 terminals, e.g., by mounting /dev/pts into the container."
+            )
+        })?;
 
         // Wait for the child to complete
         let child = pair.slave.spawn_command(cmd)?;
 
+        // Read the output of the child on a dedicated thread, as reading
+        // blocks. The output is handed over in chunks and processed on tick,
+        // see `process_output`. Once the backlog is full, reading pauses,
+        // which in turn blocks the child from flooding the PTY any further.
         let mut reader = pair.master.try_clone_reader()?;
-        let parser_clone = Arc::clone(&parser);
-        tokio::spawn(async move {
-            // Consume the output from the child
-            // Can't read the full buffer, since that would wait for EOF
-            let mut buf = [0u8; 8192];
-            let mut processed_buf = Vec::new();
+        let (output_tx, output_rx) = channel(PTY_OUTPUT_BACKLOG);
+        thread::spawn(move || {
+            // Can't read the full buffer, since that would wait for EOF.
+            let mut buf = [0u8; OUTPUT_CHUNK_SIZE];
             loop {
-                let size = reader
-                    .read(&mut buf)
-                    .expect("Unable to read from terminal reader.");
-                if size == 0 {
-                    break;
+                let size = match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(size) => size,
+                    Err(e) => {
+                        error!("Error reading from terminal: {e}");
+                        break;
+                    }
+                };
+                if let Some(first_output_tx) = first_output_tx.take() {
+                    let _ = first_output_tx.send(());
                 }
-                if size > 0 {
-                    processed_buf.extend_from_slice(&buf[..size]);
-                    parser_clone
-                        .lock()
-                        .expect("Unable to lock terminal parser.")
-                        .process(&processed_buf);
-
-                    // Clear the processed portion of the buffer
-                    processed_buf.clear();
+                // A closed channel means the terminal is gone.
+                if output_tx.blocking_send(buf[..size].to_vec()).is_err() {
+                    break;
                 }
             }
         });
@@ -80,16 +107,66 @@ impl Terminal {
             master_pty: pair.master,
             parser,
             child_killer: child.clone_killer(),
+            output_rx,
+            paused: false,
         };
 
         Ok((terminal, child))
     }
 
-    pub fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+    /// # Feed stdin
+    ///
+    /// Writes the content to stdin of the command and ends the input. The PTY
+    /// only treats Ctrl-D as end of input at the start of a line, so a second
+    /// one follows content without trailing newline.
+    pub fn feed_stdin(&self, content: &[u8]) -> Result<()> {
+        let mut input = content.to_vec();
+        if !content.is_empty() && !content.ends_with(b"\n") {
+            input.push(END_OF_TRANSMISSION);
+        }
+        input.push(END_OF_TRANSMISSION);
+        self.sender.send(Bytes::from(input))?;
+        Ok(())
+    }
+
+    /// # Process output
+    ///
+    /// Feeds pending output of the PTY into the parser. At most
+    /// `PTY_OUTPUT_PER_TICK` bytes, rounded up to the next chunk, are
+    /// processed per call, so that output bursts do not stall the UI. The
+    /// rest is processed on the next ticks in order. Returns the amount of
+    /// processed bytes, which is zero while paused.
+    pub fn process_output(&mut self) -> usize {
+        if self.paused {
+            return 0;
+        }
+        process_output_chunks(&mut self.output_rx, &self.parser)
+    }
+
+    /// # Pause output
+    ///
+    /// Stops feeding output into the parser. Incoming output stays buffered
+    /// in the backlog, and once that is full, the PTY holds back the rest, so
+    /// nothing is lost.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// # Resume output
+    ///
+    /// Continues processing the buffered output in order on the next ticks.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// # Handle key event
+    ///
+    /// Sends the key to the PTY and returns the bytes that were sent.
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> Result<Bytes> {
         let bytes = self.key_to_bytes(event);
-        self.sender.send(bytes)?;
+        self.sender.send(bytes.clone())?;
 
-        Ok(())
+        Ok(bytes)
     }
 
     pub fn key_to_bytes(&self, key: KeyEvent) -> Bytes {
@@ -139,3 +216,52 @@ impl Terminal {
         Ok(())
     }
 }
+
+/// # Process output chunks
+///
+/// Feeds pending output chunks into the parser until `PTY_OUTPUT_PER_TICK`
+/// bytes are processed or no output is left. Returns the amount of processed
+/// bytes.
+fn process_output_chunks(output_rx: &mut Receiver<Vec<u8>>, parser: &Mutex<Parser>) -> usize {
+    let mut processed = 0;
+    let mut parser_guard = None;
+    while processed < PTY_OUTPUT_PER_TICK {
+        let Ok(chunk) = output_rx.try_recv() else {
+            break;
+        };
+        // Only lock the parser once there is something to process.
+        let parser = parser_guard
+            .get_or_insert_with(|| parser.lock().expect("Unable to lock terminal parser."));
+        parser.process(&chunk);
+        processed += chunk.len();
+    }
+    processed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn processes_at_most_the_budget_per_tick() {
+        let parser = Mutex::new(Parser::new(24, 80, 0));
+        let (output_tx, mut output_rx) = channel(PTY_OUTPUT_BACKLOG);
+        // Fill up the backlog with a burst of output.
+        while output_tx.try_send(vec![b'y'; OUTPUT_CHUNK_SIZE]).is_ok() {}
+        let burst = PTY_OUTPUT_BACKLOG * OUTPUT_CHUNK_SIZE;
+
+        let processed = process_output_chunks(&mut output_rx, &parser);
+        assert!(processed >= PTY_OUTPUT_PER_TICK);
+        assert!(processed < PTY_OUTPUT_PER_TICK + OUTPUT_CHUNK_SIZE);
+
+        // The rest follows on the next ticks.
+        let mut total = processed;
+        while total < burst {
+            let processed = process_output_chunks(&mut output_rx, &parser);
+            assert!(processed > 0);
+            total += processed;
+        }
+        assert_eq!(total, burst);
+        assert_eq!(process_output_chunks(&mut output_rx, &parser), 0);
+    }
+}
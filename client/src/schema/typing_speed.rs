@@ -0,0 +1,71 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::constants::{TYPING_SPEED_REFRESH_INTERVAL, TYPING_SPEED_WINDOW};
+
+/// Counts the keystrokes of the local player sent to the editor during a
+/// round and derives a rolling keystrokes per minute figure.
+#[derive(Debug, Default)]
+pub struct TypingSpeed {
+    /// The start of the current round. Keystrokes are only counted while a
+    /// round is in progress.
+    started_at: Option<Instant>,
+    /// Keystrokes within the last `TYPING_SPEED_WINDOW`.
+    keystrokes: VecDeque<Instant>,
+    /// The displayed figure and when it was computed.
+    displayed: Option<(usize, Instant)>,
+}
+
+impl TypingSpeed {
+    /// # Start counting
+    ///
+    /// Forgets the keystrokes of any previous round and starts counting.
+    pub fn start(&mut self) {
+        self.started_at = Some(Instant::now());
+        self.keystrokes.clear();
+        self.displayed = None;
+    }
+
+    /// Stops counting until the next round starts.
+    pub fn stop(&mut self) {
+        self.started_at = None;
+    }
+
+    /// Counts a keystroke, unless no round is in progress.
+    pub fn record(&mut self) {
+        if self.started_at.is_some() {
+            self.keystrokes.push_back(Instant::now());
+        }
+    }
+
+    /// # Keystrokes per minute
+    ///
+    /// Returns the keystrokes per minute over the last `TYPING_SPEED_WINDOW`,
+    /// or over the round if it is shorter. The figure is recomputed at most
+    /// every `TYPING_SPEED_REFRESH_INTERVAL`, so that it stays readable.
+    pub fn per_minute(&mut self) -> Option<usize> {
+        let started_at = self.started_at?;
+        if let Some((per_minute, computed_at)) = self.displayed {
+            if computed_at.elapsed() < TYPING_SPEED_REFRESH_INTERVAL {
+                return Some(per_minute);
+            }
+        }
+        let now = Instant::now();
+        while self
+            .keystrokes
+            .front()
+            .is_some_and(|keystroke| now.duration_since(*keystroke) > TYPING_SPEED_WINDOW)
+        {
+            self.keystrokes.pop_front();
+        }
+        // Avoid huge figures for the first keystrokes of a round.
+        let window = now
+            .duration_since(started_at)
+            .clamp(Duration::from_secs(1), TYPING_SPEED_WINDOW);
+        let per_minute = (self.keystrokes.len() as f64 * 60.0 / window.as_secs_f64()) as usize;
+        self.displayed = Some((per_minute, now));
+        Some(per_minute)
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::{tls::TlsInfo, Client, Response};
+use ring::digest::{digest, SHA256};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream,
+};
+
+use crate::config::ServerConfig;
+
+/// # Build HTTP client
+///
+/// Builds the HTTP client used to talk to the backend. If a certificate is
+/// pinned, the pin replaces the usual certificate validation, so that servers
+/// with self-signed certificates can be used.
+pub fn http_client(server_config: &ServerConfig) -> Result<Client> {
+    let is_pinned = server_config.pinned_fingerprint()?.is_some();
+    let client = Client::builder()
+        .tls_info(is_pinned)
+        .danger_accept_invalid_certs(is_pinned)
+        .build()?;
+    Ok(client)
+}
+
+/// # Get
+///
+/// Sends a GET request to the given backend path and rejects the response if
+/// the certificate of the backend does not match the pinned one.
+pub async fn get(client: &Client, server_config: &ServerConfig, path: &str) -> Result<Response> {
+    let response = client.get(server_config.http_url(path)).send().await?;
+    if let Some(expected) = server_config.pinned_fingerprint()? {
+        let certificate = response
+            .extensions()
+            .get::<TlsInfo>()
+            .and_then(|tls_info| tls_info.peer_certificate());
+        verify_fingerprint(&expected, certificate)?;
+    }
+    Ok(response)
+}
+
+/// # Connect WebSocket
+///
+/// Opens a WebSocket connection to the given backend path and closes it
+/// again if the certificate of the backend does not match the pinned one.
+pub async fn connect_ws(
+    server_config: &ServerConfig,
+    path: &str,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    let url = server_config.ws_url(path);
+    let Some(expected) = server_config.pinned_fingerprint()? else {
+        let (ws_stream, _) = connect_async_tls_with_config(url, None, false, None).await?;
+        return Ok(ws_stream);
+    };
+
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()?;
+    let (mut ws_stream, _) =
+        connect_async_tls_with_config(url, None, false, Some(Connector::NativeTls(connector)))
+            .await?;
+
+    let certificate = match ws_stream.get_ref() {
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref().peer_certificate()?,
+        _ => None,
+    };
+    let certificate = certificate
+        .map(|certificate| certificate.to_der())
+        .transpose()?;
+    if let Err(e) = verify_fingerprint(&expected, certificate.as_deref()) {
+        let _ = ws_stream.close(None).await;
+        return Err(e);
+    }
+    Ok(ws_stream)
+}
+
+/// # Verify fingerprint
+///
+/// Compares the SHA-256 hash of the DER encoded certificate with the expected
+/// fingerprint.
+fn verify_fingerprint(expected: &[u8], certificate: Option<&[u8]>) -> Result<()> {
+    let Some(certificate) = certificate else {
+        return Err(anyhow!(
+            "The backend presented no certificate to check against the pinned fingerprint."
+        ));
+    };
+    let fingerprint = digest(&SHA256, certificate);
+    if fingerprint.as_ref().ne(expected) {
+        return Err(anyhow!(
+            "The backend certificate does not match the pinned fingerprint. Expected {}, got {}.",
+            to_hex(expected),
+            to_hex(fingerprint.as_ref())
+        ));
+    }
+    debug!("The backend certificate matches the pinned fingerprint.");
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
@@ -3,6 +3,8 @@ use play::{chat::draw_chat, editor::draw_editor, goal::draw_goal, join::draw_joi
 use rand::{thread_rng, Rng};
 use ratatui::{
     layout::{Constraint, Flex, Layout, Rect},
+    style::Style,
+    widgets::{Block, BorderType},
     Frame,
 };
 
@@ -12,6 +14,7 @@ use self::{
 };
 use crate::{
     app::App,
+    config::Config,
     constants::SYMBOLS,
     schema::{connection::Connection, focused_component::ComponentKind, tab::Tab},
 };
@@ -56,12 +59,12 @@ pub fn draw_application(f: &mut Frame, app: &mut App) {
     match app.current_tab {
         Tab::Home => draw_home_tab(f, app, chunks[1]),
         Tab::Play => draw_play_tab(f, app, chunks[1]),
-        Tab::Logs => draw_logs_tab(f, chunks[1]),
+        Tab::Logs => draw_logs_tab(f, &app.config, chunks[1]),
     };
 
     // If we are offline just draw the offline UI above everything else.
     if let Connection::Offline(ref offline) = app.connection {
-        draw_offline(f, offline);
+        draw_offline(f, &app.config, offline);
     }
 }
 
@@ -99,7 +102,9 @@ pub fn draw_full_screen(f: &mut Frame, app: &mut App) {
             | ComponentKind::Editor
             | ComponentKind::Goal
             | ComponentKind::ExitPopup => {}
-            ComponentKind::Lobbies => draw_join(f, &app.config, area, join, &app.focused_component),
+            ComponentKind::Lobbies | ComponentKind::LobbyFilter => {
+                draw_join(f, &app.config, area, join, &app.focused_component)
+            }
         },
         Connection::Lobby(ref mut lobby) => match focused_component.kind {
             ComponentKind::Chat => draw_chat(
@@ -112,16 +117,34 @@ pub fn draw_full_screen(f: &mut Frame, app: &mut App) {
             ComponentKind::Editor => {
                 draw_editor(f, area, &app.config, &lobby.editor, &app.focused_component)
             }
-            ComponentKind::Goal => {
-                draw_goal(f, area, &app.config, &lobby.goal, &app.focused_component)
-            }
+            ComponentKind::Goal => draw_goal(
+                f,
+                area,
+                &app.config,
+                &lobby.goal,
+                lobby.goal_preview,
+                &app.focused_component,
+            ),
             ComponentKind::ExitPopup => draw_exit(f, &app.config),
-            ComponentKind::Lobbies => {}
+            ComponentKind::Lobbies | ComponentKind::LobbyFilter => {}
         },
         Connection::Offline(_) => {}
     }
 }
 
+/// # Focus block
+///
+/// Marks the block of the focused component. The accessibility mode draws
+/// thick borders in addition to the focus color.
+pub fn focus_block<'a>(block: Block<'a>, config: &Config) -> Block<'a> {
+    let block = block.border_style(Style::default().fg(config.palette().focus));
+    if config.accessibility {
+        block.border_type(BorderType::Thick)
+    } else {
+        block
+    }
+}
+
 pub fn get_random_symbol() -> char {
     let mut rng = thread_rng();
     let idx = rng.gen_range(0..SYMBOLS.len());
@@ -1,5 +1,5 @@
 use ratatui::{
-    style::{Color, Style},
+    style::Style,
     widgets::{Block, Paragraph},
     Frame,
 };
@@ -11,14 +11,16 @@ use super::centered_rect;
 pub fn draw_exit(f: &mut Frame, config: &Config) {
     let popup = Block::bordered()
         .title("Exit?")
-        .border_style(Style::default().fg(Color::Black));
+        .border_style(Style::default().fg(config.palette().popup_text));
     let text = format!(
         "Confirm {}, Abort {}",
         config.key_bindings.popup.confirm, config.key_bindings.popup.abort
     );
     let area = centered_rect(f.area(), text.len() as u16, 1);
-    let paragraph = Paragraph::new(text)
-        .block(popup)
-        .style(Style::default().bg(Color::LightRed).fg(Color::Black));
+    let paragraph = Paragraph::new(text).block(popup).style(
+        Style::default()
+            .bg(config.palette().error)
+            .fg(config.palette().popup_text),
+    );
     f.render_widget(paragraph, area);
 }
@@ -1,21 +1,67 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
+    style::{Modifier, Style, Stylize},
     text::{self},
     widgets::{Block, Tabs},
     Frame,
 };
 use strum::IntoEnumIterator;
 
-use crate::{app::App, constants::APP_TITLE, schema::tab::Tab};
+use crate::{
+    app::App,
+    constants::{APP_TITLE, LATENCY_GOOD, LATENCY_OK},
+    schema::{
+        connection::{Connection, ConnectionState},
+        tab::Tab,
+    },
+};
 
 pub fn draw_header(f: &mut Frame, app: &App, area: Rect) {
-    // Display all tabs in the header. Highlight the current selection.
+    let mut block = Block::bordered().title(APP_TITLE);
+
+    // Show the state of the connection to the backend.
+    let theme = app.config.palette();
+    let color = match app.connection_state {
+        ConnectionState::Connected => theme.success,
+        ConnectionState::Connecting | ConnectionState::Reconnecting => theme.notice,
+        ConnectionState::Disconnected | ConnectionState::ShuttingDown => theme.error,
+    };
+    let state = text::Line::from(format!("● {}", app.connection_state)).fg(color);
+    block = block.title_top(state.right_aligned());
+
+    // Show the latency to the backend while playing.
+    if let Connection::Lobby(ref lobby) = app.connection {
+        if let Some(latency) = lobby.latency.average() {
+            let (color, rating) = if latency < LATENCY_GOOD {
+                (theme.latency_good, "good")
+            } else if latency < LATENCY_OK {
+                (theme.latency_ok, "ok")
+            } else {
+                (theme.latency_bad, "poor")
+            };
+            // Spell out the rating in the accessibility mode instead of only
+            // coloring the latency.
+            let text = if app.config.accessibility {
+                format!("{} ms ({rating})", latency.as_millis())
+            } else {
+                format!("{} ms", latency.as_millis())
+            };
+            let latency = text::Line::from(text).fg(color);
+            block = block.title_top(latency.right_aligned());
+        }
+    }
+
+    // Display all tabs in the header. Highlight the current selection, which
+    // is underlined as well in the accessibility mode.
+    let mut highlight_style = Style::default().fg(app.config.palette().highlight);
+    if app.config.accessibility {
+        highlight_style = highlight_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    }
     let tabs = Tab::iter()
         .map(|t| text::Line::from(t.to_string()))
         .collect::<Tabs>()
-        .block(Block::bordered().title(APP_TITLE))
-        .highlight_style(Style::default().fg(Color::Yellow))
+        .block(block)
+        .highlight_style(highlight_style)
         .select(app.current_tab.index());
     f.render_widget(tabs, area);
 }
@@ -1,19 +1,16 @@
-use ratatui::{
-    layout::Rect,
-    style::{Color, Style},
-    widgets::Block,
-    Frame,
-};
+use ratatui::{layout::Rect, style::Style, widgets::Block, Frame};
 use tui_logger::{TuiLoggerLevelOutput, TuiLoggerWidget};
 
-pub fn draw_logs_tab(f: &mut Frame, area: Rect) {
+use crate::config::Config;
+
+pub fn draw_logs_tab(f: &mut Frame, config: &Config, area: Rect) {
     let block = Block::bordered().title("Logger");
     let logger = TuiLoggerWidget::default()
         .block(block)
-        .style_error(Style::default().fg(Color::LightRed))
-        .style_debug(Style::default().fg(Color::DarkGray))
-        .style_warn(Style::default().fg(Color::LightYellow))
-        .style_info(Style::default().fg(Color::LightGreen))
+        .style_error(Style::default().fg(config.palette().error))
+        .style_debug(Style::default().fg(config.palette().muted))
+        .style_warn(Style::default().fg(config.palette().notice))
+        .style_info(Style::default().fg(config.palette().success))
         .output_separator(' ')
         .output_level(Some(TuiLoggerLevelOutput::Long))
         .output_target(true)
@@ -1,23 +1,24 @@
 use ratatui::{
-    style::{Color, Style},
+    style::Style,
     text::Line,
     widgets::{Block, Clear, Paragraph, Wrap},
     Frame,
 };
 
 use super::centered_rect;
-use crate::{constants::RECONNECT_INTERVAL, schema::offline::Offline};
+use crate::{config::Config, schema::offline::Offline};
 
-pub fn draw_offline(f: &mut Frame, offline: &Offline) {
+pub fn draw_offline(f: &mut Frame, config: &Config, offline: &Offline) {
     let popup = Block::bordered()
         .title("Service offline")
-        .border_style(Style::default().fg(Color::LightYellow));
+        .border_style(Style::default().fg(config.palette().notice));
     let text = "It appears we are offline. You can keep this window open. We will try to reconnect automatically.";
 
     // Calculate the amount of seconds that remain to start the reconnect.
     let since_last_reconnected = offline.last_reconnect.elapsed();
+    let reconnect_delay = offline.reconnect_delay;
     let reconnect_status =
-        if let Some(reconnecting_in) = RECONNECT_INTERVAL.checked_sub(since_last_reconnected) {
+        if let Some(reconnecting_in) = reconnect_delay.checked_sub(since_last_reconnected) {
             let millis = reconnecting_in.as_millis();
             let seconds_with_millis = millis as f64 / 1000.0;
             &format!(
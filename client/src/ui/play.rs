@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     widgets::{Block, Clear, Paragraph},
     Frame,
 };
@@ -9,20 +9,31 @@ use ratatui::{
 use common::LobbyStatus;
 
 use self::{
-    chat::draw_chat, editor::draw_editor, goal::draw_goal, join::draw_join, lobby::draw_lobby,
+    chat::draw_chat,
+    diff::draw_solution_diff,
+    editor::{draw_editor, draw_status_bar},
+    goal::draw_goal,
+    join::draw_join,
+    lobby::draw_lobby,
+    solution::draw_winning_solution,
+    summary::draw_round_summary,
 };
 use super::centered_rect;
 use crate::{
     app::App,
+    config::Config,
     constants::{EDITOR_HEIGHT, GOAL_HEIGHT, PLAY_SIDE_WIDTH, TERMINAL_WIDTH},
-    schema::connection::Connection,
+    schema::{connection::Connection, lobby::Lobby},
 };
 
 pub mod chat;
+mod diff;
 pub mod editor;
 pub mod goal;
 pub mod join;
 mod lobby;
+mod solution;
+mod summary;
 
 pub fn draw_play_tab(f: &mut Frame, app: &mut App, area: Rect) {
     match app.connection {
@@ -35,7 +46,13 @@ pub fn draw_play_tab(f: &mut Frame, app: &mut App, area: Rect) {
             let vertical =
                 Layout::vertical([Constraint::Min(0), Constraint::Min(0)]).split(horizontal[0]);
 
-            draw_lobby(f, vertical[0], &app.config, lobby);
+            // Show the scoreboard of the last round until the lobby resets.
+            match (&lobby.status, &lobby.round_summary) {
+                (LobbyStatus::Finish(reset_date), Some(summary)) => {
+                    draw_round_summary(f, vertical[0], &app.config, lobby, summary, *reset_date)
+                }
+                _ => draw_lobby(f, vertical[0], &app.config, lobby),
+            }
             draw_chat(
                 f,
                 vertical[1],
@@ -54,23 +71,57 @@ pub fn draw_play_tab(f: &mut Frame, app: &mut App, area: Rect) {
             )
             .split(horizontal[1]);
 
+            // Reserve the last row of the editor area for the status bar.
+            let editor_area = if lobby.editor.status_bar {
+                let [editor_area, status_area] =
+                    Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(layout[0]);
+                draw_status_bar(f, status_area, &app.config, lobby);
+                editor_area
+            } else {
+                layout[0]
+            };
             draw_editor(
                 f,
-                layout[0],
+                editor_area,
                 &app.config,
                 &lobby.editor,
                 &app.focused_component,
             );
-            draw_goal(
-                f,
-                layout[1],
-                &app.config,
-                &lobby.goal,
-                &app.focused_component,
-            );
+            // Compare the solution against the goal once the round is over.
+            match (&lobby.status, &lobby.solution_diff) {
+                (LobbyStatus::Finish(_), Some(diff)) => match lobby.winning_solution {
+                    // Show the solution of the winner next to the own one,
+                    // unless the local player won.
+                    Some((winner_id, ref content)) if lobby.local_player != Some(winner_id) => {
+                        let [diff_area, solution_area] = Layout::horizontal([
+                            Constraint::Percentage(50),
+                            Constraint::Percentage(50),
+                        ])
+                        .areas(layout[1]);
+                        draw_solution_diff(f, diff_area, &app.config, diff);
+                        let winner = lobby
+                            .players
+                            .get(&winner_id)
+                            .map_or("a former player", |player| player.name.as_str());
+                        draw_winning_solution(f, solution_area, winner, content);
+                    }
+                    _ => draw_solution_diff(f, layout[1], &app.config, diff),
+                },
+                _ => draw_goal(
+                    f,
+                    layout[1],
+                    &app.config,
+                    &lobby.goal,
+                    lobby.goal_preview,
+                    &app.focused_component,
+                ),
+            }
 
             if let LobbyStatus::AboutToStart(start_date) = lobby.status {
-                draw_start_timer(f, area, start_date);
+                draw_start_timer(f, &app.config, area, lobby, start_date);
+            }
+            if lobby.round_paused_at.is_some() {
+                draw_pause_banner(f, &app.config, horizontal[1]);
             }
         }
         // If we are not connected to a lobby, draw the join form.
@@ -81,12 +132,31 @@ pub fn draw_play_tab(f: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
-fn draw_start_timer(f: &mut Frame, area: Rect, start_date: DateTime<Utc>) {
+fn draw_pause_banner(f: &mut Frame, config: &Config, area: Rect) {
+    let popup = Block::bordered()
+        .title("Paused")
+        .border_style(Style::default().fg(config.palette().notice));
+    let text = "The lobby owner paused the round.";
+
+    let area = centered_rect(area, text.len() as u16, 1);
+    let paragraph = Paragraph::new(text).block(popup);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn draw_start_timer(
+    f: &mut Frame,
+    config: &Config,
+    area: Rect,
+    lobby: &Lobby,
+    start_date: DateTime<Utc>,
+) {
     let popup = Block::bordered()
         .title("Get ready")
-        .border_style(Style::default().fg(Color::LightYellow));
-    let now = Utc::now();
-    let remaining_millis = start_date.signed_duration_since(now).num_milliseconds();
+        .border_style(Style::default().fg(config.palette().notice));
+    let remaining_millis = start_date
+        .signed_duration_since(lobby.server_now())
+        .num_milliseconds();
     let seconds_with_millis = remaining_millis as f64 / 1000.0;
     let text = format!("Game is starting in {:.1}s.", seconds_with_millis);
 
@@ -1,6 +1,7 @@
+use chrono::Local;
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Text,
     widgets::{block::Title, Block, Cell, Paragraph, Row, Table, Wrap},
     Frame,
@@ -12,6 +13,7 @@ use crate::{
         chat::Chat,
         focused_component::{ComponentKind, FocusedComponent},
     },
+    ui::focus_block,
 };
 
 pub fn draw_chat(
@@ -38,7 +40,7 @@ pub fn draw_chat(
         .as_ref()
         .is_some_and(|component| component.kind.eq(&ComponentKind::Chat))
     {
-        input_block = input_block.border_style(Style::default().fg(Color::Green));
+        input_block = focus_block(input_block, config);
         input_text.push('|');
     }
 
@@ -58,14 +60,22 @@ pub fn draw_chat(
     let messages: Vec<Row> = chat
         .messages
         .iter()
-        .map(|msg| {
-            let (formatted_text, height) = insert_newlines(msg, chat_width as usize);
+        .map(|line| {
+            let text = match line.sent_at {
+                Some(sent_at) => format!(
+                    "[{}] {}",
+                    sent_at.with_timezone(&Local).format("%H:%M:%S"),
+                    line.text
+                ),
+                None => line.text.clone(),
+            };
+            let (formatted_text, height) = insert_newlines(&text, chat_width as usize);
             Row::new([Cell::from(Text::from(formatted_text))]).height(height)
         })
         .collect();
     let selected_style = Style::default()
         .add_modifier(Modifier::REVERSED)
-        .fg(Color::DarkGray);
+        .fg(config.palette().selection);
     let table = Table::new(messages, [Constraint::Min(0)])
         .block(block)
         .highlight_style(selected_style);
@@ -0,0 +1,88 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::{
+    config::Config,
+    constants::DIFF_CONTEXT_LINES,
+    schema::diff::{DiffLine, SolutionDiff},
+};
+
+/// # Draw solution diff
+///
+/// Shows the diff of the final buffer against the goal after a round. Long
+/// runs of unchanged lines are collapsed, keeping `DIFF_CONTEXT_LINES` around
+/// each difference.
+pub fn draw_solution_diff(f: &mut Frame, area: Rect, config: &Config, diff: &SolutionDiff) {
+    let palette = config.palette();
+    let block = Block::bordered().title("Your solution vs. goal");
+
+    if diff.is_perfect() {
+        let paragraph = Paragraph::new("Perfect solution!")
+            .style(
+                Style::default()
+                    .fg(palette.success)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .alignment(Alignment::Center)
+            .block(block);
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let (added, removed, changed) = diff.count_changes();
+    let block =
+        block.title_top(Line::from(format!("+{added} -{removed} ~{changed}")).right_aligned());
+
+    // Mark the unchanged lines close enough to a difference to be shown.
+    let mut shown = vec![false; diff.lines.len()];
+    for (i, line) in diff.lines.iter().enumerate() {
+        if !matches!(line, DiffLine::Unchanged(_)) {
+            let start = i.saturating_sub(DIFF_CONTEXT_LINES);
+            let end = (i + DIFF_CONTEXT_LINES + 1).min(diff.lines.len());
+            shown[start..end].fill(true);
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut collapsed = 0;
+    for (line, shown) in diff.lines.iter().zip(shown) {
+        if !shown {
+            collapsed += 1;
+            continue;
+        }
+        if collapsed > 0 {
+            lines.push(collapsed_line(collapsed, config));
+            collapsed = 0;
+        }
+        match line {
+            DiffLine::Unchanged(line) => lines.push(Line::from(format!("  {line}"))),
+            DiffLine::Added(line) => lines
+                .push(Line::from(format!("+ {line}")).style(Style::default().fg(palette.success))),
+            DiffLine::Removed(line) => lines
+                .push(Line::from(format!("- {line}")).style(Style::default().fg(palette.error))),
+            DiffLine::Changed { goal, solution } => {
+                lines.push(
+                    Line::from(format!("~ {solution}")).style(Style::default().fg(palette.notice)),
+                );
+                lines.push(
+                    Line::from(format!("  goal: {goal}")).style(Style::default().fg(palette.muted)),
+                );
+            }
+        }
+    }
+    if collapsed > 0 {
+        lines.push(collapsed_line(collapsed, config));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn collapsed_line(count: usize, config: &Config) -> Line<'static> {
+    Line::from(format!("  ... {count} unchanged line(s)"))
+        .style(Style::default().fg(config.palette().muted))
+}
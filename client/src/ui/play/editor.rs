@@ -1,17 +1,23 @@
+use std::time::Instant;
+
 use ratatui::{
     layout::{Alignment, Rect},
-    style::{Color, Style},
-    widgets::{block::Title, Block},
+    style::Stylize,
+    widgets::{block::Title, Block, Paragraph},
     Frame,
 };
 use tui_term::widget::PseudoTerminal;
 
+use common::LobbyStatus;
+
 use crate::{
     config::Config,
     schema::{
         editor::Editor,
         focused_component::{ComponentKind, FocusedComponent},
+        lobby::Lobby,
     },
+    ui::focus_block,
 };
 
 pub fn draw_editor(
@@ -30,7 +36,7 @@ pub fn draw_editor(
         .as_ref()
         .is_some_and(|component| component.kind.eq(&ComponentKind::Editor))
     {
-        block = block.border_style(Style::default().fg(Color::Green));
+        block = focus_block(block, config);
     }
     let parser = editor
         .terminal
@@ -40,3 +46,41 @@ pub fn draw_editor(
     let terminal = PseudoTerminal::new(parser.screen()).block(block);
     f.render_widget(terminal, area);
 }
+
+/// # Draw status bar
+///
+/// Draws a single row below the editor with the challenge, the elapsed round
+/// time, the progress and the typing speed of the local player, so that
+/// players keep track of the match while editing.
+pub fn draw_status_bar(f: &mut Frame, area: Rect, config: &Config, lobby: &mut Lobby) {
+    let mut parts = vec![format!(
+        "{} ({})",
+        lobby.challenge.name, lobby.challenge.difficulty
+    )];
+    match (&lobby.status, lobby.round_started_at) {
+        (LobbyStatus::InProgress(_), Some(started_at)) => {
+            let elapsed = lobby
+                .round_paused_at
+                .unwrap_or_else(Instant::now)
+                .duration_since(started_at)
+                .as_secs();
+            parts.push(format!("{:02}:{:02}", elapsed / 60, elapsed % 60));
+        }
+        (status, _) => parts.push(status.to_string()),
+    }
+    parts.push(format!("{} players", lobby.players.len()));
+    if let Some(player) = lobby
+        .local_player
+        .and_then(|player_id| lobby.players.get(&player_id))
+    {
+        parts.push(format!("{:.0}% done", player.progress * 100.0));
+    }
+    if let Some(comparison) = lobby.comparison {
+        parts.push(comparison.to_string());
+    }
+    if let Some(per_minute) = lobby.typing_speed.per_minute() {
+        parts.push(format!("{per_minute} KPM"));
+    }
+    let status_bar = Paragraph::new(parts.join(" | ")).fg(config.palette().muted);
+    f.render_widget(status_bar, area);
+}
@@ -1,6 +1,5 @@
 use ratatui::{
     layout::{Alignment, Rect},
-    style::{Color, Style},
     widgets::{block::Title, Block},
     Frame,
 };
@@ -12,6 +11,7 @@ use crate::{
         focused_component::{ComponentKind, FocusedComponent},
         goal::Goal,
     },
+    ui::focus_block,
 };
 
 pub fn draw_goal(
@@ -19,18 +19,25 @@ pub fn draw_goal(
     area: Rect,
     config: &Config,
     goal: &Goal,
+    goal_preview: bool,
     focused_component: &Option<FocusedComponent>,
 ) {
     let focus_goal_key = format!("{}", config.key_bindings.lobby.focus_goal);
+    // Players race blind without the goal preview.
+    let title = if goal_preview {
+        "Editor"
+    } else {
+        "Editor (goal hidden)"
+    };
     let mut block = Block::bordered()
-        .title("Editor")
+        .title(title)
         .title(Title::from(focus_goal_key).alignment(Alignment::Right));
 
     if focused_component
         .as_ref()
         .is_some_and(|component| component.kind.eq(&ComponentKind::Goal))
     {
-        block = block.border_style(Style::default().fg(Color::Green));
+        block = focus_block(block, config);
     }
     let parser = goal
         .terminal
@@ -1,17 +1,25 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Margin, Rect},
-    style::{Color, Modifier, Style, Stylize},
-    widgets::{block::Title, Block, Cell, Row, Scrollbar, ScrollbarOrientation, Table},
+    layout::{Alignment, Constraint, Layout, Margin, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{
+        block::Title, Block, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, Table,
+        TableState,
+    },
     Frame,
 };
 
+use common::ChallengeInformation;
+
 use crate::{
     config::Config,
+    constants::UNAVAILABLE_SYMBOL,
     schema::{
         focused_component::{ComponentKind, FocusedComponent},
         join::Join,
+        lobby::lobby_details,
     },
-    ui::get_random_symbol,
+    ui::{focus_block, get_random_symbol},
 };
 
 pub fn draw_join(
@@ -21,16 +29,47 @@ pub fn draw_join(
     join: &mut Join,
     focused_component: &Option<FocusedComponent>,
 ) {
+    let [filter_area, area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+    draw_filter(f, config, filter_area, join, focused_component);
+    if let Some(ref challenges) = join.challenges {
+        draw_challenges(f, config, area, challenges, &mut join.challenge_state);
+        return;
+    }
+
     let focus_lobby_key = format!("{}", config.key_bindings.join.focus_lobby_list);
+    let title = if join.filter.joinable_only {
+        format!(
+            "Joinable lobbies ({} to show all, {} for challenges)",
+            config.key_bindings.join.toggle_joinable, config.key_bindings.join.toggle_challenges
+        )
+    } else {
+        format!(
+            "All lobbies ({} to show joinable, {} for challenges)",
+            config.key_bindings.join.toggle_joinable, config.key_bindings.join.toggle_challenges
+        )
+    };
     let mut block = Block::bordered()
-        .title("Lobbies")
+        .title(title)
         .title(Title::from(focus_lobby_key).alignment(Alignment::Right));
 
     if focused_component
         .as_ref()
         .is_some_and(|component| component.kind.eq(&ComponentKind::Lobbies))
     {
-        block = block.border_style(Style::default().fg(Color::Green));
+        block = focus_block(block, config);
+    }
+    // Notices take precedence over the description and tags of the selected
+    // lobby.
+    let selected_lobby = join
+        .selected_lobby
+        .and_then(|lobby_id| join.lobby_list.get(&lobby_id));
+    if let Some(ref notice) = join.notice {
+        block = block.title_bottom(Line::from(notice.as_str()).fg(config.palette().notice));
+    } else if let Some(details) =
+        selected_lobby.and_then(|lobby| lobby_details(lobby.description.as_deref(), &lobby.tags))
+    {
+        block = block.title_bottom(Line::from(details).fg(config.palette().muted));
     }
 
     let rows = join
@@ -38,7 +77,7 @@ pub fn draw_join(
         .iter()
         .zip(join.encrypted_player_counts.values())
         .zip(join.encrypted_status.values())
-        .map(|(((_, name), player_count), status)| {
+        .map(|(((id, name), player_count), status)| {
             let encrypted_name = name
                 .value
                 .chars()
@@ -75,12 +114,32 @@ pub fn draw_join(
                     }
                 })
                 .collect::<String>();
+            let lobby = join.lobby_list.get(id);
+            let is_joinable = lobby.is_none_or(|lobby| lobby.is_joinable());
+            // Tell players upfront that they can not watch this lobby.
+            let encrypted_status = if lobby.is_some_and(|lobby| !lobby.spectators_allowed) {
+                format!("{encrypted_status} (no spectators)")
+            } else {
+                encrypted_status
+            };
+            // Mark lobbies players can not join right now with a symbol as
+            // well in the accessibility mode.
+            let encrypted_name = if config.accessibility && !is_joinable {
+                format!("{UNAVAILABLE_SYMBOL} {encrypted_name}")
+            } else {
+                encrypted_name
+            };
             let row = Row::new(vec![
                 Cell::from(encrypted_name),
                 Cell::from(encrypted_player_count),
                 Cell::from(encrypted_status),
             ]);
-            row
+            // Gray out lobbies players can not join right now.
+            if !is_joinable {
+                row.fg(config.palette().muted)
+            } else {
+                row
+            }
         });
     // Columns widths are constrained in the same way as Layout...
     let widths = [
@@ -90,7 +149,7 @@ pub fn draw_join(
     ];
     let selected_style = Style::default()
         .add_modifier(Modifier::REVERSED)
-        .fg(Color::DarkGray);
+        .fg(config.palette().selection);
     let table = Table::new(rows, widths)
         .column_spacing(1)
         .header(
@@ -114,3 +173,77 @@ pub fn draw_join(
         &mut join.scroll_state,
     );
 }
+
+/// # Draw challenges
+///
+/// Lists the challenge catalog of the backend in place of the lobby list.
+fn draw_challenges(
+    f: &mut Frame,
+    config: &Config,
+    area: Rect,
+    challenges: &[ChallengeInformation],
+    state: &mut TableState,
+) {
+    let block = Block::bordered().title(format!(
+        "Challenges ({} to show lobbies)",
+        config.key_bindings.join.toggle_challenges
+    ));
+    let rows = challenges.iter().map(|challenge| {
+        Row::new(vec![
+            Cell::from(challenge.id.as_str()),
+            Cell::from(challenge.name.as_str()),
+            Cell::from(challenge.language.as_str()),
+            Cell::from(challenge.difficulty.to_string()),
+            Cell::from(challenge.description.as_deref().unwrap_or_default()),
+        ])
+    });
+    let widths = [
+        Constraint::Percentage(15),
+        Constraint::Percentage(20),
+        Constraint::Percentage(15),
+        Constraint::Percentage(10),
+        Constraint::Percentage(40),
+    ];
+    let selected_style = Style::default()
+        .add_modifier(Modifier::REVERSED)
+        .fg(config.palette().selection);
+    let table = Table::new(rows, widths)
+        .column_spacing(1)
+        .header(
+            Row::new(vec!["ID", "Name", "Language", "Difficulty", "Description"])
+                .style(Style::new().bold())
+                .bottom_margin(1),
+        )
+        .block(block)
+        .row_highlight_style(selected_style);
+    f.render_stateful_widget(table, area, state);
+}
+
+fn draw_filter(
+    f: &mut Frame,
+    config: &Config,
+    area: Rect,
+    join: &Join,
+    focused_component: &Option<FocusedComponent>,
+) {
+    let focus_filter_key = format!("{}", config.key_bindings.join.focus_lobby_filter);
+    let mut block = Block::bordered()
+        .title("Filter")
+        .title_top(Line::from(focus_filter_key).right_aligned());
+
+    let is_focused = focused_component
+        .as_ref()
+        .is_some_and(|component| component.kind.eq(&ComponentKind::LobbyFilter));
+    if is_focused {
+        block = focus_block(block, config);
+    }
+
+    // Show the filter syntax as long as there is no input.
+    let paragraph = if join.filter_input.is_empty() && !is_focused {
+        Paragraph::new("<name> lang:<language> diff:<easy|medium|hard> tag:<tag>")
+            .fg(config.palette().muted)
+    } else {
+        Paragraph::new(join.filter_input.as_str())
+    };
+    f.render_widget(paragraph.block(block), area);
+}
@@ -1,14 +1,21 @@
-use chrono::Utc;
+use std::time::Duration;
+
 use ratatui::{
     layout::{Constraint, Layout, Margin, Rect},
+    style::Stylize,
     text::Line,
     widgets::{Block, Gauge, List},
     Frame,
 };
 
+use common::{GameMode, LobbyStatus, RoundState};
+
 use crate::{
     config::Config,
-    schema::{encryption::Encryption, lobby::Lobby},
+    schema::{
+        encryption::Encryption,
+        lobby::{lobby_details, Lobby},
+    },
     ui::get_random_symbol,
 };
 
@@ -31,13 +38,45 @@ pub fn draw_lobby(f: &mut Frame, area: Rect, config: &Config, lobby: &mut Lobby)
         common::LobbyStatus::Finish(time) => Some(time),
     };
 
-    let title = lobby.name.as_str();
+    let title = match (lobby.game_mode, &lobby.warmup) {
+        (GameMode::Race, Some(warmup)) if lobby.is_warmup_round => {
+            format!("{} (Warmup: {})", lobby.name, warmup.name)
+        }
+        (GameMode::Race, _) => format!(
+            "{} ({}: {})",
+            lobby.name, lobby.game_mode, lobby.challenge.name
+        ),
+        (GameMode::TimeAttack, _) => format!("{} ({})", lobby.name, lobby.game_mode),
+    };
     let mut block = Block::bordered()
         .title(title)
         .title_bottom(lobby.status.to_string());
+    if lobby.round_number > 0 {
+        block = block.title_bottom(format!("Round {}", lobby.round_number));
+    }
+    if let Some(details) = lobby_details(lobby.description.as_deref(), &lobby.tags) {
+        block = block.title(Line::from(details).fg(config.palette().muted).centered());
+    }
+
+    // Show the time to beat. Records are only tracked for races.
+    if lobby.game_mode == GameMode::Race {
+        let record = match lobby.record {
+            Some(ref record) => format!(
+                "Record: {:.1}s by {}",
+                record.best_time.as_secs_f64(),
+                record.holder_name
+            ),
+            None => "No record yet".to_string(),
+        };
+        block = block.title(Line::from(record).right_aligned());
+    }
 
     if let Some(time) = time {
-        let now = Utc::now();
+        // The countdown stands still while the round is paused.
+        let paused_for = lobby
+            .round_paused_at
+            .map_or(Duration::ZERO, |paused_at| paused_at.elapsed());
+        let now = lobby.server_now() - paused_for;
         let remaining_millis = time.signed_duration_since(now).num_milliseconds();
         let seconds_with_millis = remaining_millis as f64 / 1000.0;
         let text = format!("{:.1}s", seconds_with_millis);
@@ -66,7 +105,29 @@ pub fn draw_lobby(f: &mut Frame, area: Rect, config: &Config, lobby: &mut Lobby)
             .enumerate()
             .map(|(i, c)| if i < *index { c } else { get_random_symbol() })
             .collect::<String>();
-        let mut gauge = Gauge::default().block(Block::bordered().title(encryption));
+        let mut title = encryption;
+        if let Some(player) = lobby.players.get(player_id) {
+            // Show the score next to the player name in time attack rounds.
+            if lobby.game_mode == GameMode::TimeAttack {
+                title.push_str(&format!(" [{}]", player.score));
+            }
+            if player.ready && lobby.status == LobbyStatus::WaitingForPlayers {
+                title.push_str(" (ready)");
+            }
+            if player.muted {
+                title.push_str(" (muted)");
+            }
+        }
+        // Tell who is still working on the challenge, and who did not finish
+        // it after the round.
+        if let (LobbyStatus::InProgress(_) | LobbyStatus::Finish(_), Some(state)) =
+            (&lobby.status, lobby.round_roster.get(player_id))
+        {
+            if *state != RoundState::Editing {
+                title.push_str(&format!(" ({state})"));
+            }
+        }
+        let mut gauge = Gauge::default().block(Block::bordered().title(title));
         if let Some(player) = lobby.players.get(player_id) {
             gauge = gauge.ratio(player.progress);
         };
@@ -97,10 +158,19 @@ pub fn draw_lobby(f: &mut Frame, area: Rect, config: &Config, lobby: &mut Lobby)
 }
 
 fn draw_lobby_commands(f: &mut Frame, config: &Config, area: Rect, lobby: &Lobby) {
-    let mut commands = vec![format!(
-        "{} - Disconnect from the lobby",
-        config.key_bindings.lobby.disconnect
-    )];
+    let mut commands = vec![
+        format!(
+            "{} - Disconnect from the lobby",
+            config.key_bindings.lobby.disconnect
+        ),
+        "/help - List chat commands".to_string(),
+    ];
+    if lobby.status == LobbyStatus::WaitingForPlayers {
+        commands.push(format!(
+            "{} - Toggle your ready state",
+            config.key_bindings.lobby.toggle_ready
+        ));
+    }
 
     // Add lobby owner specific commands depending on the lobby status.
     if lobby.local_player == lobby.owner && lobby.local_player.is_some() {
@@ -110,6 +180,19 @@ fn draw_lobby_commands(f: &mut Frame, config: &Config, area: Rect, lobby: &Lobby
                     "{} - Start the lobby",
                     config.key_bindings.lobby.start
                 ));
+                commands.push(format!(
+                    "{} - Switch to {}",
+                    config.key_bindings.lobby.toggle_game_mode,
+                    lobby.game_mode.next()
+                ));
+                let bot_command = match lobby.next_bot_difficulty() {
+                    Some(difficulty) => format!("Race against a bot ({difficulty})"),
+                    None => "Remove the bot".to_string(),
+                };
+                commands.push(format!(
+                    "{} - {}",
+                    config.key_bindings.lobby.toggle_bot, bot_command
+                ));
             }
             common::LobbyStatus::AboutToStart(_) => {}
             common::LobbyStatus::InProgress(_) => {}
@@ -0,0 +1,14 @@
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+/// # Draw winning solution
+///
+/// Shows the solution the winner of the last race submitted.
+pub fn draw_winning_solution(f: &mut Frame, area: Rect, winner: &str, content: &[u8]) {
+    let block = Block::bordered().title(format!("Winning solution by {winner}"));
+    let content = String::from_utf8_lossy(content).replace('\t', "    ");
+    f.render_widget(Paragraph::new(content).block(block), area);
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Row, Table},
+    Frame,
+};
+
+use common::{GameMode, RoundSummary};
+
+use crate::{config::Config, schema::lobby::Lobby};
+
+/// # Draw round summary
+///
+/// Shows the scoreboard of the last round in place of the player list. Race
+/// results show the finish time, time attack results the score.
+pub fn draw_round_summary(
+    f: &mut Frame,
+    area: Rect,
+    config: &Config,
+    lobby: &Lobby,
+    summary: &RoundSummary,
+    reset_date: DateTime<Utc>,
+) {
+    let challenge = [Some(&lobby.challenge), lobby.warmup.as_ref()]
+        .into_iter()
+        .flatten()
+        .find(|challenge| challenge.id == summary.challenge_id)
+        .map_or(summary.challenge_id.as_str(), |challenge| {
+            challenge.name.as_str()
+        });
+    let title = if lobby.is_warmup_round {
+        format!("Warmup results ({challenge})")
+    } else {
+        format!("Round {} results ({challenge})", summary.round_number)
+    };
+    let remaining_millis = reset_date
+        .signed_duration_since(lobby.server_now())
+        .num_milliseconds();
+    let block = Block::bordered().title(title).title_bottom(
+        Line::from(format!("{:.1}s", remaining_millis as f64 / 1000.0)).right_aligned(),
+    );
+
+    let rows = summary.results.iter().map(|result| {
+        let rank = result
+            .rank
+            .map_or("-".to_string(), |rank| format!("{rank}."));
+        let outcome = match (lobby.game_mode, result.finish_time) {
+            (GameMode::TimeAttack, _) => format!("{} solved", result.score),
+            (GameMode::Race, Some(finish_time)) => format!("{:.1}s", finish_time.as_secs_f64()),
+            (GameMode::Race, None) => "DNF".to_string(),
+        };
+        let row = Row::new(vec![rank, result.name.clone(), outcome]);
+        if lobby.local_player == Some(result.player_id) {
+            row.add_modifier(Modifier::BOLD)
+        } else if result.rank.is_none() {
+            row.fg(config.palette().muted)
+        } else {
+            row
+        }
+    });
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Min(0),
+        Constraint::Length(10),
+    ];
+    let table = Table::new(rows, widths).column_spacing(1).header(
+        Row::new(vec!["#", "Player", "Result"])
+            .style(Style::new().bold())
+            .bottom_margin(1),
+    );
+    f.render_widget(table.block(block), area);
+}
@@ -6,7 +6,8 @@ use syn::{parse_macro_input, Data, DeriveInput, Fields};
 ///
 /// Implements the `has_duplicates` method for given struct. The method iterates
 /// over all fields of the struct, inserts the values into a temporary `HashSet`
-/// and checks for duplicate values.
+/// and checks for duplicate values. Additionally, implements the
+/// `key_bindings` method listing all fields together with their names.
 ///
 /// By using this macro we can extend a struct without having to worry about adding
 /// the new field to the duplicate check.
@@ -33,6 +34,13 @@ pub fn derive_check_duplicates(input: TokenStream) -> TokenStream {
                 }
             });
 
+            let field_entries = fields.iter().map(|field| {
+                let field_name = &field.ident;
+                quote! {
+                    (stringify!(#field_name), &self.#field_name)
+                }
+            });
+
             quote! {
                 impl #name {
                     fn has_duplicates(&self) -> bool {
@@ -40,6 +48,10 @@ pub fn derive_check_duplicates(input: TokenStream) -> TokenStream {
                         #(#field_checks)*
                         false
                     }
+
+                    fn key_bindings(&self) -> Vec<(&'static str, &KeyBinding)> {
+                        vec![#(#field_entries),*]
+                    }
                 }
             }
         }
@@ -1,2 +1,7 @@
 pub static MAX_LOBBY_SIZE: usize = 4;
 pub static MAX_CHAT_MESSAGE_LENGTH: usize = 50;
+pub static MAX_PLAYER_NAME_LENGTH: usize = 32;
+pub static MAX_LOBBY_DESCRIPTION_LENGTH: usize = 80;
+pub static MAX_LOBBY_TAGS: usize = 3;
+pub static MAX_REPLAY_FRAMES: usize = 20_000;
+pub static MAX_REPLAY_BYTES: usize = 100_000;
@@ -1,4 +1,8 @@
-use std::{collections::BTreeMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+    time::Duration,
+};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -8,17 +12,108 @@ use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 
 pub mod constants;
+pub mod normalize;
+#[cfg(feature = "client")]
+pub mod trace;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ClientMessage {
-    Progress { progress: Vec<u8> },
-    SendMessage { message: String },
+    Progress {
+        progress: Vec<u8>,
+    },
+    SendMessage {
+        message: String,
+    },
     RequestStart,
+    SetGameMode {
+        game_mode: GameMode,
+    },
+    ToggleReady,
+    EditorReady,
+    Whisper {
+        name: String,
+        message: String,
+    },
+    KickPlayer {
+        name: String,
+        reason: String,
+    },
+    SetLobbyGoal {
+        challenge_id: String,
+    },
+    SetWarmup {
+        challenge_id: Option<String>,
+    },
+    SetBot {
+        difficulty: Option<Difficulty>,
+    },
+    ListLobbies(LobbyFilter),
+    SubmitReplay {
+        frames: Vec<ReplayFrame>,
+    },
+    RequestReplay {
+        name: String,
+    },
+    SpectatePlayer {
+        lobby_id: Uuid,
+        target_id: Uuid,
+    },
+    LeaveLobby,
+    SetPlayerMuted {
+        target_id: Uuid,
+        muted: bool,
+    },
+    /// Votes for removing the target player from the lobby.
+    VoteKick {
+        target_id: Uuid,
+    },
+    RenamePlayer {
+        name: String,
+    },
+    SetMaxSpectators {
+        max_spectators: usize,
+    },
+    SetSpectatorsAllowed {
+        allowed: bool,
+    },
+    SetGoalPreview {
+        enabled: bool,
+    },
+    SetSolutionReveal {
+        enabled: bool,
+    },
+    WhoAmI,
+    RequestRoster {
+        lobby_id: Uuid,
+    },
+    ListChallenges,
+    PauseRound,
+    ResumeRound,
+    SetLobbyDescription {
+        description: Option<String>,
+    },
+    SetLobbyTags {
+        tags: BTreeSet<LobbyTag>,
+    },
+    /// Overrides how player files are compared against the goal file. `None`
+    /// falls back to the comparison of the challenge.
+    SetComparison {
+        comparison: Option<Comparison>,
+    },
+    SetMaxPlayers {
+        max_players: usize,
+    },
+    /// Limits races to the given amount of seconds. `None` falls back to the
+    /// time limit of the challenge.
+    SetTimeLimit {
+        seconds: Option<u64>,
+    },
 }
 
 #[cfg(feature = "client")]
 impl From<ClientMessage> for Message {
     fn from(value: ClientMessage) -> Self {
+        trace::record("sent", &value);
         let text = serde_json::to_string(&value).expect("Converting message to JSON");
         Message::text(text)
     }
@@ -27,13 +122,17 @@ impl From<ClientMessage> for Message {
 #[cfg(feature = "client")]
 impl From<Message> for BackendMessage {
     fn from(value: Message) -> Self {
-        match value {
+        let message = match value {
             Message::Text(msg) => serde_json::from_str(&msg).unwrap(),
-            Message::Close(_) => Self::CloseConnection,
+            Message::Close(frame) => Self::CloseConnection {
+                reason: frame.and_then(|frame| CloseReason::from_code(frame.code.into())),
+            },
             Message::Binary(_) | Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {
                 Self::Unknown
             }
-        }
+        };
+        trace::record("received", &message);
+        message
     }
 }
 
@@ -43,13 +142,235 @@ pub struct Player {
     pub name: String,
     pub progress: f64,
     pub waiting: bool,
+    pub ready: bool,
+    /// Amount of challenges the player completed in the current time attack
+    /// round.
+    pub score: usize,
+    /// The difficulty of a bot opponent. `None` for real players.
+    pub bot: Option<Difficulty>,
+    /// Whether the lobby owner muted the player in the chat.
+    pub muted: bool,
+    /// The time the player joined the lobby. Missing for bots.
+    #[serde(default)]
+    pub joined_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LobbyListItem {
     pub name: String,
     pub player_count: usize,
+    /// The amount of seats of the lobby.
+    pub max_players: usize,
     pub status: LobbyStatus,
+    pub language: String,
+    pub difficulty: Difficulty,
+    /// Whether clients may watch the players of the lobby.
+    pub spectators_allowed: bool,
+    /// What the host describes the lobby with, if anything.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: BTreeSet<LobbyTag>,
+}
+
+impl LobbyListItem {
+    /// # Is joinable
+    ///
+    /// Whether players can join the lobby right now, i.e., it has free seats
+    /// and waits for players.
+    pub fn is_joinable(&self) -> bool {
+        self.player_count < self.max_players && self.status == LobbyStatus::WaitingForPlayers
+    }
+}
+
+/// Announces that a lobby became full or has free seats again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LobbyAvailability {
+    pub lobby_id: Uuid,
+    pub is_full: bool,
+    pub player_count: usize,
+    pub max_players: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Display, PartialEq, Eq)]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = ParseDifficultyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(ParseDifficultyError),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseDifficultyError;
+
+impl std::fmt::Display for ParseDifficultyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected easy, medium or hard")
+    }
+}
+
+/// How the file of a player is compared against the goal file.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Display, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Comparison {
+    /// Every character counts.
+    Exact,
+    /// Every run of whitespace counts as a single space, e.g., for challenges
+    /// where indentation does not matter.
+    #[default]
+    IgnoreWhitespace,
+    /// Whitespace outside of string literals only counts where it separates
+    /// two words. Falls back to `IgnoreWhitespace` for languages without
+    /// known string literals.
+    Tokens,
+}
+
+impl FromStr for Comparison {
+    type Err = ParseComparisonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exact" => Ok(Comparison::Exact),
+            "ignore-whitespace" | "whitespace" => Ok(Comparison::IgnoreWhitespace),
+            "tokens" => Ok(Comparison::Tokens),
+            _ => Err(ParseComparisonError),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseComparisonError;
+
+impl std::fmt::Display for ParseComparisonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected exact, ignore-whitespace or tokens")
+    }
+}
+
+/// Categories hosts tag their lobby with, so that players find lobbies that
+/// suit them.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, PartialEq, Eq, PartialOrd, Ord)]
+#[strum(serialize_all = "lowercase")]
+pub enum LobbyTag {
+    Ranked,
+    Casual,
+    Teaching,
+    Practice,
+}
+
+impl FromStr for LobbyTag {
+    type Err = ParseLobbyTagsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ranked" => Ok(LobbyTag::Ranked),
+            "casual" => Ok(LobbyTag::Casual),
+            "teaching" => Ok(LobbyTag::Teaching),
+            "practice" => Ok(LobbyTag::Practice),
+            _ => Err(ParseLobbyTagsError::Unknown(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseLobbyTagsError {
+    Unknown(String),
+    TooMany,
+}
+
+impl std::fmt::Display for ParseLobbyTagsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseLobbyTagsError::Unknown(tag) => write!(
+                f,
+                "unknown tag {tag}, expected ranked, casual, teaching or practice"
+            ),
+            ParseLobbyTagsError::TooMany => {
+                write!(
+                    f,
+                    "lobbies can have at most {} tags",
+                    constants::MAX_LOBBY_TAGS
+                )
+            }
+        }
+    }
+}
+
+/// # Parse lobby tags
+///
+/// Parses a list of tags separated by commas or whitespace. Fails on unknown
+/// tags and on more than `MAX_LOBBY_TAGS` distinct ones.
+pub fn parse_lobby_tags(input: &str) -> Result<BTreeSet<LobbyTag>, ParseLobbyTagsError> {
+    let tags = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(str::parse)
+        .collect::<Result<BTreeSet<_>, _>>()?;
+    if tags.len() > constants::MAX_LOBBY_TAGS {
+        return Err(ParseLobbyTagsError::TooMany);
+    }
+    Ok(tags)
+}
+
+/// Criteria a lobby has to match to show up in the lobby list. Fields that are
+/// `None` match every lobby.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LobbyFilter {
+    /// Case-insensitive substring of the lobby name.
+    pub name_contains: Option<String>,
+    /// Exact language of the lobby challenge, ignoring case.
+    pub language: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    /// Only matches lobbies tagged with this tag.
+    #[serde(default)]
+    pub tag: Option<LobbyTag>,
+    /// Only matches lobbies players can join right now.
+    #[serde(default)]
+    pub joinable_only: bool,
+}
+
+impl LobbyFilter {
+    /// # Joinable lobbies
+    ///
+    /// Matches every lobby players can join right now. This is what the lobby
+    /// browser shows by default.
+    pub fn joinable() -> Self {
+        Self {
+            joinable_only: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn matches(&self, lobby: &LobbyListItem) -> bool {
+        let name_matches = self
+            .name_contains
+            .as_ref()
+            .is_none_or(|name| lobby.name.to_lowercase().contains(&name.to_lowercase()));
+        let language_matches = self
+            .language
+            .as_ref()
+            .is_none_or(|language| lobby.language.eq_ignore_ascii_case(language));
+        let difficulty_matches = self
+            .difficulty
+            .is_none_or(|difficulty| lobby.difficulty == difficulty);
+        let tag_matches = self.tag.is_none_or(|tag| lobby.tags.contains(&tag));
+        let joinable_matches = !self.joinable_only || lobby.is_joinable();
+        name_matches && language_matches && difficulty_matches && tag_matches && joinable_matches
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Display, PartialEq, Eq)]
@@ -67,17 +388,166 @@ pub enum LobbyStatus {
     Finish(DateTime<Utc>),
 }
 
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Display, PartialEq, Eq)]
+pub enum GameMode {
+    /// The first player reaching the goal shortens the round for everyone
+    /// else.
+    #[default]
+    #[strum(to_string = "Race")]
+    Race,
+    /// Players complete as many small challenges as possible until a shared
+    /// timer expires. The player with the highest score wins.
+    #[strum(to_string = "Time attack")]
+    TimeAttack,
+}
+
+impl GameMode {
+    pub fn next(&self) -> Self {
+        match self {
+            GameMode::Race => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Race,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LobbyInformation {
     pub id: Uuid,
     pub name: String,
+    /// What the host describes the lobby with, if anything.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: BTreeSet<LobbyTag>,
     pub status: LobbyStatus,
+    pub game_mode: GameMode,
     pub owner: Option<Uuid>,
     pub players: BTreeMap<Uuid, Player>,
+    /// The amount of seats of the lobby.
+    pub max_players: usize,
+    pub challenge: ChallengeInformation,
     pub challenge_files: ChallengeFiles,
+    /// Whether players see the goal file. The goal file of
+    /// `challenge_files` is empty otherwise.
+    pub goal_preview: bool,
+    /// Whether the winning solution is shown to everyone after a race.
+    #[serde(default)]
+    pub solution_reveal: bool,
+    /// The warmup challenge played once before the scored rounds, if any.
+    pub warmup: Option<ChallengeInformation>,
+    /// The number of the current or last scored round of the series. Zero
+    /// before the first round.
+    pub round_number: usize,
+    /// How quickplay picked this lobby. `None` for other join modes.
+    #[serde(default)]
+    pub quickplay: Option<QuickplayOutcome>,
+}
+
+/// Tells a quickplaying player whether an existing lobby was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuickplayOutcome {
+    /// The player joins a lobby that already existed.
+    Joined,
+    /// No lobby had a free seat, so a new one was created.
+    Created,
+}
+
+/// What a player is doing in the current round.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+pub enum RoundState {
+    Editing,
+    Finished,
+    /// Knocked out of the series. No game mode eliminates players yet.
+    Eliminated,
+    /// Lost the connection, the seat is held for a reconnect.
+    Disconnected,
+    /// Still editing when the race ended.
+    #[strum(to_string = "dnf")]
+    DidNotFinish,
+}
+
+/// The round state of a single player of the lobby roster.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerRoundState {
+    pub player_id: Uuid,
+    pub state: RoundState,
+}
+
+/// A lobby member as listed in a full roster.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub player: Player,
+    /// What the player is doing in the current round. `None` outside of
+    /// rounds and for waiting players.
+    pub round_state: Option<RoundState>,
+}
+
+/// The identity the backend tracks for a connection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    /// ID of a lobby browser or spectator connection.
+    pub client_id: Option<Uuid>,
+    pub player_id: Option<Uuid>,
+    /// The lobby the player plays in or the client spectates.
+    pub lobby_id: Option<Uuid>,
+    /// Whether the player owns the lobby.
+    pub is_host: bool,
+}
+
+/// Announces the new player count of a lobby after a player joined or left.
+/// Much smaller than resending the whole lobby list item.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LobbyPlayerCount {
+    pub lobby_id: Uuid,
+    pub player_count: usize,
+    pub max_players: usize,
+}
+
+/// Announces how many clients watch a lobby.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpectatorCount {
+    pub lobby_id: Uuid,
+    pub spectators: usize,
+    pub max_spectators: usize,
+    pub spectators_allowed: bool,
+}
+
+/// A chat line sent by the backend.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub text: String,
+    /// The time the backend sent the message, used to order and display chat
+    /// lines.
+    #[serde(default)]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl ChatMessage {
+    /// # Create a new chat message
+    ///
+    /// Creates a chat message stamped with the current time.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            sent_at: Some(Utc::now()),
+        }
+    }
 }
 
+/// Describes a challenge without its files.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChallengeInformation {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+    pub difficulty: Difficulty,
+    /// Short summary of the challenge, if its manifest has one.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChallengeFiles {
     /// File all players start from.
     pub start_file: Vec<u8>,
@@ -85,6 +555,45 @@ pub struct ChallengeFiles {
     pub goal_file: Vec<u8>,
 }
 
+/// How a single player did in a finished round.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub player_id: Uuid,
+    pub name: String,
+    /// Time from the round start to reaching the goal of a race. `None` if
+    /// the player did not finish.
+    pub finish_time: Option<Duration>,
+    /// Place of the player. Race ranks only count finishers.
+    pub rank: Option<usize>,
+    /// Completed challenges of a time attack round.
+    pub score: usize,
+}
+
+/// Results of every player once a round ended.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundSummary {
+    pub challenge_id: String,
+    pub round_number: usize,
+    /// Ordered by rank, players without a rank last.
+    pub results: Vec<RoundResult>,
+}
+
+/// The fastest recorded solve time of a challenge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub challenge_id: String,
+    pub best_time: Duration,
+    pub holder_name: String,
+}
+
+/// A single input sent to the editor during a round.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// Time since the start of the round.
+    pub elapsed: Duration,
+    pub bytes: Vec<u8>,
+}
+
 #[derive(Debug, Display)]
 #[strum(serialize_all = "snake_case")]
 pub enum JoinMode {
@@ -123,22 +632,262 @@ impl FromStr for JoinMode {
 pub enum BackendMessage {
     CurrentLobbies(BTreeMap<Uuid, LobbyListItem>),
     AddLobby(Uuid, LobbyListItem),
-    UpdateLobbyPlayerCount { id: Uuid, player_count: usize },
-    UpdateLobbyStatus { id: Uuid, status: LobbyStatus },
+    UpdateLobbyPlayerCount(LobbyPlayerCount),
+    UpdateLobbyStatus {
+        id: Uuid,
+        status: LobbyStatus,
+    },
+    LobbyAvailabilityChanged(LobbyAvailability),
     RemoveLobby(Uuid),
     LobbyFull,
     LobbyNotWaitingForPlayers,
-    ConnectionCounts { clients: usize, players: usize },
+    RoundStarting,
+    /// The join was rejected as a round is in progress. Tells whether the
+    /// client of the player watches the round instead.
+    RoundInProgress {
+        spectating: bool,
+    },
+    AlreadyInLobby,
+    /// The join did not name a connected client, see `ProvideClientId`.
+    UnknownClient,
+    ConnectionCounts {
+        clients: usize,
+        players: usize,
+    },
+    SpectatorFrame {
+        player_id: Uuid,
+        file: Vec<u8>,
+    },
+    SpectateTargetLeft {
+        player_id: Uuid,
+    },
+    SpectatorsFull,
+    /// Secret that lets the client resume watching after a disconnect.
+    ProvideSpectatorToken {
+        token: Uuid,
+    },
+    /// The owner of the lobby does not allow spectators, or stopped allowing
+    /// them while the client was watching.
+    SpectatorsDisabled,
+    SpectatorCountChanged(SpectatorCount),
 
-    SendLobbyInformation(LobbyInformation),
-    ProvidePlayerId { id: Uuid },
-    AssignOwner { id: Uuid },
+    SendLobbyInformation(Box<LobbyInformation>),
+    ProvidePlayerId {
+        id: Uuid,
+    },
+    /// Tells a lobby browser connection its ID. Clients pass it on when
+    /// joining, so that the backend keeps them in a single lobby. Joins
+    /// without the ID of a connected client are rejected.
+    ProvideClientId {
+        id: Uuid,
+    },
+    /// Answers `ClientMessage::WhoAmI`.
+    SessionInfo(SessionInfo),
+    /// Every current member of the lobby, sent on request to resynchronize.
+    Roster(Vec<PlayerInfo>),
+    /// Answers `ClientMessage::ListChallenges` with the whole catalog, leaving
+    /// out the challenge files.
+    Challenges(Vec<ChallengeInformation>),
+    /// Secret that lets the player take back the seat after a disconnect.
+    ProvideReconnectToken {
+        token: Uuid,
+    },
+    AssignOwner {
+        id: Uuid,
+    },
     AddPlayer(Player),
     RemovePlayer(Uuid),
-    StatusUpdate { status: LobbyStatus },
-    UpdatePlayerProgress { player_id: Uuid, progress: f64 },
+    StatusUpdate {
+        status: LobbyStatus,
+    },
+    UpdatePlayerProgress {
+        player_id: Uuid,
+        progress: f64,
+    },
+    GameModeUpdate {
+        game_mode: GameMode,
+    },
+    ProvideChallengeFiles(ChallengeFiles),
+    ChallengeChanged(ChallengeInformation),
+    WarmupChanged(Option<ChallengeInformation>),
+    RoundStarted {
+        warmup: bool,
+        round_number: usize,
+        /// How files are compared against the goal file in this round.
+        #[serde(default)]
+        comparison: Comparison,
+    },
+    RoundSummary(RoundSummary),
+    GoalPreviewChanged {
+        enabled: bool,
+    },
+    GoalPreview {
+        content: Option<Vec<u8>>,
+    },
+    /// The first player finished the race. The round ends in the given
+    /// amount of seconds.
+    RoundEndingSoon {
+        seconds: u64,
+    },
+    /// The lobby owner froze the round timer.
+    RoundPaused,
+    /// The round timer runs again. The new round end follows as status
+    /// update.
+    RoundResumed,
+    /// Goal of the race the player just finished, sent once the round is
+    /// over to compare the own solution against.
+    RevealGoal {
+        content: Vec<u8>,
+    },
+    LobbyDescriptionChanged {
+        description: Option<String>,
+    },
+    LobbyTagsChanged {
+        tags: BTreeSet<LobbyTag>,
+    },
+    /// The host changed the comparison override. `None` means the comparison
+    /// of the challenge applies again.
+    ComparisonChanged {
+        comparison: Option<Comparison>,
+    },
+    SolutionRevealChanged {
+        enabled: bool,
+    },
+    /// The solution of the race winner, sent once the round is over.
+    WinningSolution {
+        player_id: Uuid,
+        content: Vec<u8>,
+    },
+    UpdatePlayerScore {
+        player_id: Uuid,
+        score: usize,
+    },
+    FinalScores(BTreeMap<Uuid, usize>),
+    PlayerReady {
+        player_id: Uuid,
+        ready: bool,
+    },
+    PlayerMuted {
+        player_id: Uuid,
+        muted: bool,
+    },
+    PlayerRenamed {
+        player_id: Uuid,
+        new_name: String,
+    },
+    YouAreMuted,
+    MessageFiltered,
+    ReadyTimeout,
+    RoundRoster(Vec<PlayerRoundState>),
+    Kicked {
+        reason: String,
+    },
+    /// The time of the backend in milliseconds since the Unix epoch, sent
+    /// periodically for the clock sync of clients.
+    ServerTime {
+        unix_millis: i64,
+    },
+    /// Sends the player back to the lobby browser, e.g., after an operator
+    /// closed the lobby.
+    ReturnToBrowser {
+        reason: String,
+    },
+    PlayerKicked {
+        player_id: Uuid,
+        reason: String,
+    },
+    /// Tally of the votes to kick the target player. The target is kicked
+    /// once `votes` reaches `needed`.
+    VoteKickProgress {
+        target_id: Uuid,
+        votes: usize,
+        needed: usize,
+    },
+    ChallengeRecord(Record),
+    NewRecord(Record),
+    Replay {
+        frames: Vec<ReplayFrame>,
+    },
 
-    SendMessage(String),
-    CloseConnection,
+    /// The backend is shutting down and closes all connections shortly.
+    ServerShutdown,
+
+    SendMessage(ChatMessage),
+    /// Closes the WebSocket connection with the close code of the reason.
+    /// Connections closed without a known code have no reason.
+    CloseConnection {
+        reason: Option<CloseReason>,
+    },
     Unknown,
 }
+
+/// Why the backend closed a WebSocket connection. Each reason is sent as
+/// close code from the range reserved for applications:
+///
+/// | Code | Reason          | Reconnect |
+/// |------|-----------------|-----------|
+/// | 4000 | `Shutdown`      | yes       |
+/// | 4001 | `Kicked`        | no        |
+/// | 4002 | `ProtocolError` | no        |
+/// | 4003 | `IdleTimeout`   | no        |
+/// | 4004 | `Superseded`    | no        |
+/// | 4005 | `LobbyClosed`   | no        |
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The backend is shutting down.
+    Shutdown,
+    /// The player was kicked from the lobby, by the owner or a vote.
+    Kicked,
+    /// The connection sent something the backend could not read, e.g., an
+    /// oversized message.
+    ProtocolError,
+    /// The lobby browser client stayed idle for too long.
+    IdleTimeout,
+    /// The player resumed the seat through another connection.
+    Superseded,
+    /// An operator closed the lobby.
+    LobbyClosed,
+}
+
+impl CloseReason {
+    pub fn code(self) -> u16 {
+        match self {
+            CloseReason::Shutdown => 4000,
+            CloseReason::Kicked => 4001,
+            CloseReason::ProtocolError => 4002,
+            CloseReason::IdleTimeout => 4003,
+            CloseReason::Superseded => 4004,
+            CloseReason::LobbyClosed => 4005,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            4000 => Some(CloseReason::Shutdown),
+            4001 => Some(CloseReason::Kicked),
+            4002 => Some(CloseReason::ProtocolError),
+            4003 => Some(CloseReason::IdleTimeout),
+            4004 => Some(CloseReason::Superseded),
+            4005 => Some(CloseReason::LobbyClosed),
+            _ => None,
+        }
+    }
+
+    /// Whether the client should connect again, as the backend is expected
+    /// back soon.
+    pub fn should_reconnect(self) -> bool {
+        matches!(self, CloseReason::Shutdown)
+    }
+
+    /// Text sent along with the close code.
+    pub fn description(self) -> &'static str {
+        match self {
+            CloseReason::Shutdown => "The server is shutting down",
+            CloseReason::Kicked => "You were kicked from the lobby",
+            CloseReason::ProtocolError => "The server could not read a message",
+            CloseReason::IdleTimeout => "Disconnected after being idle for too long",
+            CloseReason::Superseded => "Your seat was taken over by another connection",
+            CloseReason::LobbyClosed => "The lobby was closed",
+        }
+    }
+}
@@ -0,0 +1,24 @@
+/// # Normalize line endings
+///
+/// Converts all CRLF (`\r\n`) and lone CR (`\r`) line endings to LF (`\n`).
+///
+/// Challenge files authored on Windows may contain CRLF line endings. Editors
+/// differ in whether they keep or normalize them on save, which would make
+/// goal comparisons fail even though the buffer looks identical. Therefore,
+/// files are normalized before they are handed to an editor and both sides
+/// are normalized before being compared.
+pub fn normalize_line_endings(file: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(file.len());
+    let mut bytes = file.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'\r' {
+            // Drop the carriage return of a CRLF pair, convert lone ones.
+            if bytes.peek() != Some(&&b'\n') {
+                normalized.push(b'\n');
+            }
+        } else {
+            normalized.push(byte);
+        }
+    }
+    normalized
+}
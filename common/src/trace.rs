@@ -0,0 +1,77 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Fields holding secrets, e.g., reconnect tokens. Their values never end up
+/// in the trace.
+static REDACTED_FIELDS: [&str; 1] = ["token"];
+
+static TRACE: OnceLock<Mutex<BufWriter<File>>> = OnceLock::new();
+
+/// # Enable protocol trace
+///
+/// Writes every message converted from or to a websocket message into the
+/// given file from now on. Fails if the file can not be created or the trace
+/// was already enabled.
+pub fn enable(path: &Path) -> std::io::Result<()> {
+    let file = File::create(path)?;
+    TRACE
+        .set(Mutex::new(BufWriter::new(file)))
+        .map_err(|_| std::io::Error::other("Protocol trace is already enabled."))
+}
+
+/// # Record message
+///
+/// Appends the pretty-printed message to the trace, if enabled. Redacted
+/// fields are replaced at any depth and file contents are shown as text
+/// instead of byte arrays.
+pub(crate) fn record(direction: &str, message: &impl Serialize) {
+    let Some(trace) = TRACE.get() else {
+        return;
+    };
+    let Ok(mut message) = serde_json::to_value(message) else {
+        return;
+    };
+    simplify(&mut message);
+    let Ok(message) = serde_json::to_string_pretty(&message) else {
+        return;
+    };
+    if let Ok(mut trace) = trace.lock() {
+        let _ = writeln!(trace, "{} {direction}\n{message}", Utc::now().to_rfc3339());
+        let _ = trace.flush();
+    }
+}
+
+fn simplify(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if REDACTED_FIELDS.contains(&name.as_str()) {
+                    *field = Value::String("<redacted>".to_string());
+                } else {
+                    simplify(field);
+                }
+            }
+        }
+        Value::Array(values) => {
+            let bytes = values
+                .iter()
+                .map(|value| value.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect::<Option<Vec<_>>>();
+            match bytes {
+                Some(bytes) if !bytes.is_empty() => {
+                    *value = Value::String(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                _ => values.iter_mut().for_each(simplify),
+            }
+        }
+        _ => {}
+    }
+}